@@ -7,19 +7,27 @@ use crate::App;
 use base64::{engine::general_purpose::STANDARD, Engine as _};
 use image::{DynamicImage, GenericImageView};
 use log::debug;
-use optimize::find::{AbsoluteCost, MapStorage, MapTrait, PathFinder, PathFinderState, Visited};
+use optimize::color::ColorRamp;
+use optimize::find::{
+    are_connected, AbsoluteCost, MapStorage, MapTrait, PathFinder, PathFinderState, Visited,
+};
 use optimize::grid::{Cell, Direction, GridMap, Point};
-use optimize::util::parse_img;
+use optimize::util::{fill_map_from_image, parse_img, rasterize_svg};
 use std::io::Cursor;
 use wasm_bindgen::Clamped;
 use wasm_bindgen::JsCast;
-use web_sys::{CanvasRenderingContext2d, ImageBitmap};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlElement, ImageBitmap};
 use web_sys::{HtmlInputElement, ImageData};
 
+use self::ui::brush::brush_cells;
 use self::ui::camera::Camera;
+use self::ui::curve::{bend_control_point, quadratic_bezier};
+use self::ui::export::{image_file_name, parse_data_url};
+use self::ui::tiles::{tile_rects, TileRect, MAX_TILE_SIZE};
 
 const STORAGE_KEY_MAP: &str = "map";
 const STORAGE_KEY_BACKGROUND: &str = "background";
+const STORAGE_KEY_POINTS: &str = "points";
 
 pub(crate) trait AppMapTrait:
     MapTrait + serde::Serialize + for<'de> serde::Deserialize<'de>
@@ -49,6 +57,19 @@ where
     start: Option<M::Reference>,
     goal: Option<M::Reference>,
     auto_step: bool,
+    steps_per_frame: usize,
+    /// the time to wait between auto steps, in milliseconds
+    frame_delay: u32,
+    /// the costs at the left and right edge of a gradient fill
+    gradient_costs: (usize, usize),
+    /// the size of the brush used for painting cells, 0 to select rectangles instead
+    brush_size: usize,
+    /// if the main mouse button is held down while painting with the brush
+    painting: bool,
+    /// the number of samples per cell side when creating a map from the background
+    sample_density: usize,
+    color_tolerance: f64,
+    matching_is_valid: bool,
     edit_selection: Option<Selection<M::Reference>>,
 
     // stuff for selecting rectangles
@@ -68,6 +89,12 @@ where
 
     draw_grid: bool,
     draw_pathfind_debug: bool,
+    /// dim the visited cells that are not on the path once a path is found
+    highlight_path_only: bool,
+    /// whether to draw the nodes that are reached but not settled yet
+    draw_frontier: bool,
+    draw_legend: bool,
+    color_ramp: ColorRamp,
 }
 
 struct Selection<R> {
@@ -81,7 +108,37 @@ where
     K: Ord,
     <M as MapTrait>::Cost: AbsoluteCost<CmpContext = K>,
 {
+    #[allow(clippy::type_complexity)]
     pathfinder: PathFinder<M::Reference, K, M::Cost, M::Storage<Visited<M::Cost, M::Reference>>, M>,
+    /// whether the goal can be reached from the start at all
+    connected: bool,
+    /// the number of nodes the search can visit, for reporting progress
+    total_nodes: usize,
+}
+
+impl FindState<GridMap<usize>, CmpCtx> {
+    fn new(map: &GridMap<usize>, start: Point, goal: Point) -> Self {
+        Self {
+            pathfinder: PathFinder::new(
+                start,
+                goal,
+                map.create_storage::<Visited<usize, Point>>(),
+                (),
+            )
+            // shown live while stepping
+            .with_open_count(),
+            connected: are_connected(map, start, goal),
+            total_nodes: map.valid_count(),
+        }
+    }
+
+    /// Performs the steps of one frame, returning whether the search is still computing
+    fn step_frame(&mut self, map: &GridMap<usize>, steps: usize) -> bool {
+        matches!(
+            self.pathfinder.step_n(map, steps),
+            PathFinderState::Computing
+        )
+    }
 }
 
 struct MouseSelectState<M, K>
@@ -90,12 +147,14 @@ where
     K: Ord,
     <M as MapTrait>::Cost: AbsoluteCost<CmpContext = K>,
 {
+    #[allow(clippy::type_complexity)]
     callback: Box<dyn FnOnce(&mut AppImpl<M, K>, &Context, MouseEvent)>,
 }
 
 struct Background {
     image_data: DynamicImage,
-    image: ImageBitmap,
+    /// the image split into parts small enough for the browser to handle
+    tiles: Vec<(TileRect, ImageBitmap)>,
     scale: f64,
 }
 
@@ -119,6 +178,13 @@ impl From<&Background> for SerializableBackground {
     }
 }
 
+/// The selected start and goal points, stored so a reload resumes with the same endpoints
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct SerializablePoints {
+    start: Option<Point>,
+    goal: Option<Point>,
+}
+
 impl AppImpl<GridMap<usize>, CmpCtx> {
     pub async fn new(context: &Context) -> Self {
         // if the map has been stored in the browser, get it from there
@@ -129,13 +195,58 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
             GridMap::new(10, 10, 1)
         };
 
-        let mut s = Self {
+        let mut s = Self::with_map(map);
+
+        // load the background if it was stored
+        let loaded_background =
+            context.get_storage::<Option<SerializableBackground>>(STORAGE_KEY_BACKGROUND);
+        if let Some(Some(background)) = loaded_background {
+            let data = STANDARD.decode(&background.image_data_base64).unwrap();
+            s.set_background(&data).await;
+            if let Some(b) = &mut s.background {
+                b.scale = background.scale;
+            }
+            debug!("loaded background from storage");
+        }
+
+        // load the start and goal points if they were stored, they are clamped to the map bounds
+        // and used to re-create the pathfinder in on_map_change
+        if let Some(points) = context.get_storage::<SerializablePoints>(STORAGE_KEY_POINTS) {
+            s.start = points.start;
+            s.goal = points.goal;
+            debug!("loaded points from storage");
+        }
+
+        // give a fresh map endpoints that are far apart, so there is something to search for
+        if s.start.is_none() && s.goal.is_none() {
+            if let Some((start, goal)) = s.map.suggest_endpoints() {
+                s.start = Some(start);
+                s.goal = Some(goal);
+            }
+        }
+
+        s.set_editing(false, context);
+        s.on_map_change(context);
+        s
+    }
+
+    /// The app with default settings for the given map, without anything loaded from storage
+    fn with_map(map: GridMap<usize>) -> Self {
+        Self {
             editing: false,
             map,
             find_state: None,
             start: None,
             goal: None,
             auto_step: true,
+            steps_per_frame: 5,
+            frame_delay: 0,
+            brush_size: 0,
+            painting: false,
+            gradient_costs: (1, 10),
+            sample_density: 3,
+            color_tolerance: 10.0,
+            matching_is_valid: true,
             edit_selection: None,
             selection_start: None,
             selection_end: None,
@@ -147,23 +258,11 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
             background_alpha: 0.8,
             draw_grid: true,
             draw_pathfind_debug: true,
-        };
-
-        // load the background if it was stored
-        let loaded_background =
-            context.get_storage::<Option<SerializableBackground>>(STORAGE_KEY_BACKGROUND);
-        if let Some(Some(background)) = loaded_background {
-            let data = STANDARD.decode(&background.image_data_base64).unwrap();
-            s.set_background(&data).await;
-            if let Some(b) = &mut s.background {
-                b.scale = background.scale;
-            }
-            debug!("loaded background from storage");
+            highlight_path_only: false,
+            draw_frontier: false,
+            draw_legend: true,
+            color_ramp: ColorRamp::default(),
         }
-
-        s.set_editing(false, context);
-        s.on_map_change(context);
-        s
     }
 }
 
@@ -184,13 +283,19 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
     async fn handle_event(&mut self, event: Event, context: &Context) {
         // switch mode if the mode buttons were pressed
         match event {
-            Event::ButtonPressed(ButtonId::ClearStorage) => {
-                if gloo::dialogs::confirm("Are you sure you want to clear the storage?") {
-                    context.remove_storage(STORAGE_KEY_MAP);
-                    context.remove_storage(STORAGE_KEY_BACKGROUND);
-                }
+            Event::ButtonPressed(ButtonId::ClearStorage)
+                if gloo::dialogs::confirm("Are you sure you want to clear the storage?") =>
+            {
+                context.remove_storage(STORAGE_KEY_MAP);
+                context.remove_storage(STORAGE_KEY_BACKGROUND);
+                context.remove_storage(STORAGE_KEY_POINTS);
             }
             Event::ButtonPressed(ButtonId::ToggleEdit) => self.set_editing(!self.editing, context),
+            Event::ButtonPressed(ButtonId::SaveImage) => {
+                if let Err(e) = save_canvas_image() {
+                    context.set_output(&format!("Could not save the image: {e}"));
+                }
+            }
             Event::InputChanged(InputChange::Checkbox {
                 id: CheckboxId::AutoStep,
                 value: checked,
@@ -203,6 +308,18 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
                 id: CheckboxId::DrawPathfindDebug,
                 value,
             }) => self.draw_pathfind_debug = value,
+            Event::InputChanged(InputChange::Checkbox {
+                id: CheckboxId::HighlightPathOnly,
+                value,
+            }) => self.highlight_path_only = value,
+            Event::InputChanged(InputChange::Checkbox {
+                id: CheckboxId::DrawFrontier,
+                value,
+            }) => self.draw_frontier = value,
+            Event::InputChanged(InputChange::Checkbox {
+                id: CheckboxId::DrawLegend,
+                value,
+            }) => self.draw_legend = value,
             Event::InputChanged(InputChange::Number {
                 id: NumberInputId::BackgroundAlpha,
                 value,
@@ -213,6 +330,38 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
                 id: NumberInputId::ForegroundAlpha,
                 value,
             }) => self.map_alpha = value,
+            Event::InputChanged(InputChange::Number {
+                id: NumberInputId::StepsPerFrame,
+                value,
+            }) => self.steps_per_frame = (value as usize).max(1),
+            Event::InputChanged(InputChange::Number {
+                id: NumberInputId::FrameDelay,
+                value,
+            }) => self.frame_delay = value.max(0.0) as u32,
+            Event::InputChanged(InputChange::Number {
+                id: NumberInputId::GradientStart,
+                value,
+            }) => self.gradient_costs.0 = value as usize,
+            Event::InputChanged(InputChange::Number {
+                id: NumberInputId::GradientEnd,
+                value,
+            }) => self.gradient_costs.1 = value as usize,
+            Event::InputChanged(InputChange::Number {
+                id: NumberInputId::BrushSize,
+                value,
+            }) => self.brush_size = value.max(0.0) as usize,
+            Event::InputChanged(InputChange::Number {
+                id: NumberInputId::SampleDensity,
+                value,
+            }) => self.sample_density = (value as usize).max(1),
+            Event::InputChanged(InputChange::Number {
+                id: NumberInputId::ColorTolerance,
+                value,
+            }) => self.color_tolerance = value,
+            Event::InputChanged(InputChange::Checkbox {
+                id: CheckboxId::MatchingIsValid,
+                value,
+            }) => self.matching_is_valid = value,
             _ => {}
         }
         // handle the event depending on the current mode
@@ -237,6 +386,19 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
         }
     }
 
+    /// Paints the cells under the brush at the mouse position with the active cell
+    fn paint_brush(&mut self, x: i32, y: i32, context: &Context) {
+        if let (Some(center), Some(cell)) = (
+            self.mouse_to_world_point_valid(x, y),
+            context.get_active_cell(),
+        ) {
+            let radius = self.brush_size.saturating_sub(1);
+            for p in brush_cells(center, radius, self.map.rows, self.map.columns) {
+                self.map.cells[p.row][p.col] = cell;
+            }
+        }
+    }
+
     fn mouse_to_world_point_valid(&self, x: i32, y: i32) -> Option<Point> {
         let (x, y) = self.camera.pixel_to_world(x, y);
 
@@ -254,6 +416,15 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
         }
     }
 
+    /// In edit mode, scrolling over a valid cell without ctrl changes its cost instead of zooming
+    fn wheel_edits_cost(&self, x: i32, y: i32, ctrl_pressed: bool) -> bool {
+        self.editing
+            && !ctrl_pressed
+            && self
+                .mouse_to_world_point_valid(x, y)
+                .is_some_and(|p| matches!(self.map.cells[p.row][p.col], Cell::Valid { .. }))
+    }
+
     fn handle_event_panning(&mut self, event: &Event) -> bool {
         match event {
             Event::MousePressed(MouseEvent {
@@ -287,20 +458,17 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
             | Event::MouseMove(MouseEvent {
                 ctrl_pressed: false,
                 ..
-            }) => {
-                if self.last_pan_position.is_some() {
-                    self.last_pan_position = None;
-                    true
-                } else {
-                    false
-                }
+            }) if self.last_pan_position.is_some() => {
+                self.last_pan_position = None;
+                true
             }
             Event::MouseWheel {
                 x,
                 y,
                 delta_x: _,
                 delta_y,
-            } => {
+                ctrl_pressed,
+            } if !self.wheel_edits_cost(*x, *y, *ctrl_pressed) => {
                 let scale_factor = 1.1;
                 let scale_factor = if *delta_y < 0.0 {
                     scale_factor
@@ -324,6 +492,13 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
             // disable the edit inputs
             context.enable_element("edit-inputs", false);
 
+            // let the user know if there are any problems with the map
+            let issues = self.map.validate();
+            if !issues.is_empty() {
+                let issues: Vec<String> = issues.iter().map(|i| i.to_string()).collect();
+                gloo::dialogs::alert(&format!("The map has problems:\n{}", issues.join("\n")));
+            }
+
             // store the map in the localstorage
             context.set_storage(STORAGE_KEY_MAP, &self.map);
 
@@ -332,11 +507,46 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
                 STORAGE_KEY_BACKGROUND,
                 &self.background.as_ref().map(SerializableBackground::from),
             );
+
+            self.store_points(context);
         }
     }
 
+    /// Sets the start and goal points, clamped to the map, and starts a new search between them
+    pub fn set_endpoints(&mut self, start: Point, goal: Point, context: &Context) {
+        self.start = Some(start);
+        self.goal = Some(goal);
+        // clamps the points and re-creates the pathfinder
+        self.on_map_change(context);
+        self.store_points(context);
+    }
+
+    /// Stores the start and goal points in the localstorage
+    fn store_points(&self, context: &Context) {
+        context.set_storage(
+            STORAGE_KEY_POINTS,
+            &SerializablePoints {
+                start: self.start,
+                goal: self.goal,
+            },
+        );
+    }
+
     async fn handle_event_edit(&mut self, event: Event, context: &Context) {
         match event {
+            Event::MouseWheel {
+                x,
+                y,
+                delta_y,
+                ctrl_pressed: false,
+                ..
+            } => {
+                if let Some(point) = self.mouse_to_world_point_valid(x, y) {
+                    if let Cell::Valid { cost, .. } = &mut self.map.cells[point.row][point.col] {
+                        *cost = scroll_cost(*cost, delta_y);
+                    }
+                }
+            }
             Event::ButtonPressed(ButtonId::LoadPreset) => {
                 // load the image by including the bytes in the compilation
 
@@ -353,21 +563,15 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
                         cost: 1,
                         direction: Direction::Right,
                         target: Some(goal),
+                        target_cost: None,
                     };
 
-                    let finder = PathFinder::new(
-                        start,
-                        goal,
-                        map.create_storage::<Visited<usize, Point>>(),
-                        (),
-                    );
+                    self.find_state = Some(FindState::new(&map, start, goal));
 
                     self.map = map;
                     self.goal = Some(goal);
                     self.start = Some(start);
 
-                    self.find_state = Some(FindState { pathfinder: finder });
-
                     self.on_map_change(context);
                 }
             }
@@ -401,24 +605,55 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
             Event::ButtonPressed(ButtonId::SetOnewayTarget) => {
                 self.mouse_select_state = Some(MouseSelectState {
                     callback: Box::new(|app, context, event| {
-                        // if the user selected a alid cell, replace the target of the currently active cell
-                        // but only if it is a one way cell
-                        if let Some(point) = app.mouse_to_world_point_valid(event.x, event.y) {
-                            let cell = context.get_active_cell();
-                            if let Some(Cell::OneWay {
-                                direction, cost, ..
-                            }) = cell
-                            {
+                        // replace the target of the active cell, if it is a OneWay cell and
+                        // the user selected a passable cell
+                        let Some(Cell::OneWay {
+                            direction,
+                            cost,
+                            target_cost,
+                            ..
+                        }) = context.get_active_cell()
+                        else {
+                            context.set_output("Select a OneWay cell before picking its target");
+                            return;
+                        };
+                        match app.mouse_to_world_point_valid(event.x, event.y) {
+                            Some(point) if app.map.cells[point.row][point.col].is_passable() => {
                                 context.set_active_cell(Cell::OneWay {
                                     target: Some(point),
                                     direction,
                                     cost,
+                                    target_cost,
                                 });
+                                context.set_output(&format!("Selected target: {:?}", point));
+                            }
+                            _ => {
+                                context.set_output("The target must be a passable cell in the map")
                             }
                         }
                     }),
                 });
             }
+            Event::MousePressed(MouseEvent {
+                x,
+                y,
+                button: MouseButton::Secondary,
+                ..
+            }) => {
+                // pick up the properties of the clicked cell like an eyedropper
+                if let Some(point) = self.mouse_to_world_point_valid(x, y) {
+                    context.set_active_cell(self.map.cells[point.row][point.col]);
+                }
+            }
+            Event::MousePressed(MouseEvent {
+                x,
+                y,
+                button: MouseButton::Main,
+                ..
+            }) if self.brush_size > 0 => {
+                self.painting = true;
+                self.paint_brush(x, y, context);
+            }
             Event::MousePressed(MouseEvent {
                 x,
                 y,
@@ -434,6 +669,12 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
                     });
                 }
             }
+            Event::MouseReleased(MouseEvent {
+                x: _,
+                y: _,
+                button: MouseButton::Main,
+                ..
+            }) if self.painting => self.painting = false,
             Event::MouseReleased(MouseEvent {
                 x: _,
                 y: _,
@@ -460,6 +701,17 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
                     }
                 }
             }
+            Event::ButtonPressed(ButtonId::EditGradient) => {
+                if let Some(Selection { start, end }) = &self.edit_selection {
+                    let (start_cost, end_cost) = self.gradient_costs;
+                    self.map.fill_rect_gradient(
+                        start.row, start.col, end.row, end.col, start_cost, end_cost,
+                    );
+                }
+            }
+            Event::MouseMove(MouseEvent { x, y, .. }) if self.painting => {
+                self.paint_brush(x, y, context);
+            }
             Event::MouseMove(MouseEvent { x, y, .. }) => {
                 if let Some(start) = self.selection_start {
                     if let Some(end) = self.mouse_to_world_point_valid(x, y) {
@@ -509,42 +761,43 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
                         unreachable!()
                     };
 
-                    let rows = background.image.height() as f64 / ppc;
-                    let cols = background.image.width() as f64 / ppc;
+                    let rows = background.image_data.height() as f64 / ppc;
+                    let cols = background.image_data.width() as f64 / ppc;
                     self.map.resize(cols as usize, rows as usize);
                     background.scale = 1.0 / ppc;
                     self.on_map_change(context);
                 }
             }
-            Event::ButtonPressed(ButtonId::AutoCreateMap) => {
-                if self.background.is_some() {
-                    context.set_output("Click to select color that represents a valid free cell");
-                    self.mouse_select_state = Some(MouseSelectState {
-                        callback: Box::new(|app, context, event| {
-                            if let Some(background) = app.background.as_ref() {
-                                let (x, y) = app.camera.pixel_to_world(event.x, event.y);
-                                let (x, y) =
-                                    ((x / background.scale) as u32, (y / background.scale) as u32);
-
-                                let (width, height) = background.image_data.dimensions();
-                                if x < width && y < height {
-                                    let color = background.image_data.get_pixel(x, y);
-                                    context.set_output(&format!("Selected color: {:?}", color));
-
-                                    // generate a map based on the selected color
-                                    fill_map_from_image(
-                                        &mut app.map,
-                                        &background.image_data,
-                                        background.scale,
-                                        &color,
-                                    );
-                                } else {
-                                    context.set_output("Selected color is out of bounds");
-                                }
+            Event::ButtonPressed(ButtonId::AutoCreateMap) if self.background.is_some() => {
+                context.set_output("Click to select color that represents a valid free cell");
+                self.mouse_select_state = Some(MouseSelectState {
+                    callback: Box::new(|app, context, event| {
+                        if let Some(background) = app.background.as_ref() {
+                            let (x, y) = app.camera.pixel_to_world(event.x, event.y);
+                            let (x, y) =
+                                ((x / background.scale) as u32, (y / background.scale) as u32);
+
+                            let (width, height) = background.image_data.dimensions();
+                            if x < width && y < height {
+                                let color = background.image_data.get_pixel(x, y);
+                                context.set_output(&format!("Selected color: {:?}", color));
+
+                                // generate a map based on the selected color
+                                fill_map_from_image(
+                                    &mut app.map,
+                                    &background.image_data,
+                                    background.scale,
+                                    &color,
+                                    app.color_tolerance,
+                                    app.sample_density,
+                                    app.matching_is_valid,
+                                );
+                            } else {
+                                context.set_output("Selected color is out of bounds");
                             }
-                        }),
-                    });
-                }
+                        }
+                    }),
+                });
             }
             Event::ButtonPressed(ButtonId::LoadBackground) => {
                 let InputChange::Select {
@@ -602,33 +855,40 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
     }
 
     async fn set_background(&mut self, bytes: &[u8]) {
-        let dynamic_image = image::load_from_memory(bytes).expect("could not load image");
-
-        let rgba_image = dynamic_image.to_rgba8();
-
-        let clamped_buf: Clamped<&[u8]> = Clamped(rgba_image.as_raw());
-        let image_data_temp = ImageData::new_with_u8_clamped_array_and_sh(
-            clamped_buf,
-            dynamic_image.width(),
-            dynamic_image.height(),
-        )
-        .unwrap();
-
-        let jsimage = web_sys::window()
-            .expect("no global `window` exists")
-            .create_image_bitmap_with_image_data(&image_data_temp)
-            .unwrap();
+        // anything that is not a raster image is assumed to be an svg
+        let dynamic_image = match image::load_from_memory(bytes) {
+            Ok(image) => image,
+            Err(_) => rasterize_svg(bytes, 96.0).expect("could not load image"),
+        };
 
-        let jsimage = wasm_bindgen_futures::JsFuture::from(jsimage)
-            .await
-            .unwrap()
-            .into();
+        let mut tiles = Vec::new();
+        for rect in tile_rects(dynamic_image.width(), dynamic_image.height(), MAX_TILE_SIZE) {
+            let rgba_image = dynamic_image
+                .crop_imm(rect.x, rect.y, rect.width, rect.height)
+                .to_rgba8();
+
+            let clamped_buf: Clamped<&[u8]> = Clamped(rgba_image.as_raw());
+            let image_data_temp =
+                ImageData::new_with_u8_clamped_array_and_sh(clamped_buf, rect.width, rect.height)
+                    .unwrap();
+
+            let jsimage = web_sys::window()
+                .expect("no global `window` exists")
+                .create_image_bitmap_with_image_data(&image_data_temp)
+                .unwrap();
+
+            let jsimage = wasm_bindgen_futures::JsFuture::from(jsimage)
+                .await
+                .unwrap()
+                .into();
+            tiles.push((rect, jsimage));
+        }
 
-        debug!("loaded background image");
+        debug!("loaded background image in {} tiles", tiles.len());
 
         self.background = Some(Background {
             image_data: dynamic_image,
-            image: jsimage,
+            tiles,
             scale: 1.0,
         });
     }
@@ -664,37 +924,26 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
 
         // also need to reset the pathfinder
         if let (Some(start), Some(goal)) = (self.start, self.goal) {
-            self.find_state = Some(FindState {
-                pathfinder: PathFinder::new(
-                    start,
-                    goal,
-                    self.map.create_storage::<Visited<usize, Point>>(),
-                    (),
-                ),
-            });
+            self.find_state = Some(FindState::new(&self.map, start, goal));
         }
     }
 
-    fn handle_event_path_find(&mut self, event: Event, _context: &Context) {
-        match event {
-            Event::ButtonPressed(ButtonId::Reset) => {
+    /// Handles the buttons that control the search, returning false for any other button
+    fn handle_search_button(&mut self, button: ButtonId) -> bool {
+        match button {
+            ButtonId::Reset => {
                 if let (Some(start), Some(goal)) = (self.start, self.goal) {
-                    self.find_state = Some(FindState {
-                        pathfinder: PathFinder::new(
-                            start,
-                            goal,
-                            self.map.create_storage::<Visited<usize, Point>>(),
-                            (),
-                        ),
-                    });
+                    self.find_state = Some(FindState::new(&self.map, start, goal));
                 }
             }
-            Event::ButtonPressed(ButtonId::Step) => {
+            // only the bare map is drawn, and the kept endpoints let a reset start a new search
+            ButtonId::ClearSearch => self.find_state = None,
+            ButtonId::Step => {
                 if let Some(pathfinder) = &mut self.find_state {
                     pathfinder.pathfinder.step(&self.map);
                 }
             }
-            Event::ButtonPressed(ButtonId::Finish) => loop {
+            ButtonId::Finish => loop {
                 if let Some(pathfinder) = &mut self.find_state {
                     match pathfinder.pathfinder.step(&self.map) {
                         PathFinderState::Computing => {}
@@ -702,7 +951,22 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
                     }
                 }
             },
+            _ => return false,
+        }
+        true
+    }
 
+    fn handle_event_path_find(&mut self, event: Event, context: &Context) {
+        match event {
+            Event::ButtonPressed(button) if self.handle_search_button(button) => {}
+            Event::ButtonPressed(ButtonId::SwapEndpoints) => {
+                // searching backwards can cost something else on maps with OneWay cells
+                std::mem::swap(&mut self.start, &mut self.goal);
+                self.store_points(context);
+                if let (Some(start), Some(goal)) = (self.start, self.goal) {
+                    self.find_state = Some(FindState::new(&self.map, start, goal));
+                }
+            }
             Event::MouseReleased(MouseEvent {
                 x,
                 y,
@@ -715,17 +979,11 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
                         false => self.start = Some(point),
                         true => self.goal = Some(point),
                     }
+                    self.store_points(context);
 
                     debug!("{:?} -> {:?}", self.start, self.goal);
                     if let (Some(start), Some(goal)) = (self.start, self.goal) {
-                        self.find_state = Some(FindState {
-                            pathfinder: PathFinder::new(
-                                start,
-                                goal,
-                                self.map.create_storage::<Visited<usize, Point>>(),
-                                (),
-                            ),
-                        });
+                        self.find_state = Some(FindState::new(&self.map, start, goal));
                     }
                 }
             }
@@ -748,14 +1006,16 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
         if let Some(background) = &self.background {
             ctx.set_global_alpha(self.background_alpha);
             ctx.set_image_smoothing_enabled(false);
-            ctx.draw_image_with_image_bitmap_and_dw_and_dh(
-                &background.image,
-                0.0,
-                0.0,
-                background.image.width() as f64 * background.scale,
-                background.image.height() as f64 * background.scale,
-            )
-            .unwrap();
+            for (rect, image) in &background.tiles {
+                ctx.draw_image_with_image_bitmap_and_dw_and_dh(
+                    image,
+                    rect.x as f64 * background.scale,
+                    rect.y as f64 * background.scale,
+                    rect.width as f64 * background.scale,
+                    rect.height as f64 * background.scale,
+                )
+                .unwrap();
+            }
         }
 
         ctx.set_global_alpha(self.map_alpha);
@@ -767,14 +1027,9 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
             // autostep if autostep is enabled and we still have steps to complete
             if self.auto_step {
                 if let Some(pathfinder) = &mut self.find_state {
-                    for _ in 0..5 {
-                        match pathfinder.pathfinder.step(&self.map) {
-                            PathFinderState::Computing => {
-                                // request another animation frame
-                                context.request_repaint();
-                            }
-                            _ => break,
-                        }
+                    if pathfinder.step_frame(&self.map, self.steps_per_frame) {
+                        // request another frame, no more repaints are needed once done
+                        context.request_repaint_after(self.frame_delay);
                     }
                 }
             }
@@ -783,6 +1038,14 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
 
         ctx.restore();
 
+        // the labels are drawn in screen coordinates to keep the text crisp
+        if !self.editing {
+            self.render_endpoint_labels(ctx);
+        }
+        if self.draw_legend {
+            self.render_legend(ctx);
+        }
+
         // if we are in point selection mode, draw a crosshair at the mouse position
         if let Some(MouseSelectState { .. }) = self.mouse_select_state {
             if let Some((x, y)) = context.input(|input| input.current_mouse_position()) {
@@ -804,8 +1067,7 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
 
                 let color: String = match cell {
                     Cell::Invalid => "#000000".into(),
-                    Cell::Valid { cost: 1 } => "#FFFFFF".into(),
-                    Cell::Valid { .. } => "#FFFF00".into(),
+                    Cell::Valid { cost, .. } => css_color(&self.color_ramp.color_for(cost)),
                     // TODO: draw these as arrows!
                     Cell::OneWay { target: None, .. } => "#00FFFF".into(),
                     Cell::OneWay {
@@ -817,6 +1079,30 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
                 ctx.fill_rect(col as f64, row as f64, 1.0, 1.0);
             }
         }
+
+        // draw the teleport links of one way cells as faint dashed curves
+        ctx.set_stroke_style(&"rgba(255, 0, 255, 0.6)".into());
+        ctx.begin_path();
+        for (row, cells) in self.map.cells.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                if let Cell::OneWay {
+                    target: Some(target),
+                    ..
+                } = cell
+                {
+                    let from = (col as f64 + 0.5, row as f64 + 0.5);
+                    let to = (target.col as f64 + 0.5, target.row as f64 + 0.5);
+                    let points = quadratic_bezier(from, bend_control_point(from, to, 0.2), to, 16);
+
+                    // only draw every other segment to make the line dashed
+                    for segment in points.windows(2).step_by(2) {
+                        ctx.move_to(segment[0].0, segment[0].1);
+                        ctx.line_to(segment[1].0, segment[1].1);
+                    }
+                }
+            }
+        }
+        ctx.stroke();
         if self.draw_grid {
             // draw lines between all the cells
             ctx.set_stroke_style(&"#000000".into());
@@ -833,6 +1119,70 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
         }
     }
 
+    /// Draws "S" and "G" centered on the start and goal cells, scaled with the zoom level
+    fn render_endpoint_labels(&self, ctx: &CanvasRenderingContext2d) {
+        let scale = self.camera.scale();
+        ctx.set_font(&format!("bold {}px sans-serif", (scale * 0.8).round()));
+        ctx.set_text_align("center");
+        ctx.set_text_baseline("middle");
+        ctx.set_fill_style(&"#000000".into());
+
+        for (point, label) in [(self.start, "S"), (self.goal, "G")] {
+            if let Some(point) = point {
+                let (x, y) = self
+                    .camera
+                    .world_to_pixel(point.col as f64 + 0.5, point.row as f64 + 0.5);
+                ctx.fill_text(label, x, y).unwrap();
+            }
+        }
+    }
+
+    /// Draws a key of the cell colors in the top left corner, in screen coordinates
+    fn render_legend(&self, ctx: &CanvasRenderingContext2d) {
+        const SWATCH: f64 = 14.0;
+        const MARGIN: f64 = 8.0;
+
+        let mut entries: Vec<(String, String)> = vec![
+            ("#000000".into(), "Wall".into()),
+            ("#00FFFF".into(), "One way".into()),
+            ("#FF00FF".into(), "Teleport".into()),
+        ];
+        entries.extend(
+            self.color_ramp
+                .sample(LEGEND_RAMP_SWATCHES)
+                .into_iter()
+                .map(|(cost, color)| (css_color(&color), format!("Cost {cost}"))),
+        );
+
+        ctx.set_font("12px sans-serif");
+        ctx.set_text_align("left");
+        ctx.set_text_baseline("middle");
+        let width = entries
+            .iter()
+            .filter_map(|(_, label)| ctx.measure_text(label).ok())
+            .map(|metrics| metrics.width())
+            .fold(0.0, f64::max)
+            + SWATCH
+            + 3.0 * MARGIN / 2.0;
+        let height = entries.len() as f64 * (SWATCH + MARGIN / 2.0) + MARGIN / 2.0;
+
+        ctx.set_fill_style(&"rgba(255, 255, 255, 0.8)".into());
+        ctx.fill_rect(MARGIN, MARGIN, width, height);
+
+        ctx.set_line_width(1.0);
+        ctx.set_stroke_style(&"#000000".into());
+        for (i, (color, label)) in entries.iter().enumerate() {
+            let x = MARGIN * 1.5;
+            let y = MARGIN * 1.5 + i as f64 * (SWATCH + MARGIN / 2.0);
+            ctx.set_fill_style(&color.as_str().into());
+            ctx.fill_rect(x, y, SWATCH, SWATCH);
+            ctx.stroke_rect(x, y, SWATCH, SWATCH);
+            ctx.set_fill_style(&"#000000".into());
+            ctx.fill_text(label, x + SWATCH + MARGIN / 2.0, y + SWATCH / 2.0)
+                .unwrap();
+        }
+    }
+
     fn draw_neighbors(&self, point: &Point, ctx: &CanvasRenderingContext2d, style: &str) {
         if !self.map.is_valid(*point) {
             return;
@@ -882,7 +1232,23 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
 
     fn render_app_find(&self, context: &Context, ctx: &CanvasRenderingContext2d) {
         // render the app
-        context.set_output("");
+        context.set_output(&match &self.find_state {
+            Some(state) => {
+                let mut output = format!(
+                    "Steps: {}\nOpen: {}, closed: {}\nProgress: {:.0}%\nVisited storage: {} KiB",
+                    state.pathfinder.steps(),
+                    state.pathfinder.open_count().unwrap_or_default(),
+                    state.pathfinder.nodes_visited(),
+                    state.pathfinder.progress(state.total_nodes) * 100.0,
+                    state.pathfinder.get_visited().approx_memory_bytes() / 1024
+                );
+                if !state.connected {
+                    output.push_str("\nWarning: the goal can not be reached from the start");
+                }
+                output
+            }
+            None => String::new(),
+        });
 
         // draw the cells of the map
         self.render_map(context, ctx);
@@ -901,6 +1267,14 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
             let visited = state.pathfinder.get_visited();
 
             if self.draw_pathfind_debug {
+                // the cells of the found path, if only the path should be highlighted
+                let on_path = match state.pathfinder.state() {
+                    PathFinderState::PathFound(pr) if self.highlight_path_only => {
+                        Some(pr.path_set())
+                    }
+                    _ => None,
+                };
+
                 let margin = 0.15;
                 for row in 0..self.map.rows {
                     for col in 0..self.map.columns {
@@ -908,7 +1282,13 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
                         let v = visited.get(p);
 
                         if let Some(f) = *v {
-                            let color = format!("rgba({}, 0.0, 0.0, 0.8)", f.cost);
+                            let color = match &on_path {
+                                Some(on_path) if on_path.contains(&p) => {
+                                    "rgba(255, 255, 0, 0.9)".to_string()
+                                }
+                                Some(_) => format!("rgba({}, 0.0, 0.0, 0.2)", f.cost),
+                                None => format!("rgba({}, 0.0, 0.0, 0.8)", f.cost),
+                            };
                             ctx.set_fill_style(&color.into());
                             ctx.fill_rect(
                                 col as f64 + margin,
@@ -921,10 +1301,27 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
                 }
             }
 
+            if self.draw_frontier {
+                let margin = 0.15;
+                ctx.set_fill_style(&"rgba(0, 128, 255, 0.8)".into());
+                for p in state.pathfinder.iter_frontier() {
+                    ctx.fill_rect(
+                        p.col as f64 + margin,
+                        p.row as f64 + margin,
+                        1.0 - 2.0 * margin,
+                        1.0 - 2.0 * margin,
+                    );
+                }
+            }
+
             match state.pathfinder.state() {
                 PathFinderState::Computing => {}
-                PathFinderState::NoPathFound => {
-                    context.set_output("No path found");
+                PathFinderState::NoPathFound(reason) => {
+                    context.set_output(&format!(
+                        "No path found after {} steps: {}",
+                        state.pathfinder.steps(),
+                        reason
+                    ));
                 }
                 PathFinderState::PathFound(pr) => {
                     ctx.set_stroke_style(&"#FF0000".into());
@@ -933,7 +1330,7 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
                     ctx.set_line_width(1.0 / 3.0);
                     ctx.begin_path();
                     ctx.move_to(pr.start.col as f64 + 0.5, pr.start.row as f64 + 0.5);
-                    for p in &pr.path {
+                    for p in &pr.simplify_collinear() {
                         ctx.line_to(p.col as f64 + 0.5, p.row as f64 + 0.5);
                     }
 
@@ -961,40 +1358,116 @@ impl AppImpl<GridMap<usize>, CmpCtx> {
     }
 }
 
-/// Fills a map based on the pixels on an image and a selected color for valid cells
-fn fill_map_from_image(
-    map: &mut GridMap<usize>,
-    image: &DynamicImage,
-    image_scale: f64,
-    color: &image::Rgba<u8>,
-) {
-    for row in 0..map.rows {
-        for col in 0..map.columns {
-            // find the pixel at the center of the cell
-            let (x, y) = (col as f64 + 0.5, row as f64 + 0.5);
-            let (x, y) = (x / image_scale, y / image_scale);
-            let (x, y) = (x as u32, y as u32);
-            let pixel = image.get_pixel(x, y);
-
-            let diff = pixel_difference_norm(&pixel, color);
-
-            if diff < 10.0 {
-                map.cells[row][col] = Cell::Valid { cost: 1 };
-            } else {
-                map.cells[row][col] = Cell::Invalid;
-            }
-        }
+/// Returns the cost of a cell after scrolling by `delta_y`: scrolling up increases the cost by one
+/// and scrolling down decreases it, never going below 1
+fn scroll_cost(cost: usize, delta_y: f64) -> usize {
+    if delta_y < 0.0 {
+        cost.saturating_add(1)
+    } else if delta_y > 0.0 {
+        cost.saturating_sub(1).max(1)
+    } else {
+        cost
     }
 }
 
-fn pixel_difference_norm(a: &image::Rgba<u8>, b: &image::Rgba<u8>) -> f64 {
-    let a = a.0;
-    let b = b.0;
-    let diff = [
-        (a[0] as f64 - b[0] as f64).abs(),
-        (a[1] as f64 - b[1] as f64).abs(),
-        (a[2] as f64 - b[2] as f64).abs(),
-    ];
-    let diff = (diff[0].powi(2) + diff[1].powi(2) + diff[2].powi(2)).sqrt();
-    diff
+/// The number of cost swatches in the legend, spread evenly over the color ramp
+const LEGEND_RAMP_SWATCHES: usize = 5;
+
+/// Formats a color as a css color string
+fn css_color(color: &image::Rgba<u8>) -> String {
+    let [r, g, b, a] = color.0;
+    format!("rgba({}, {}, {}, {})", r, g, b, a as f64 / 255.0)
+}
+
+/// Downloads what is currently drawn on the canvas as a PNG image
+fn save_canvas_image() -> Result<(), String> {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let canvas: HtmlCanvasElement = document
+        .get_element_by_id("canvas")
+        .and_then(|e| e.dyn_into().ok())
+        .ok_or("there is no canvas")?;
+
+    // fails with a security error if a cross-origin background tainted the canvas
+    let url = canvas
+        .to_data_url_with_type("image/png")
+        .map_err(|_| "the canvas contains images from other sites that can not be exported")?;
+    let file_name = parse_data_url(&url)
+        .and_then(|data| image_file_name("mappath", data.mime))
+        .ok_or("the canvas is empty")?;
+
+    let link: HtmlElement = document
+        .create_element("a")
+        .and_then(|e| e.dyn_into().map_err(Into::into))
+        .map_err(|_| "could not create a download link")?;
+    link.set_attribute("href", &url)
+        .and_then(|_| link.set_attribute("download", &file_name))
+        .map_err(|_| "could not create a download link")?;
+    link.click();
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_scroll_cost() {
+        assert_eq!(scroll_cost(1, -120.0), 2);
+        assert_eq!(scroll_cost(5, 3.0), 4);
+        assert_eq!(scroll_cost(1, 120.0), 1);
+        assert_eq!(scroll_cost(0, 120.0), 1);
+        assert_eq!(scroll_cost(7, 0.0), 7);
+        assert_eq!(scroll_cost(usize::MAX, -1.0), usize::MAX);
+    }
+
+    #[test]
+    fn test_points_round_trip() {
+        let points = SerializablePoints {
+            start: Some(Point { row: 3, col: 7 }),
+            goal: None,
+        };
+
+        let json = serde_json::to_string(&points).unwrap();
+        let restored: SerializablePoints = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, points);
+    }
+
+    #[test]
+    fn test_clear_search() {
+        let mut app = AppImpl::with_map(GridMap::new(1, 4, 1));
+        let (start, goal) = (Point { row: 0, col: 0 }, Point { row: 0, col: 3 });
+        app.start = Some(start);
+        app.goal = Some(goal);
+        assert!(app.handle_search_button(ButtonId::Reset));
+        assert!(app.handle_search_button(ButtonId::Step));
+        assert!(app.find_state.is_some());
+
+        // the visited overlay is drawn from the search, which is gone, but the endpoints are kept
+        assert!(app.handle_search_button(ButtonId::ClearSearch));
+        assert!(app.find_state.is_none());
+        assert_eq!((app.start, app.goal), (Some(start), Some(goal)));
+
+        // a reset searches between them again
+        assert!(app.handle_search_button(ButtonId::Reset));
+        assert!(app.find_state.is_some());
+    }
+
+    #[test]
+    fn test_step_frame_stops_when_found() {
+        let map: GridMap<usize> = GridMap::new(1, 4, 1);
+        let mut state = FindState::new(&map, Point { row: 0, col: 0 }, Point { row: 0, col: 3 });
+        assert!(state.connected);
+
+        assert!(state.step_frame(&map, 1));
+        while state.step_frame(&map, 2) {}
+        assert!(matches!(
+            state.pathfinder.state(),
+            PathFinderState::PathFound(_)
+        ));
+
+        // further frames do not step or request repaints
+        let steps = state.pathfinder.steps();
+        assert!(!state.step_frame(&map, 5));
+        assert_eq!(state.pathfinder.steps(), steps);
+    }
 }
@@ -0,0 +1,53 @@
+use optimize::grid::Point;
+
+/// Returns the cells covered by a circular brush of `radius` cells centered on `center`, row by
+/// row. A cell is covered if its distance to the center is at most `radius`, and cells outside of
+/// a map with `rows` x `columns` cells are left out.
+pub fn brush_cells(center: Point, radius: usize, rows: usize, columns: usize) -> Vec<Point> {
+    if center.row >= rows || center.col >= columns {
+        return Vec::new();
+    }
+
+    let row_range = center.row.saturating_sub(radius)..=(center.row + radius).min(rows - 1);
+    let col_range = center.col.saturating_sub(radius)..=(center.col + radius).min(columns - 1);
+
+    row_range
+        .flat_map(|row| col_range.clone().map(move |col| Point { row, col }))
+        .filter(|p| {
+            let (dr, dc) = (p.row.abs_diff(center.row), p.col.abs_diff(center.col));
+            dr * dr + dc * dc <= radius * radius
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_brush_cells() {
+        let center = Point { row: 5, col: 5 };
+
+        assert_eq!(brush_cells(center, 0, 10, 10), vec![center]);
+
+        let cells = brush_cells(center, 1, 10, 10);
+        assert_eq!(cells.len(), 5);
+        assert!(cells.contains(&Point { row: 4, col: 5 }));
+        assert!(!cells.contains(&Point { row: 4, col: 4 }));
+
+        let cells = brush_cells(center, 2, 10, 10);
+        assert_eq!(cells.len(), 13);
+        assert!(cells.contains(&Point { row: 4, col: 4 }));
+        assert!(cells.contains(&Point { row: 5, col: 7 }));
+        assert!(!cells.contains(&Point { row: 3, col: 4 }));
+
+        // clipped at the edges of the map
+        let corner = Point { row: 0, col: 0 };
+        assert_eq!(
+            brush_cells(corner, 1, 10, 10),
+            vec![corner, Point { row: 0, col: 1 }, Point { row: 1, col: 0 }]
+        );
+        assert_eq!(brush_cells(corner, 2, 10, 10).len(), 6);
+        assert!(brush_cells(Point { row: 10, col: 0 }, 1, 10, 10).is_empty());
+    }
+}
@@ -19,6 +19,14 @@ impl Camera {
         (x - self.offset.0, y - self.offset.1)
     }
 
+    /// Converts a world position into a pixel position
+    pub fn world_to_pixel(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            (x + self.offset.0) * self.scale,
+            (y + self.offset.1) * self.scale,
+        )
+    }
+
     /// Pans the camera by the given amount in pixels
     pub fn pan_pixels(&mut self, dx: i32, dy: i32) {
         self.offset.0 += dx as f64 / self.scale;
@@ -44,3 +52,22 @@ impl Camera {
         self.offset
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_world_to_pixel() {
+        let mut camera = Camera::new(10.0);
+        camera.pan_pixels(20, -10);
+        assert_eq!(camera.world_to_pixel(0.5, 0.5), (25.0, -5.0));
+
+        camera.zoom_at(100, 50, 2.0);
+        for (x, y) in [(0, 0), (100, 50), (-30, 70)] {
+            let (wx, wy) = camera.pixel_to_world(x, y);
+            let (px, py) = camera.world_to_pixel(wx, wy);
+            assert!((px - x as f64).abs() < 1e-9 && (py - y as f64).abs() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,50 @@
+/// Returns a control point that bends a curve between `from` and `to` to the side, by `bend`
+/// times the distance between them
+pub fn bend_control_point(from: (f64, f64), to: (f64, f64), bend: f64) -> (f64, f64) {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let middle = (from.0 + dx / 2.0, from.1 + dy / 2.0);
+
+    // the perpendicular of (dx, dy) has the same length as the distance
+    (middle.0 - dy * bend, middle.1 + dx * bend)
+}
+
+/// Samples `segments + 1` points along the quadratic bezier curve from `from` to `to`, including
+/// both endpoints
+pub fn quadratic_bezier(
+    from: (f64, f64),
+    control: (f64, f64),
+    to: (f64, f64),
+    segments: usize,
+) -> Vec<(f64, f64)> {
+    let segments = segments.max(1);
+    (0..=segments)
+        .map(|i| {
+            let t = i as f64 / segments as f64;
+            let (a, b, c) = ((1.0 - t) * (1.0 - t), 2.0 * (1.0 - t) * t, t * t);
+            (
+                a * from.0 + b * control.0 + c * to.0,
+                a * from.1 + b * control.1 + c * to.1,
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_quadratic_bezier() {
+        let (from, to) = ((1.5, 2.5), (8.5, 4.5));
+        let control = bend_control_point(from, to, 0.25);
+
+        let points = quadratic_bezier(from, control, to, 16);
+        assert_eq!(points.len(), 17);
+        assert_eq!(points[0], from);
+        assert_eq!(points[16], to);
+
+        // a straight line when the control point is on the line
+        let points = quadratic_bezier((0.0, 0.0), (1.0, 1.0), (2.0, 2.0), 4);
+        assert!(points.iter().all(|(x, y)| (x - y).abs() < 1e-9));
+    }
+}
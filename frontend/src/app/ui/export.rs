@@ -0,0 +1,79 @@
+/// The parts of a `data:` URL, as returned by `HTMLCanvasElement.toDataURL`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataUrl<'a> {
+    pub mime: &'a str,
+    pub base64: bool,
+    pub data: &'a str,
+}
+
+/// Splits a `data:[<mime>][;base64],<data>` URL into its parts. Parameters other than `base64`
+/// are ignored. Returns None if the URL is not a data URL.
+pub fn parse_data_url(url: &str) -> Option<DataUrl<'_>> {
+    let (header, data) = url.strip_prefix("data:")?.split_once(',')?;
+    let mut parts = header.split(';');
+    let mime = parts.next().unwrap_or_default();
+    let base64 = parts.any(|p| p == "base64");
+    Some(DataUrl { mime, base64, data })
+}
+
+/// The name to save an image of the given MIME type as, or None if it is not an image type we
+/// know the extension of. Canvases that are too small to hold any pixels give an empty MIME type.
+pub fn image_file_name(stem: &str, mime: &str) -> Option<String> {
+    let extension = match mime {
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/webp" => "webp",
+        _ => return None,
+    };
+    Some(format!("{stem}.{extension}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_data_url() {
+        assert_eq!(
+            parse_data_url("data:image/png;base64,iVBORw0KGgo="),
+            Some(DataUrl {
+                mime: "image/png",
+                base64: true,
+                data: "iVBORw0KGgo="
+            })
+        );
+        assert_eq!(
+            parse_data_url("data:text/plain;charset=utf-8,a,b"),
+            Some(DataUrl {
+                mime: "text/plain",
+                base64: false,
+                data: "a,b"
+            })
+        );
+        // what an empty canvas returns
+        assert_eq!(
+            parse_data_url("data:,"),
+            Some(DataUrl {
+                mime: "",
+                base64: false,
+                data: ""
+            })
+        );
+        assert_eq!(parse_data_url("https://example.com/image.png"), None);
+        assert_eq!(parse_data_url("data:image/png"), None);
+    }
+
+    #[test]
+    fn test_image_file_name() {
+        assert_eq!(
+            image_file_name("mappath", "image/png").as_deref(),
+            Some("mappath.png")
+        );
+        assert_eq!(
+            image_file_name("mappath", "image/jpeg").as_deref(),
+            Some("mappath.jpg")
+        );
+        assert_eq!(image_file_name("mappath", ""), None);
+        assert_eq!(image_file_name("mappath", "text/plain"), None);
+    }
+}
@@ -1 +1,5 @@
+pub mod brush;
 pub mod camera;
+pub mod curve;
+pub mod export;
+pub mod tiles;
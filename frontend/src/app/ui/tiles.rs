@@ -0,0 +1,61 @@
+/// The largest width and height of a single background tile in pixels. Browsers fail to create
+/// bitmaps above a size limit, so larger images are split into several tiles.
+pub const MAX_TILE_SIZE: u32 = 4096;
+
+/// A part of an image, in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Splits an image of `width` x `height` pixels into tiles no larger than `max_size` in either
+/// direction, row by row. The tiles along the right and bottom edges are smaller if the size is
+/// not a multiple of `max_size`.
+pub fn tile_rects(width: u32, height: u32, max_size: u32) -> Vec<TileRect> {
+    assert!(max_size > 0, "tiles can not be empty");
+    let split = |length: u32| {
+        (0..length)
+            .step_by(max_size as usize)
+            .map(move |start| (start, max_size.min(length - start)))
+    };
+
+    split(height)
+        .flat_map(|(y, height)| {
+            split(width).map(move |(x, width)| TileRect {
+                x,
+                y,
+                width,
+                height,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tile_rects() {
+        let tiles = tile_rects(5000, 5000, MAX_TILE_SIZE);
+        assert_eq!(tiles.len(), 4);
+        assert_eq!(
+            tiles[3],
+            TileRect {
+                x: 4096,
+                y: 4096,
+                width: 904,
+                height: 904
+            }
+        );
+        let area: u32 = tiles.iter().map(|t| t.width * t.height).sum();
+        assert_eq!(area, 5000 * 5000);
+
+        assert_eq!(tile_rects(100, 50, MAX_TILE_SIZE).len(), 1);
+        assert_eq!(tile_rects(8192, 1, MAX_TILE_SIZE).len(), 2);
+        assert!(tile_rects(0, 10, MAX_TILE_SIZE).is_empty());
+    }
+}
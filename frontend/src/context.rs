@@ -16,6 +16,7 @@ use crate::event::ButtonId;
 use crate::event::Event;
 use crate::event::InputChange;
 use crate::event::InputId;
+use crate::event::KeyBindings;
 use crate::event::MouseEvent;
 
 #[derive(Clone)]
@@ -24,6 +25,7 @@ pub struct Context {
 }
 
 impl Context {
+    #[allow(clippy::arc_with_non_send_sync)]
     pub fn new(inner: ContextImpl) -> Self {
         Self {
             inner: Arc::new(RwLock::new(inner)),
@@ -36,7 +38,7 @@ impl Context {
         F: FnOnce(&ContextImpl) -> T,
     {
         let inner = self.inner.read().unwrap();
-        f(&*inner)
+        f(&inner)
     }
 
     /// apply exclusive write access to the inner context
@@ -45,7 +47,7 @@ impl Context {
         F: FnOnce(&mut ContextImpl) -> T,
     {
         let mut inner = self.inner.write().unwrap();
-        f(&mut *inner)
+        f(&mut inner)
     }
 }
 
@@ -131,19 +133,36 @@ impl Context {
         });
     }
 
+    pub fn button_for_key(&self, key: &str) -> Option<ButtonId> {
+        self.read(|inner| inner.key_bindings.button_for(key))
+    }
+
     pub fn pop_event(&self) -> Option<Event> {
         self.write(|inner| inner.events.pop_front())
     }
 
     pub fn request_repaint(&self) {
-        self.write(|inner| inner.repaint_requested = true);
+        self.request_repaint_after(0);
+    }
+
+    /// Requests a repaint once `delay_ms` milliseconds have passed. If several repaints are
+    /// requested during a frame, the earliest one wins.
+    pub fn request_repaint_after(&self, delay_ms: u32) {
+        self.write(|inner| {
+            inner.repaint_delay_ms = match inner.repaint_requested {
+                true => inner.repaint_delay_ms.min(delay_ms),
+                false => delay_ms,
+            };
+            inner.repaint_requested = true;
+        });
     }
 
-    pub fn is_repaint_requested(&self) -> bool {
+    /// Returns the delay of the requested repaint (if any) and clears the request
+    pub fn take_repaint_request(&self) -> Option<u32> {
         self.write(|inner| {
             let repaint_requested = inner.repaint_requested;
             inner.repaint_requested = false;
-            repaint_requested
+            repaint_requested.then_some(std::mem::take(&mut inner.repaint_delay_ms))
         })
     }
 
@@ -215,88 +234,173 @@ pub struct CellSelector {
     pub radio_invalid: HtmlInputElement,
     pub radio_valid: HtmlInputElement,
     pub input_valid_cost: HtmlInputElement,
+    pub input_entry_cost: HtmlInputElement,
     pub radio_oneway: HtmlInputElement,
     pub select_oneway: HtmlSelectElement,
     pub span_oneway_target: HtmlElement,
+    pub input_target_cost: HtmlInputElement,
 }
 
 impl CellSelector {
     pub fn set_cell(&self, cell: Cell<usize>) {
         debug!("setting cell: {:?}", cell);
 
-        match cell {
-            Cell::Invalid => self.radio_invalid.set_checked(true),
-            Cell::Valid { cost } => {
+        let fields = CellFields::from_cell(cell);
+        match fields.kind {
+            Some(CellKind::Invalid) | None => self.radio_invalid.set_checked(true),
+            Some(CellKind::Valid) => {
                 self.radio_valid.set_checked(true);
-                self.input_valid_cost.set_value(&cost.to_string());
+                self.input_valid_cost.set_value(&fields.cost);
+                self.input_entry_cost.set_value(&fields.entry_cost);
             }
-            Cell::OneWay {
-                cost,
-                direction,
-                target,
-            } => {
+            Some(CellKind::OneWay) => {
                 self.radio_oneway.set_checked(true);
-                self.input_valid_cost.set_value(&cost.to_string());
-                self.select_oneway.set_value(&direction.to_string());
-
-                let target_string = if let Some(target) = target {
-                    serde_json::to_string(&target).unwrap()
-                } else {
-                    "".to_string()
-                };
-
-                self.span_oneway_target
-                    .set_inner_text(target_string.as_str());
+                self.input_valid_cost.set_value(&fields.cost);
+                self.select_oneway.set_value(&fields.direction);
+                self.span_oneway_target.set_inner_text(&fields.target);
+                self.input_target_cost.set_value(&fields.target_cost);
             }
         }
     }
 
     pub fn get_cell(&self) -> Option<Cell<usize>> {
-        if self.radio_invalid.checked() {
-            Some(Cell::Invalid)
+        let kind = if self.radio_invalid.checked() {
+            Some(CellKind::Invalid)
         } else if self.radio_valid.checked() {
-            let cost = self.input_valid_cost.value().parse().unwrap();
-            Some(Cell::Valid { cost })
+            Some(CellKind::Valid)
         } else if self.radio_oneway.checked() {
-            let cost = self.input_valid_cost.value().parse().unwrap();
-            let direction = self.select_oneway.value().parse().unwrap();
-
-            // try to deserialize the target
-            let target = self.span_oneway_target.inner_text();
-            let target: Option<Point> = if !target.is_empty() {
-                match serde_json::from_str(&target) {
-                    Ok(point) => Some(point),
-                    Err(e) => {
-                        gloo::dialogs::alert(&format!("Could not parse target point: {e}"));
-                        None
-                    }
-                }
-            } else {
+            Some(CellKind::OneWay)
+        } else {
+            None
+        };
+        let fields = CellFields {
+            kind,
+            cost: self.input_valid_cost.value(),
+            entry_cost: self.input_entry_cost.value(),
+            direction: self.select_oneway.value(),
+            target: self.span_oneway_target.inner_text(),
+            target_cost: self.input_target_cost.value(),
+        };
+
+        match fields.to_cell() {
+            Ok(cell) => cell,
+            Err(e) => {
+                gloo::dialogs::alert(&e);
                 None
-            };
+            }
+        }
+    }
+}
+
+/// The kinds of cells the cell selector can show, one per radio button
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CellKind {
+    Invalid,
+    Valid,
+    OneWay,
+}
+
+/// The contents of the cell selector inputs, which keeps the conversion between cells and the
+/// text of the inputs separate from the DOM. Only the fields used by `kind` are filled in.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct CellFields {
+    kind: Option<CellKind>,
+    cost: String,
+    entry_cost: String,
+    direction: String,
+    target: String,
+    target_cost: String,
+}
 
-            Some(Cell::OneWay {
+impl CellFields {
+    fn from_cell(cell: Cell<usize>) -> Self {
+        match cell {
+            Cell::Invalid => Self {
+                kind: Some(CellKind::Invalid),
+                ..Default::default()
+            },
+            Cell::Valid { cost, entry_cost } => Self {
+                kind: Some(CellKind::Valid),
+                cost: cost.to_string(),
+                entry_cost: entry_cost.unwrap_or(0).to_string(),
+                ..Default::default()
+            },
+            Cell::OneWay {
                 cost,
                 direction,
                 target,
-            })
-        } else {
-            None
+                target_cost,
+            } => Self {
+                kind: Some(CellKind::OneWay),
+                cost: cost.to_string(),
+                direction: direction.to_string(),
+                target: format_target(target),
+                target_cost: target_cost.map(|c| c.to_string()).unwrap_or_default(),
+                ..Default::default()
+            },
         }
     }
+
+    /// Parses the fields used by the kind into a cell, None if no kind is selected
+    fn to_cell(&self) -> Result<Option<Cell<usize>>, String> {
+        let cost = || {
+            self.cost
+                .parse()
+                .map_err(|e| format!("Could not parse cost: {e}"))
+        };
+        let cell = match self.kind {
+            None => return Ok(None),
+            Some(CellKind::Invalid) => Cell::Invalid,
+            Some(CellKind::Valid) => Cell::Valid {
+                cost: cost()?,
+                entry_cost: match self.entry_cost.parse() {
+                    Ok(0) => None,
+                    Ok(entry_cost) => Some(entry_cost),
+                    Err(e) => return Err(format!("Could not parse entry cost: {e}")),
+                },
+            },
+            Some(CellKind::OneWay) => Cell::OneWay {
+                cost: cost()?,
+                direction: self
+                    .direction
+                    .parse()
+                    .map_err(|e| format!("Could not parse direction: {e}"))?,
+                target: parse_target(&self.target)
+                    .map_err(|e| format!("Could not parse target point: {e}"))?,
+                // empty to use the cost of the cell for the teleport as well
+                target_cost: match self.target_cost.as_str() {
+                    "" => None,
+                    c => Some(
+                        c.parse()
+                            .map_err(|e| format!("Could not parse target cost: {e}"))?,
+                    ),
+                },
+            },
+        };
+        Ok(Some(cell))
+    }
 }
 
-pub struct Input {
-    mouse_position: Option<(i32, i32)>,
+/// Formats the target of a OneWay cell for the cell selector, empty if there is no target
+fn format_target(target: Option<Point>) -> String {
+    target
+        .map(|target| serde_json::to_string(&target).unwrap())
+        .unwrap_or_default()
 }
 
-impl Default for Input {
-    fn default() -> Self {
-        Self {
-            mouse_position: Default::default(),
-        }
+/// Parses a target written by [`format_target`]
+fn parse_target(target: &str) -> Result<Option<Point>, serde_json::Error> {
+    if target.is_empty() {
+        return Ok(None);
     }
+    serde_json::from_str(target).map(Some)
 }
+
+#[derive(Default)]
+pub struct Input {
+    mouse_position: Option<(i32, i32)>,
+}
+
 impl Input {
     pub fn on_event(&mut self, event: Event) {
         match event {
@@ -318,4 +422,60 @@ pub struct ContextImpl {
     pub input: Input,
     pub events: VecDeque<Event>,
     pub repaint_requested: bool,
+    pub repaint_delay_ms: u32,
+    pub key_bindings: KeyBindings,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use optimize::grid::Direction;
+
+    #[test]
+    fn test_target_round_trip() {
+        let target = Some(Point { row: 3, col: 14 });
+        assert_eq!(parse_target(&format_target(target)).unwrap(), target);
+        assert_eq!(format_target(None), "");
+        assert_eq!(parse_target("").unwrap(), None);
+        assert!(parse_target("not a point").is_err());
+    }
+
+    #[test]
+    fn test_cell_fields_round_trip() {
+        let cells = [
+            Cell::Invalid,
+            Cell::Valid {
+                cost: 3,
+                entry_cost: None,
+            },
+            Cell::Valid {
+                cost: 1,
+                entry_cost: Some(7),
+            },
+            Cell::OneWay {
+                cost: 2,
+                direction: Direction::Left,
+                target: None,
+                target_cost: None,
+            },
+            Cell::OneWay {
+                cost: 5,
+                direction: Direction::Down,
+                target: Some(Point { row: 4, col: 9 }),
+                target_cost: Some(8),
+            },
+        ];
+        for cell in cells {
+            assert_eq!(CellFields::from_cell(cell).to_cell(), Ok(Some(cell)));
+        }
+
+        assert_eq!(CellFields::default().to_cell(), Ok(None));
+        let fields = CellFields {
+            kind: Some(CellKind::Valid),
+            cost: "cheap".to_string(),
+            entry_cost: "0".to_string(),
+            ..Default::default()
+        };
+        assert!(fields.to_cell().is_err());
+    }
 }
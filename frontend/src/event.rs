@@ -1,5 +1,9 @@
 #![allow(dead_code)]
 
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
 #[derive(Debug, Clone)]
 pub enum Event {
     ButtonPressed(ButtonId),
@@ -16,6 +20,7 @@ pub enum Event {
         y: i32,
         delta_x: f64,
         delta_y: f64,
+        ctrl_pressed: bool,
     },
 }
 
@@ -40,7 +45,7 @@ impl From<web_sys::MouseEvent> for MouseEvent {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ButtonId {
     Reset,
     Step,
@@ -55,6 +60,10 @@ pub enum ButtonId {
     LoadBackground,
     SetOnewayTarget,
     DoubleMap,
+    EditGradient,
+    SaveImage,
+    ClearSearch,
+    SwapEndpoints,
 }
 
 impl ButtonId {
@@ -74,6 +83,10 @@ impl ButtonId {
             ButtonId::LoadBackground => "btn-load-background",
             ButtonId::SetOnewayTarget => "btn-oneway-target-set",
             ButtonId::DoubleMap => "btn-double-map",
+            ButtonId::EditGradient => "btn-edit-gradient",
+            ButtonId::SaveImage => "btn-save-image",
+            ButtonId::ClearSearch => "btn-clear-search",
+            ButtonId::SwapEndpoints => "btn-swap-endpoints",
         }
     }
 
@@ -93,21 +106,49 @@ impl ButtonId {
             ButtonId::LoadBackground,
             ButtonId::SetOnewayTarget,
             ButtonId::DoubleMap,
+            ButtonId::EditGradient,
+            ButtonId::SaveImage,
+            ButtonId::ClearSearch,
+            ButtonId::SwapEndpoints,
         ]
         .iter()
         .copied()
     }
+}
 
-    pub fn from_key_code(key: &str) -> Option<ButtonId> {
-        match key {
-            "r" => Some(ButtonId::Reset),
-            "t" => Some(ButtonId::Step),
-            "f" => Some(ButtonId::Finish),
-            "e" => Some(ButtonId::ToggleEdit),
-            "s" => Some(ButtonId::EditSave),
-            "p" => Some(ButtonId::SelectPoint),
-            _ => None,
-        }
+/// Maps keys (as reported by `KeyboardEvent.key`) to the buttons they press
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyBindings(HashMap<String, ButtonId>);
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self(
+            [
+                ("r", ButtonId::Reset),
+                ("t", ButtonId::Step),
+                ("f", ButtonId::Finish),
+                ("e", ButtonId::ToggleEdit),
+                ("s", ButtonId::EditSave),
+                ("p", ButtonId::SelectPoint),
+            ]
+            .into_iter()
+            .map(|(key, button)| (key.to_string(), button))
+            .collect(),
+        )
+    }
+}
+
+impl KeyBindings {
+    /// The default bindings, with `overrides` added on top (replacing the defaults for the same
+    /// keys)
+    pub fn with_overrides(overrides: HashMap<String, ButtonId>) -> Self {
+        let mut bindings = Self::default();
+        bindings.0.extend(overrides);
+        bindings
+    }
+
+    pub fn button_for(&self, key: &str) -> Option<ButtonId> {
+        self.0.get(key).copied()
     }
 }
 
@@ -151,6 +192,10 @@ pub enum CheckboxId {
     AutoStep,
     DrawGrid,
     DrawPathfindDebug,
+    MatchingIsValid,
+    HighlightPathOnly,
+    DrawFrontier,
+    DrawLegend,
 }
 
 impl CheckboxId {
@@ -159,6 +204,10 @@ impl CheckboxId {
             CheckboxId::AutoStep => "input-auto-step",
             CheckboxId::DrawGrid => "input-draw-grid",
             CheckboxId::DrawPathfindDebug => "input-draw-pathfind-debug",
+            CheckboxId::MatchingIsValid => "input-matching-valid",
+            CheckboxId::HighlightPathOnly => "input-highlight-path-only",
+            CheckboxId::DrawFrontier => "input-draw-frontier",
+            CheckboxId::DrawLegend => "input-draw-legend",
         }
     }
     pub fn iterate() -> impl Iterator<Item = CheckboxId> {
@@ -166,6 +215,10 @@ impl CheckboxId {
             CheckboxId::AutoStep,
             CheckboxId::DrawGrid,
             CheckboxId::DrawPathfindDebug,
+            CheckboxId::MatchingIsValid,
+            CheckboxId::HighlightPathOnly,
+            CheckboxId::DrawFrontier,
+            CheckboxId::DrawLegend,
         ]
         .into_iter()
     }
@@ -178,6 +231,13 @@ pub enum NumberInputId {
     ForegroundAlpha,
     BackgroundScale,
     AutoScaleFactor,
+    StepsPerFrame,
+    FrameDelay,
+    GradientStart,
+    GradientEnd,
+    BrushSize,
+    SampleDensity,
+    ColorTolerance,
 }
 impl NumberInputId {
     pub fn id_str(&self) -> &str {
@@ -188,6 +248,13 @@ impl NumberInputId {
             NumberInputId::ForegroundAlpha => "input-foreground-alpha",
             NumberInputId::BackgroundScale => "input-background-scale",
             NumberInputId::AutoScaleFactor => "input-auto-scale-factor",
+            NumberInputId::StepsPerFrame => "input-steps-per-frame",
+            NumberInputId::FrameDelay => "input-frame-delay",
+            NumberInputId::GradientStart => "input-gradient-start",
+            NumberInputId::GradientEnd => "input-gradient-end",
+            NumberInputId::BrushSize => "input-brush-size",
+            NumberInputId::SampleDensity => "input-sample-density",
+            NumberInputId::ColorTolerance => "input-color-tolerance",
         }
     }
     pub fn iterate() -> impl Iterator<Item = NumberInputId> {
@@ -198,6 +265,13 @@ impl NumberInputId {
             NumberInputId::ForegroundAlpha,
             NumberInputId::BackgroundScale,
             NumberInputId::AutoScaleFactor,
+            NumberInputId::StepsPerFrame,
+            NumberInputId::FrameDelay,
+            NumberInputId::GradientStart,
+            NumberInputId::GradientEnd,
+            NumberInputId::BrushSize,
+            NumberInputId::SampleDensity,
+            NumberInputId::ColorTolerance,
         ]
         .into_iter()
     }
@@ -243,3 +317,22 @@ impl InputId {
             .chain(SelectId::iterate().map(InputId::Select))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_key_bindings() {
+        // the bindings are stored as a json object from key to button
+        let overrides = serde_json::from_str(r#"{"x": "Reset", "t": "Finish"}"#).unwrap();
+        let bindings = KeyBindings::with_overrides(overrides);
+
+        assert_eq!(bindings.button_for("x"), Some(ButtonId::Reset));
+        assert_eq!(bindings.button_for("t"), Some(ButtonId::Finish));
+        // the defaults are kept for keys that are not remapped
+        assert_eq!(bindings.button_for("r"), Some(ButtonId::Reset));
+        assert_eq!(bindings.button_for("q"), None);
+        assert_eq!(KeyBindings::default().button_for("x"), None);
+    }
+}
@@ -2,8 +2,10 @@ use std::{cell::RefCell, collections::VecDeque, rc::Rc};
 
 use app::AppImpl;
 use context::{CellSelector, Context, ContextImpl, Input};
-use event::{ButtonId, InputChange, InputId};
+use event::{ButtonId, InputChange, InputId, KeyBindings};
+use gloo::storage::Storage;
 use log::debug;
+use optimize::grid::Point;
 use wasm_bindgen::prelude::*;
 use web_sys::{CanvasRenderingContext2d, Document, HtmlCanvasElement, HtmlElement};
 
@@ -13,13 +15,15 @@ mod app;
 mod context;
 mod event;
 
+const STORAGE_KEY_KEY_BINDINGS: &str = "key_bindings";
+
 /// The main entry point for the application
 pub trait App {
     #[allow(async_fn_in_trait)]
     async fn render(&mut self, ctx: &Context, rendering_ctx: &CanvasRenderingContext2d);
 }
 
-fn register_onclick<T: FnMut() -> () + 'static>(id: &str, callback: T) {
+fn register_onclick<T: FnMut() + 'static>(id: &str, callback: T) {
     let closure_btn_clone = Closure::<dyn FnMut()>::new(callback);
     get_element_by_id::<HtmlElement>(id)
         .set_onclick(Some(closure_btn_clone.as_ref().unchecked_ref()));
@@ -29,7 +33,7 @@ fn register_onclick<T: FnMut() -> () + 'static>(id: &str, callback: T) {
 }
 
 /// register a change event on an element (e.g. any input element)
-fn register_change_event<E: JsCast, T: FnMut(&E) -> () + 'static>(id: &str, mut callback: T) {
+fn register_change_event<E: JsCast, T: FnMut(&E) + 'static>(id: &str, mut callback: T) {
     let closure = Closure::<dyn FnMut(web_sys::Event)>::new(move |event: web_sys::Event| {
         let element = event.current_target().unwrap().dyn_into::<E>().unwrap();
         callback(&element);
@@ -40,7 +44,7 @@ fn register_change_event<E: JsCast, T: FnMut(&E) -> () + 'static>(id: &str, mut
 
     closure.forget();
 }
-fn register_canvas_event<T: FnMut(web_sys::MouseEvent) -> () + 'static>(
+fn register_canvas_event<T: FnMut(web_sys::MouseEvent) + 'static>(
     canvas: &HtmlCanvasElement,
     event: &str,
     callback: T,
@@ -53,7 +57,7 @@ fn register_canvas_event<T: FnMut(web_sys::MouseEvent) -> () + 'static>(
 
     closure.forget();
 }
-fn register_canvas_scroll<T: FnMut(web_sys::WheelEvent) -> () + 'static>(
+fn register_canvas_scroll<T: FnMut(web_sys::WheelEvent) + 'static>(
     canvas: &HtmlCanvasElement,
     callback: T,
 ) {
@@ -66,6 +70,43 @@ fn register_canvas_scroll<T: FnMut(web_sys::WheelEvent) -> () + 'static>(
     closure.forget();
 }
 
+/// Loads the keyboard shortcuts, where the ones stored in the local storage (a json object from
+/// key to button) override the defaults
+fn load_key_bindings() -> KeyBindings {
+    match gloo::storage::LocalStorage::get(STORAGE_KEY_KEY_BINDINGS) {
+        Ok(overrides) => KeyBindings::with_overrides(overrides),
+        Err(_) => KeyBindings::default(),
+    }
+}
+
+/// Parses a point written as `row,col`
+fn parse_point(s: &str) -> Option<Point> {
+    let (row, col) = s.split_once(',')?;
+    Some(Point {
+        row: row.trim().parse().ok()?,
+        col: col.trim().parse().ok()?,
+    })
+}
+
+/// Reads the `start` and `goal` parameters from the query string of the page url. Returns None
+/// unless both are present and valid.
+fn parse_endpoints(query: &str) -> Option<(Point, Point)> {
+    let (mut start, mut goal) = (None, None);
+    for parameter in query.trim_start_matches('?').split('&') {
+        let Some((key, value)) = parameter.split_once('=') else {
+            continue;
+        };
+        // browsers may encode the comma
+        let value = value.replace("%2C", ",").replace("%2c", ",");
+        match key {
+            "start" => start = parse_point(&value),
+            "goal" => goal = parse_point(&value),
+            _ => {}
+        }
+    }
+    start.zip(goal)
+}
+
 fn window() -> web_sys::Window {
     web_sys::window().expect("no global `window` exists")
 }
@@ -79,13 +120,9 @@ fn document() -> Document {
 fn get_element_by_id<T: JsCast>(id: &str) -> T {
     document()
         .get_element_by_id(id)
-        .expect(&format!("should have {} on the page", id))
+        .unwrap_or_else(|| panic!("should have {} on the page", id))
         .dyn_into::<T>()
-        .expect(&format!(
-            "{} should be an `{}`",
-            id,
-            std::any::type_name::<T>()
-        ))
+        .unwrap_or_else(|_| panic!("{} should be an `{}`", id, std::any::type_name::<T>()))
 }
 
 fn main() {
@@ -144,17 +181,22 @@ fn main() {
             radio_invalid: get_element_by_id("cell-invalid"),
             radio_valid: get_element_by_id("cell-normal"),
             input_valid_cost: get_element_by_id("input-normal-cost"),
+            input_entry_cost: get_element_by_id("input-entry-cost"),
             radio_oneway: get_element_by_id("cell-oneway"),
             select_oneway: get_element_by_id("select-oneway"),
             span_oneway_target: get_element_by_id("span-oneway-target"),
+            input_target_cost: get_element_by_id("input-target-cost"),
         },
         output,
         input: Input::default(),
         events: VecDeque::new(),
         repaint_requested: false,
+        repaint_delay_ms: 0,
+        key_bindings: load_key_bindings(),
     });
 
     // create cells for storing the closure that redraws the canvas
+    #[allow(clippy::type_complexity)]
     let redraw: Rc<RefCell<Option<Closure<dyn FnMut()>>>> = Rc::new(RefCell::new(None));
 
     let request_repaint = {
@@ -240,6 +282,7 @@ fn main() {
                 y: event.offset_y(),
                 delta_x: event.delta_x(),
                 delta_y: event.delta_y(),
+                ctrl_pressed: event.ctrl_key(),
             });
             event.prevent_default();
             request_repaint();
@@ -304,7 +347,7 @@ fn main() {
         let request_repaint = request_repaint.clone();
         let closure = Closure::<dyn FnMut(web_sys::KeyboardEvent)>::new(
             move |event: web_sys::KeyboardEvent| {
-                if let Some(button) = event::ButtonId::from_key_code(&event.key()) {
+                if let Some(button) = context.button_for_key(&event.key()) {
                     context.push_event(Event::ButtonPressed(button));
                     request_repaint();
                 }
@@ -336,7 +379,14 @@ fn main() {
         let app = app.clone();
         let context = context.clone();
         async move {
-            *app.borrow_mut() = Some(AppImpl::new(&context).await);
+            let mut new_app = AppImpl::new(&context).await;
+
+            // a link like `?start=14,0&goal=44,51` selects the endpoints of the search
+            let query = window().location().search().unwrap_or_default();
+            if let Some((start, goal)) = parse_endpoints(&query) {
+                new_app.set_endpoints(start, goal, &context);
+            }
+            *app.borrow_mut() = Some(new_app);
         }
     });
 
@@ -352,6 +402,7 @@ fn main() {
             let request_repaint = request_repaint.clone();
             let rendering_context = rendering_context.clone();
             let app = app.clone();
+            #[allow(clippy::await_holding_refcell_ref)]
             wasm_bindgen_futures::spawn_local(async move {
                 if let Some(app) = app.borrow_mut().as_mut() {
                     debug!("redraw");
@@ -359,9 +410,21 @@ fn main() {
                     app.render(&context, &rendering_context).await;
 
                     // if the app requested to be repainted, schedule another call
-                    if context.is_repaint_requested() {
-                        debug!("repaint requested");
-                        request_repaint();
+                    match context.take_repaint_request() {
+                        Some(0) => {
+                            debug!("repaint requested");
+                            request_repaint();
+                        }
+                        Some(delay_ms) => {
+                            debug!("repaint requested in {} ms", delay_ms);
+                            window()
+                                .set_timeout_with_callback_and_timeout_and_arguments_0(
+                                    Closure::once_into_js(request_repaint).unchecked_ref(),
+                                    delay_ms as i32,
+                                )
+                                .expect("should register `setTimeout` OK");
+                        }
+                        None => {}
                     }
                 }
             });
@@ -370,3 +433,32 @@ fn main() {
     // initial call to the animation frame function
     request_repaint();
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_point() {
+        assert_eq!(parse_point("14,0"), Some(Point { row: 14, col: 0 }));
+        assert_eq!(parse_point(" 3 , 7 "), Some(Point { row: 3, col: 7 }));
+        assert_eq!(parse_point("14"), None);
+        assert_eq!(parse_point("14,"), None);
+        assert_eq!(parse_point("-1,2"), None);
+        assert_eq!(parse_point("a,b"), None);
+        assert_eq!(parse_point("1,2,3"), None);
+    }
+
+    #[test]
+    fn test_parse_endpoints() {
+        let expected = Some((Point { row: 14, col: 0 }, Point { row: 44, col: 51 }));
+        assert_eq!(parse_endpoints("?start=14,0&goal=44,51"), expected);
+        assert_eq!(
+            parse_endpoints("?goal=44%2C51&zoom=2&start=14%2c0"),
+            expected
+        );
+        assert_eq!(parse_endpoints("?start=14,0"), None);
+        assert_eq!(parse_endpoints("?start=14,0&goal=44"), None);
+        assert_eq!(parse_endpoints(""), None);
+    }
+}
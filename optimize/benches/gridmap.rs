@@ -1,7 +1,8 @@
-use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 use optimize::{
-    find::{MapTrait, PathFinder, PathFinderState},
+    find::{MapStorage, MapTrait, PathFinder, PathFinderState},
     grid::{GridMap, Point},
+    tiled::TiledMap,
     util::parse_img,
 };
 
@@ -40,5 +41,156 @@ pub fn map_scaled_factor(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, map_scaled_factor);
+pub fn generated_maze(c: &mut Criterion) {
+    let mut group = c.benchmark_group("generated_maze");
+    for size in [25, 51, 101, 201].iter() {
+        group.bench_with_input(BenchmarkId::from_parameter(size), size, |b, &size| {
+            let mut map = GridMap::generate_maze(size, size, size as u64);
+            map.randomize_costs(0.1, 5, size as u64);
+            let start = Point { row: 1, col: 1 };
+            let goal = Point {
+                row: size - 2,
+                col: size - 2,
+            };
+            b.iter_batched(
+                || map.create_storage(),
+                |storage| {
+                    let (res, _) =
+                        PathFinder::new(black_box(start), black_box(goal), black_box(storage), ())
+                            .finish(&map);
+                    assert!(matches!(res, PathFinderState::PathFound(_)));
+                },
+                criterion::BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+/// Runs the search once to find how many nodes it settles, so the benchmarks can report the
+/// throughput in nodes per second alongside the time
+fn nodes_visited(map: &GridMap<usize>, start: Point, goal: Point) -> u64 {
+    let mut finder = PathFinder::new(start, goal, map.create_storage(), ());
+    while let PathFinderState::Computing = finder.step(map) {}
+    assert!(matches!(finder.state(), PathFinderState::PathFound(_)));
+    finder.nodes_visited() as u64
+}
+
+fn bench_search(
+    group: &mut criterion::BenchmarkGroup<criterion::measurement::WallTime>,
+    id: BenchmarkId,
+    map: &GridMap<usize>,
+    start: Point,
+    goal: Point,
+) {
+    group.throughput(Throughput::Elements(nodes_visited(map, start, goal)));
+    group.bench_function(id, |b| {
+        b.iter_batched(
+            || map.create_storage(),
+            |storage| {
+                let (res, _) =
+                    PathFinder::new(black_box(start), black_box(goal), black_box(storage), ())
+                        .finish(map);
+                assert!(matches!(res, PathFinderState::PathFound(_)));
+            },
+            criterion::BatchSize::SmallInput,
+        )
+    });
+}
+
+/// The same maze size with different seeds, to catch regressions that only show on some shapes
+pub fn maze_seeds(c: &mut Criterion) {
+    let mut group = c.benchmark_group("maze_seeds");
+    let size = 101;
+    for seed in [1, 2, 3, 4] {
+        let mut map = GridMap::generate_maze(size, size, seed);
+        map.randomize_costs(0.1, 5, seed);
+        let goal = Point {
+            row: size - 2,
+            col: size - 2,
+        };
+        bench_search(
+            &mut group,
+            BenchmarkId::from_parameter(seed),
+            &map,
+            Point { row: 1, col: 1 },
+            goal,
+        );
+    }
+    group.finish();
+}
+
+/// Searches of increasing length on the same maze
+pub fn maze_distance(c: &mut Criterion) {
+    let mut group = c.benchmark_group("maze_distance");
+    let size = 201;
+    let mut map = GridMap::generate_maze(size, size, 42);
+    map.randomize_costs(0.1, 5, 42);
+    // rooms are on the odd rows and columns
+    for distance in [10, 25, 50, 99] {
+        let goal = Point {
+            row: 2 * distance + 1,
+            col: 2 * distance + 1,
+        };
+        bench_search(
+            &mut group,
+            BenchmarkId::from_parameter(distance),
+            &map,
+            Point { row: 1, col: 1 },
+            goal,
+        );
+    }
+    group.finish();
+}
+
+/// Checking and then marking every node of a hash map backed storage, once with a lookup for each
+/// and once with a single `entry` lookup as the search does
+pub fn sparse_storage(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sparse_storage");
+    let size = 201;
+    let map = TiledMap::new(size, size, 64, 64, |_| GridMap::new(64, 64, 1));
+    let points: Vec<Point> = (0..size)
+        .flat_map(|row| (0..size).map(move |col| Point { row, col }))
+        .collect();
+    group.throughput(Throughput::Elements(points.len() as u64));
+    group.bench_function("get_then_get_mut", |b| {
+        b.iter_batched(
+            || map.create_storage::<u32>(),
+            |mut storage| {
+                for &point in &points {
+                    if storage.get(point) == 0 {
+                        *storage.get_mut(point) = 1;
+                    }
+                }
+                storage
+            },
+            criterion::BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("entry", |b| {
+        b.iter_batched(
+            || map.create_storage::<u32>(),
+            |mut storage| {
+                for &point in &points {
+                    let value = storage.entry(point);
+                    if *value == 0 {
+                        *value = 1;
+                    }
+                }
+                storage
+            },
+            criterion::BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    map_scaled_factor,
+    generated_maze,
+    maze_seeds,
+    maze_distance,
+    sparse_storage
+);
 criterion_main!(benches);
@@ -1,29 +1,139 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::Parser;
+use image::{Rgba, RgbaImage};
 use optimize::{
-    find::{MapTrait, PathFinder},
-    grid::Point,
-    util::parse_img,
+    find::{MapTrait, PathFinder, PathFinderState, PathResult},
+    grid::{Cell, GridMap, Point},
+    util::parse_img_threshold,
 };
 
-#[allow(unused_must_use)]
+/// Finds the cheapest path between two cells of a map created from an image, where dark pixels
+/// are walls
+#[derive(Parser, Debug)]
+#[command(version)]
+struct Args {
+    /// The image to create the map from, one cell per pixel
+    #[arg(short, long)]
+    input: PathBuf,
+
+    /// The start cell as `row,col`
+    #[arg(short, long, value_parser = parse_point)]
+    start: Point,
+
+    /// The goal cell as `row,col`
+    #[arg(short, long, value_parser = parse_point)]
+    goal: Point,
+
+    /// Pixels with a first channel below this value are walls
+    #[arg(short, long, default_value_t = 128)]
+    threshold: u8,
+
+    /// Treat light pixels as walls instead
+    #[arg(long)]
+    invert: bool,
+
+    /// Write an image of the map with the path drawn on top, as svg or png depending on the
+    /// extension
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Give up the search after this many seconds
+    #[arg(long)]
+    timeout: Option<f64>,
+}
+
+fn parse_point(s: &str) -> Result<Point, anyhow::Error> {
+    let (row, col) = s
+        .split_once(',')
+        .ok_or_else(|| anyhow::anyhow!("Expected a point as `row,col`, got `{}`", s))?;
+    Ok(Point {
+        row: row.trim().parse()?,
+        col: col.trim().parse()?,
+    })
+}
+
 fn main() -> Result<(), anyhow::Error> {
-    let img = image::open("data/maze-03_6_threshold.png")?;
-    let map = parse_img(&img)?;
+    let args = Args::parse();
 
-    // implement brute force breadth-first search within the validity map
-    println!("{}", map);
+    let img = image::open(&args.input)?;
+    let map = parse_img_threshold(&img, args.threshold, args.invert)?;
 
-    let visited = map.create_storage();
+    let finder = PathFinder::new(args.start, args.goal, map.create_storage(), ());
+    let (state, _) = match args.timeout {
+        Some(seconds) => finder.finish_with_timeout(&map, Duration::from_secs_f64(seconds)),
+        None => finder.finish(&map),
+    };
+    let result = match state {
+        PathFinderState::PathFound(result) => result,
+        PathFinderState::NoPathFound(reason) => {
+            return Err(anyhow::anyhow!("No path found: {}", reason))
+        }
+        PathFinderState::Computing => {
+            return Err(anyhow::anyhow!("No path found within the timeout"))
+        }
+    };
 
-    let (res, visited) = PathFinder::new(
-        Point { row: 14, col: 0 },
-        Point { row: 44, col: 51 },
-        visited,
-        (),
-    )
-    .finish(&map);
+    println!("Total cost: {}", result.total_cost);
 
-    dbg!(res);
-    println!("{}", visited);
+    if let Some(output) = &args.output {
+        match output.extension().and_then(|e| e.to_str()) {
+            Some("svg") => std::fs::write(output, render_svg(&map, &result))?,
+            Some("png") => render_png(&map, &result).save(output)?,
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Unsupported output format, use .svg or .png: {}",
+                    output.display()
+                ))
+            }
+        }
+    }
 
     Ok(())
 }
+
+/// Renders the map with one pixel per cell, walls in black and the path in red
+fn render_png(map: &GridMap<usize>, result: &PathResult<usize, Point>) -> RgbaImage {
+    let mut image = RgbaImage::from_fn(map.columns as u32, map.rows as u32, |x, y| {
+        match map.cells[y as usize][x as usize] {
+            Cell::Invalid => Rgba([0, 0, 0, 255]),
+            _ => Rgba([255, 255, 255, 255]),
+        }
+    });
+    for p in &result.path {
+        image.put_pixel(p.col as u32, p.row as u32, Rgba([255, 0, 0, 255]));
+    }
+    image
+}
+
+/// Renders the map with one unit per cell, walls in black and the path as a red line
+fn render_svg(map: &GridMap<usize>, result: &PathResult<usize, Point>) -> String {
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {} {}">"#,
+        map.columns, map.rows
+    );
+    svg.push_str(r#"<rect width="100%" height="100%" fill="white"/>"#);
+    for (row, cells) in map.cells.iter().enumerate() {
+        for (col, cell) in cells.iter().enumerate() {
+            if !cell.is_passable() {
+                svg.push_str(&format!(
+                    r#"<rect x="{}" y="{}" width="1" height="1"/>"#,
+                    col, row
+                ));
+            }
+        }
+    }
+
+    let points: Vec<String> = result
+        .path
+        .iter()
+        .map(|p| format!("{},{}", p.col as f64 + 0.5, p.row as f64 + 0.5))
+        .collect();
+    svg.push_str(&format!(
+        r#"<polyline points="{}" fill="none" stroke="red" stroke-width="0.3"/>"#,
+        points.join(" ")
+    ));
+    svg.push_str("</svg>\n");
+    svg
+}
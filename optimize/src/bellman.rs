@@ -0,0 +1,285 @@
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::ops::Add;
+
+use crate::find::{
+    AbsoluteCost, MapStorage, MapTrait, NodeReference, PathResult, RelativeCost, Visited,
+    VisitedItem,
+};
+
+/// A cost that can be negative, for maps searched with [`BellmanFordPathFinder`]
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub struct Signed(pub isize);
+
+impl Add for Signed {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Signed(self.0 + rhs.0)
+    }
+}
+
+impl Display for Signed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl RelativeCost for Signed {}
+
+impl AbsoluteCost for Signed {
+    type CmpContext = ();
+
+    fn context_cmp(&self, other: &Self, _ctx: &Self::CmpContext) -> std::cmp::Ordering {
+        self.cmp(other)
+    }
+}
+
+/// Finds the cheapest path between two nodes on maps that may contain negative edge costs, where
+/// the Dijkstra based [`crate::find::PathFinder`] can return the wrong path. Slower than
+/// Dijkstra, so only use it when negative costs are possible.
+#[derive(Debug, Clone)]
+pub struct BellmanFordPathFinder<R, K> {
+    start: R,
+    goal: R,
+    context: K,
+}
+
+impl<R: NodeReference, K> BellmanFordPathFinder<R, K> {
+    pub fn new(start: R, goal: R, context: K) -> Self {
+        Self {
+            start,
+            goal,
+            context,
+        }
+    }
+
+    /// Runs the search, returning `Ok(None)` if the goal can not be reached and an error if there
+    /// is a cycle with a negative total cost reachable from the start (in which case there is no
+    /// cheapest path).
+    pub fn find<M>(&self, map: &M) -> Result<Option<PathResult<M::Cost, R>>, anyhow::Error>
+    where
+        M: MapTrait<Reference = R>,
+        M::Cost: AbsoluteCost<CmpContext = K>,
+    {
+        if !map.is_valid(self.start) || !map.is_valid(self.goal) {
+            return Ok(None);
+        }
+
+        // no cheapest path can have more edges than there are reachable nodes
+        let reachable = count_reachable(map, self.start);
+
+        let mut best = map.create_storage::<Visited<M::Cost, R>>();
+        let mut edges = map.create_storage::<usize>();
+        let mut queued = map.create_storage::<bool>();
+
+        *best.get_mut(self.start) = Visited(Some(VisitedItem {
+            cost: Default::default(),
+            from: None,
+        }));
+        *queued.get_mut(self.start) = true;
+        let mut queue = VecDeque::from([self.start]);
+
+        // only relax the edges of nodes whose cost changed (the queue based variant)
+        while let Some(node) = queue.pop_front() {
+            *queued.get_mut(node) = false;
+            let Some(VisitedItem { cost, .. }) = *best.get(node) else {
+                unreachable!("only reached nodes are queued");
+            };
+
+            for (neighbor, move_cost) in map.neighbors_of(node) {
+                let Some(current) = best.try_get_mut(neighbor) else {
+                    continue;
+                };
+                let new_cost = cost.accumulate(move_cost);
+                let better = match **current {
+                    Some(item) => new_cost.context_cmp(&item.cost, &self.context) == Ordering::Less,
+                    None => true,
+                };
+                if !better {
+                    continue;
+                }
+
+                *current = Visited(Some(VisitedItem {
+                    cost: new_cost,
+                    from: Some(node),
+                }));
+                let count = edges.get(node) + 1;
+                if count >= reachable {
+                    return Err(anyhow::anyhow!(
+                        "Found a cycle with negative cost reachable from the start"
+                    ));
+                }
+                *edges.get_mut(neighbor) = count;
+
+                if !queued.get(neighbor) {
+                    *queued.get_mut(neighbor) = true;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        let Some(VisitedItem {
+            cost: total_cost, ..
+        }) = *best.get(self.goal)
+        else {
+            return Ok(None);
+        };
+
+        let mut path = vec![self.goal];
+        while let Some(VisitedItem {
+            from: Some(from), ..
+        }) = *best.get(*path.last().unwrap())
+        {
+            path.push(from);
+        }
+        path.reverse();
+
+        Ok(Some(PathResult {
+            path,
+            start: self.start,
+            goal: self.goal,
+            total_cost,
+        }))
+    }
+}
+
+/// Counts the nodes reachable from `start`, including `start` itself
+fn count_reachable<M: MapTrait>(map: &M, start: M::Reference) -> usize {
+    let mut seen = map.create_storage::<bool>();
+    *seen.get_mut(start) = true;
+    let mut stack = vec![start];
+    let mut count = 0;
+    while let Some(node) = stack.pop() {
+        count += 1;
+        for (neighbor, _) in map.neighbors_of(node) {
+            if let Some(seen @ false) = seen.try_get_mut(neighbor) {
+                *seen = true;
+                stack.push(neighbor);
+            }
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod test {
+    use std::any::Any;
+
+    use super::*;
+    use crate::find::{PathFinder, PathFinderState};
+
+    /// The index of a node of a [`Graph`]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Node(usize);
+
+    impl NodeReference for Node {}
+
+    /// A small directed graph with nodes `0..n`
+    struct Graph(Vec<Vec<(Node, Signed)>>);
+
+    struct VecStorage<T>(Vec<T>);
+
+    impl<T: Copy + 'static> MapStorage<T> for VecStorage<T> {
+        type Reference = Node;
+
+        fn is_valid(&self, node: Node) -> bool {
+            node.0 < self.0.len()
+        }
+
+        fn get(&self, node: Node) -> T {
+            self.0[node.0]
+        }
+
+        fn get_mut(&mut self, node: Node) -> &mut T {
+            &mut self.0[node.0]
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    impl MapTrait for Graph {
+        type Reference = Node;
+        type Storage<T: Default + Copy + Clone + 'static> = VecStorage<T>;
+        type Cost = Signed;
+
+        fn is_valid(&self, node: Node) -> bool {
+            node.0 < self.0.len()
+        }
+
+        fn neighbors_of(&self, node: Node) -> impl Iterator<Item = (Node, Signed)> {
+            self.0[node.0].iter().copied()
+        }
+
+        fn create_storage<T: Default + Copy + Clone + 'static>(&self) -> Self::Storage<T> {
+            VecStorage(vec![T::default(); self.0.len()])
+        }
+    }
+
+    // nodes: 0 = start, 1 = a, 2 = b, 3 = goal
+    fn create_graph() -> Graph {
+        let edges: [&[(usize, isize)]; 4] = [&[(1, 2), (2, 5)], &[(3, 1)], &[(1, -4)], &[]];
+        Graph(
+            edges
+                .iter()
+                .map(|edges| edges.iter().map(|&(n, c)| (Node(n), Signed(c))).collect())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_negative_edge() {
+        let graph = create_graph();
+
+        // Dijkstra settles the goal before it sees the negative edge
+        let finder = PathFinder::new(Node(0), Node(3), graph.create_storage(), ());
+        assert!(matches!(
+            finder.finish(&graph).0,
+            PathFinderState::PathFound(PathResult {
+                total_cost: Signed(3),
+                ..
+            })
+        ));
+
+        let result = BellmanFordPathFinder::new(Node(0), Node(3), ())
+            .find(&graph)
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.total_cost, Signed(2));
+        assert_eq!(result.path, vec![Node(0), Node(2), Node(1), Node(3)]);
+
+        // nothing leads back to the start
+        assert_eq!(
+            BellmanFordPathFinder::new(Node(3), Node(0), ())
+                .find(&graph)
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_negative_cycle() {
+        let mut graph = create_graph();
+        // a -> b -> a costs 1 - 4 = -3
+        graph.0[1].push((Node(2), Signed(1)));
+
+        assert!(BellmanFordPathFinder::new(Node(0), Node(3), ())
+            .find(&graph)
+            .is_err());
+    }
+}
@@ -0,0 +1,160 @@
+use crate::find::{HeuristicMap, MapTrait};
+use crate::grid::{grid_neighbors, Cell, GridMap, GridStorage, Point, Topology};
+
+const BITS: usize = u64::BITS as usize;
+
+/// A map for pure mazes where every cell is either passable with a cost of 1, or a wall. Stores
+/// one bit per cell, which makes it much smaller and more cache friendly than a [`GridMap`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitGridMap {
+    pub rows: usize,
+    pub columns: usize,
+    bits: Vec<u64>,
+}
+
+impl BitGridMap {
+    /// Creates a map where all cells are passable
+    pub fn new(rows: usize, columns: usize) -> Self {
+        Self {
+            rows,
+            columns,
+            bits: vec![u64::MAX; (rows * columns).div_ceil(BITS)],
+        }
+    }
+
+    fn index(&self, point: Point) -> usize {
+        assert!(
+            point.row < self.rows && point.col < self.columns,
+            "{:?} is outside the map",
+            point
+        );
+        point.row * self.columns + point.col
+    }
+
+    pub fn is_passable(&self, point: Point) -> bool {
+        let index = self.index(point);
+        self.bits[index / BITS] & (1 << (index % BITS)) != 0
+    }
+
+    pub fn set_passable(&mut self, point: Point, passable: bool) {
+        let index = self.index(point);
+        if passable {
+            self.bits[index / BITS] |= 1 << (index % BITS);
+        } else {
+            self.bits[index / BITS] &= !(1 << (index % BITS));
+        }
+    }
+}
+
+impl<U> TryFrom<&GridMap<usize, U>> for BitGridMap {
+    type Error = anyhow::Error;
+
+    /// Fails if the map contains anything other than invalid cells and valid cells with a cost of
+    /// 1 and no entry cost
+    fn try_from(map: &GridMap<usize, U>) -> Result<Self, Self::Error> {
+        let mut bits = Self::new(map.rows, map.columns);
+        for (row, cells) in map.cells.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                let passable = match cell {
+                    Cell::Invalid => false,
+                    Cell::Valid {
+                        cost: 1,
+                        entry_cost: None,
+                    } => true,
+                    _ => {
+                        return Err(anyhow::anyhow!(
+                            "Cell at {}:{} can not be represented as a bit: {:?}",
+                            row,
+                            col,
+                            cell
+                        ))
+                    }
+                };
+                bits.set_passable(Point { row, col }, passable);
+            }
+        }
+        Ok(bits)
+    }
+}
+
+impl MapTrait for BitGridMap {
+    type Reference = Point;
+    type Storage<T: Default + Copy + Clone + 'static> = GridStorage<T>;
+    type Cost = usize;
+
+    fn is_valid(&self, node: Self::Reference) -> bool {
+        node.row < self.rows && node.col < self.columns
+    }
+
+    fn is_passable(&self, node: Self::Reference) -> bool {
+        self.is_valid(node) && BitGridMap::is_passable(self, node)
+    }
+
+    fn neighbors_of(
+        &self,
+        node: Self::Reference,
+    ) -> impl Iterator<Item = (Self::Reference, usize)> {
+        grid_neighbors(self.rows, self.columns, Topology::Orthogonal, node, |p| {
+            if self.is_passable(p) {
+                Cell::Valid {
+                    cost: 1,
+                    entry_cost: None,
+                }
+            } else {
+                Cell::Invalid
+            }
+        })
+    }
+
+    fn create_storage<T: Default + Copy + Clone + 'static>(&self) -> Self::Storage<T> {
+        GridStorage(vec![vec![Default::default(); self.columns]; self.rows])
+    }
+}
+
+/// Every move costs exactly 1, so the manhattan distance is a lower bound
+impl HeuristicMap for BitGridMap {
+    fn heuristic(&self, from: Point, to: Point) -> usize {
+        from.row.abs_diff(to.row) + from.col.abs_diff(to.col)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::find::{PathFinder, PathFinderState};
+    use crate::grid::test::create_basic_map;
+    use crate::grid::Direction;
+
+    #[test]
+    fn test_bit_grid_matches_grid_map() {
+        let map = create_basic_map();
+        let bits = BitGridMap::try_from(&map).unwrap();
+        assert_eq!(bits.bits.len(), 1);
+        assert!(!bits.is_passable(Point { row: 0, col: 0 }));
+        assert!(bits.is_passable(Point { row: 1, col: 1 }));
+
+        let (start, goal) = (Point { row: 1, col: 1 }, Point { row: 1, col: 5 });
+        let (expected, _) = PathFinder::new(start, goal, map.create_storage(), ()).finish(&map);
+        let (state, _) = PathFinder::new(start, goal, bits.create_storage(), ()).finish(&bits);
+        assert!(matches!(state, PathFinderState::PathFound(_)));
+        assert_eq!(state, expected);
+    }
+
+    #[test]
+    fn test_bit_grid_rejects_costs() {
+        let mut map = create_basic_map();
+        map.cells[1][1] = Cell::Valid {
+            cost: 2,
+            entry_cost: None,
+        };
+        assert!(BitGridMap::try_from(&map).is_err());
+
+        map.cells[1][1] = Cell::OneWay {
+            cost: 1,
+            direction: Direction::Down,
+            target: None,
+            target_cost: None,
+        };
+        assert!(BitGridMap::try_from(&map).is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use std::hash::Hash;
+
+use crate::find::{HeuristicMap, MapTrait, RelativeCost};
+use crate::grid::{GridMap, GridStorage, Point, Topology};
+
+/// The moves of a [`GridMap`] precomputed in compressed sparse row form, so searches iterate a
+/// slice instead of looking at the cells around every node. Worth it for many searches on the
+/// same map, e.g. batches of queries. The cache is a snapshot: it does not see later changes to
+/// the map, use [`AdjacencyCache::is_current`] to tell if it needs to be rebuilt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdjacencyCache<C: RelativeCost> {
+    pub rows: usize,
+    pub columns: usize,
+    topology: Topology,
+    /// the moves out of the cell with index `i` (row by row) are `edges[offsets[i]..offsets[i+1]]`
+    offsets: Vec<usize>,
+    edges: Vec<(Point, C)>,
+    /// whether the cell with index `i` can be moved into
+    passable: Vec<bool>,
+    /// the [`GridMap::content_hash`] of the map the cache was built from
+    content_hash: u64,
+}
+
+impl<C: RelativeCost + Hash> AdjacencyCache<C> {
+    pub fn new<U>(map: &GridMap<C, U>) -> Self {
+        let mut offsets = Vec::with_capacity(map.rows * map.columns + 1);
+        let mut edges = Vec::new();
+        let mut passable = Vec::with_capacity(map.rows * map.columns);
+        offsets.push(0);
+        for row in 0..map.rows {
+            for col in 0..map.columns {
+                edges.extend(map.neighbors_of(Point { row, col }));
+                offsets.push(edges.len());
+                passable.push(map.is_passable(Point { row, col }));
+            }
+        }
+
+        Self {
+            rows: map.rows,
+            columns: map.columns,
+            topology: map.topology,
+            offsets,
+            edges,
+            passable,
+            content_hash: map.content_hash(),
+        }
+    }
+
+    /// Whether the cache still matches the map, i.e. the map has not changed since the cache was
+    /// built from it
+    pub fn is_current<U>(&self, map: &GridMap<C, U>) -> bool {
+        self.content_hash == map.content_hash()
+    }
+
+    /// The total number of moves between cells
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+}
+
+impl<C: RelativeCost> MapTrait for AdjacencyCache<C> {
+    type Reference = Point;
+    type Storage<T: Default + Copy + Clone + 'static> = GridStorage<T>;
+    type Cost = C;
+
+    fn is_valid(&self, node: Self::Reference) -> bool {
+        node.row < self.rows && node.col < self.columns
+    }
+
+    fn is_passable(&self, node: Self::Reference) -> bool {
+        self.is_valid(node) && self.passable[node.row * self.columns + node.col]
+    }
+
+    fn neighbors_of(&self, node: Self::Reference) -> impl Iterator<Item = (Self::Reference, C)> {
+        let index = node.row * self.columns + node.col;
+        let edges = match self.is_valid(node) {
+            true => &self.edges[self.offsets[index]..self.offsets[index + 1]],
+            false => &[],
+        };
+        edges.iter().copied()
+    }
+
+    fn create_storage<T: Default + Copy + Clone + 'static>(&self) -> Self::Storage<T> {
+        GridStorage(vec![vec![Default::default(); self.columns]; self.rows])
+    }
+}
+
+/// The same estimate as for the [`GridMap`] the cache was built from
+impl HeuristicMap for AdjacencyCache<usize> {
+    fn heuristic(&self, from: Point, to: Point) -> usize {
+        self.topology.min_moves(from, to)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::find::PathFinder;
+    use crate::grid::test::create_basic_map;
+    use crate::grid::Cell;
+
+    #[test]
+    fn test_cached_searches_match() {
+        let mut map = GridMap::generate_maze(31, 41, 9);
+        map.randomize_costs(0.3, 5, 9);
+        let cache = map.build_adjacency_cache();
+        assert!(cache.is_current(&map));
+
+        let pairs = [
+            (Point { row: 1, col: 1 }, Point { row: 29, col: 39 }),
+            (Point { row: 15, col: 21 }, Point { row: 1, col: 39 }),
+            (Point { row: 29, col: 1 }, Point { row: 0, col: 0 }),
+        ];
+        for (start, goal) in pairs {
+            let (uncached, _) = PathFinder::new(start, goal, map.create_storage(), ())
+                .with_heuristic(1.0)
+                .finish(&map);
+            let (cached, _) = PathFinder::new(start, goal, cache.create_storage(), ())
+                .with_heuristic(1.0)
+                .finish(&cache);
+            assert_eq!(cached, uncached);
+        }
+
+        // the cache does not follow changes to the map
+        let mut map = create_basic_map();
+        let cache = map.build_adjacency_cache();
+        map.cells[3][3] = Cell::Invalid;
+        assert!(!cache.is_current(&map));
+        assert_ne!(map.build_adjacency_cache().edge_count(), cache.edge_count());
+    }
+}
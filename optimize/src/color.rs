@@ -0,0 +1,128 @@
+use image::Rgba;
+
+/// Maps cell costs to colors by linearly interpolating between a set of color stops. Costs below
+/// the first stop or above the last stop get the color of the closest stop.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorRamp {
+    stops: Vec<(usize, Rgba<u8>)>,
+}
+
+impl ColorRamp {
+    /// Creates a new ramp from the provided stops. The stops are sorted by cost.
+    pub fn new(mut stops: Vec<(usize, Rgba<u8>)>) -> Self {
+        assert!(!stops.is_empty(), "a color ramp needs at least one stop");
+        stops.sort_by_key(|(cost, _)| *cost);
+        Self { stops }
+    }
+
+    pub fn stops(&self) -> &[(usize, Rgba<u8>)] {
+        &self.stops
+    }
+
+    /// Returns the color to use for the provided cost
+    pub fn color_for(&self, cost: usize) -> Rgba<u8> {
+        let (first_cost, first_color) = self.stops[0];
+        if cost <= first_cost {
+            return first_color;
+        }
+
+        for window in self.stops.windows(2) {
+            let [(low_cost, low_color), (high_cost, high_color)] = [window[0], window[1]];
+            if cost <= high_cost {
+                let t = (cost - low_cost) as f64 / (high_cost - low_cost) as f64;
+                let mut color = [0; 4];
+                for (i, c) in color.iter_mut().enumerate() {
+                    let (low, high) = (low_color.0[i] as f64, high_color.0[i] as f64);
+                    *c = (low + (high - low) * t).round() as u8;
+                }
+                return Rgba(color);
+            }
+        }
+
+        self.stops[self.stops.len() - 1].1
+    }
+
+    /// Returns `count` evenly spaced costs from the first to the last stop together with their
+    /// colors, e.g. for the swatches of a legend. Costs are rounded to the nearest integer, so
+    /// narrow ramps can give the same cost more than once.
+    pub fn sample(&self, count: usize) -> Vec<(usize, Rgba<u8>)> {
+        let (low, high) = (self.stops[0].0, self.stops[self.stops.len() - 1].0);
+        (0..count)
+            .map(|i| {
+                let t = match count {
+                    1 => 0.0,
+                    _ => i as f64 / (count - 1) as f64,
+                };
+                let cost = (low as f64 + (high - low) as f64 * t).round() as usize;
+                (cost, self.color_for(cost))
+            })
+            .collect()
+    }
+}
+
+impl Default for ColorRamp {
+    /// White for the lowest cost, going through yellow to red for expensive cells
+    fn default() -> Self {
+        Self::new(vec![
+            (1, Rgba([255, 255, 255, 255])),
+            (2, Rgba([255, 255, 0, 255])),
+            (10, Rgba([255, 0, 0, 255])),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_color_ramp() {
+        let ramp = ColorRamp::new(vec![
+            (10, Rgba([0, 0, 0, 255])),
+            (0, Rgba([255, 255, 255, 255])),
+            (20, Rgba([0, 100, 200, 55])),
+        ]);
+
+        assert_eq!(ramp.color_for(0), Rgba([255, 255, 255, 255]));
+        assert_eq!(ramp.color_for(5), Rgba([128, 128, 128, 255]));
+        assert_eq!(ramp.color_for(10), Rgba([0, 0, 0, 255]));
+        assert_eq!(ramp.color_for(15), Rgba([0, 50, 100, 155]));
+        assert_eq!(ramp.color_for(20), Rgba([0, 100, 200, 55]));
+        assert_eq!(ramp.color_for(100), Rgba([0, 100, 200, 55]));
+    }
+
+    #[test]
+    fn test_color_ramp_default() {
+        let ramp = ColorRamp::default();
+        assert_eq!(ramp.color_for(1), Rgba([255, 255, 255, 255]));
+        assert_eq!(ramp.color_for(2), Rgba([255, 255, 0, 255]));
+        assert_eq!(ramp.color_for(6), Rgba([255, 128, 0, 255]));
+        assert_eq!(ramp.color_for(50), Rgba([255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_color_ramp_sample() {
+        let ramp = ColorRamp::new(vec![
+            (0, Rgba([255, 255, 255, 255])),
+            (20, Rgba([0, 100, 200, 55])),
+        ]);
+
+        assert_eq!(
+            ramp.sample(3),
+            vec![
+                (0, Rgba([255, 255, 255, 255])),
+                (10, Rgba([128, 178, 228, 155])),
+                (20, Rgba([0, 100, 200, 55])),
+            ]
+        );
+        assert_eq!(ramp.sample(1), vec![(0, Rgba([255, 255, 255, 255]))]);
+        assert!(ramp.sample(0).is_empty());
+
+        let costs: Vec<_> = ColorRamp::default()
+            .sample(4)
+            .into_iter()
+            .map(|(cost, _)| cost)
+            .collect();
+        assert_eq!(costs, vec![1, 4, 7, 10]);
+    }
+}
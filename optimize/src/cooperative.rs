@@ -0,0 +1,404 @@
+use std::any::Any;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::hash::Hash;
+
+use crate::find::{
+    backtrack, settle, AbsoluteCost, HeuristicMap, MapStorage, NodeReference, PathResult, ToVisit,
+    Visited,
+};
+
+/// Keeps track of which nodes are occupied at which time steps
+#[derive(Debug, Clone)]
+pub struct ReservationTable<R: Hash + Eq> {
+    reserved: HashSet<(R, usize)>,
+    /// nodes that are occupied forever from the given time, i.e. where an agent stopped
+    parked: HashMap<R, usize>,
+    /// the last time step each node is reserved at
+    last_reserved: HashMap<R, usize>,
+}
+
+impl<R: Hash + Eq + Copy> Default for ReservationTable<R> {
+    fn default() -> Self {
+        Self {
+            reserved: HashSet::new(),
+            parked: HashMap::new(),
+            last_reserved: HashMap::new(),
+        }
+    }
+}
+
+impl<R: Hash + Eq + Copy> ReservationTable<R> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reserves the node at the given time step
+    pub fn reserve(&mut self, node: R, time: usize) {
+        self.reserved.insert((node, time));
+        let last = self.last_reserved.entry(node).or_insert(time);
+        *last = (*last).max(time);
+    }
+
+    /// Reserves the node for all time steps starting at `time`
+    pub fn park(&mut self, node: R, time: usize) {
+        self.parked.insert(node, time);
+    }
+
+    /// Check if the node is occupied at the given time step
+    pub fn is_reserved(&self, node: R, time: usize) -> bool {
+        self.reserved.contains(&(node, time))
+            || self.parked.get(&node).is_some_and(|&from| time >= from)
+    }
+
+    /// Check if the node is free at `time` and all time steps after it
+    fn is_free_from(&self, node: R, time: usize) -> bool {
+        !self.parked.contains_key(&node)
+            && self.last_reserved.get(&node).map_or(true, |&t| t < time)
+    }
+
+    /// Reserves all nodes along a path, where the node at index `i` is occupied at time `i`. The
+    /// last node stays occupied after the path has ended.
+    pub fn reserve_path(&mut self, path: &[R]) {
+        for (time, node) in path.iter().enumerate() {
+            self.reserve(*node, time);
+        }
+        if let Some(last) = path.last() {
+            self.park(*last, path.len() - 1);
+        }
+    }
+}
+
+/// A node of the map at a time step, the nodes of the space-time search
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct SpaceTime<R> {
+    node: R,
+    time: usize,
+}
+
+impl<R: NodeReference> NodeReference for SpaceTime<R> {}
+
+/// A MapStorage over the nodes of the map at the time steps up to `max_time`, which only
+/// allocates memory for the ones that have been written to
+struct SpaceTimeStorage<R, T> {
+    max_time: usize,
+    values: HashMap<SpaceTime<R>, T>,
+}
+
+impl<R: NodeReference + Hash, T: Copy + Default + 'static> MapStorage<T>
+    for SpaceTimeStorage<R, T>
+{
+    type Reference = SpaceTime<R>;
+
+    fn is_valid(&self, node: Self::Reference) -> bool {
+        node.time <= self.max_time
+    }
+
+    fn get(&self, node: Self::Reference) -> T {
+        assert!(
+            self.is_valid(node),
+            "time {} is after the search ends",
+            node.time
+        );
+        self.values.get(&node).copied().unwrap_or_default()
+    }
+
+    fn get_mut(&mut self, node: Self::Reference) -> &mut T {
+        assert!(
+            self.is_valid(node),
+            "time {} is after the search ends",
+            node.time
+        );
+        self.values.entry(node).or_default()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Finds a path through space and time from `start` to `goal` that avoids the reservations. The
+/// returned path contains one node per time step, so waiting shows up as a repeated node. Returns
+/// None if the goal cannot be reached within `max_time` time steps.
+///
+/// The search is A* over the nodes of the map at each time step, ordered by the costs of the map
+/// and estimated with its heuristic. Waiting is free, so the path is the cheapest one that avoids
+/// the reservations, and of the equally cheap ones the one that arrives the earliest.
+pub fn find_path_space_time<M, K>(
+    map: &M,
+    start: M::Reference,
+    goal: M::Reference,
+    reservations: &ReservationTable<M::Reference>,
+    max_time: usize,
+    context: K,
+) -> Option<PathResult<M::Cost, M::Reference>>
+where
+    M: HeuristicMap,
+    M::Reference: Hash,
+    M::Cost: AbsoluteCost<CmpContext = K>,
+    K: Clone,
+{
+    if reservations.is_reserved(start, 0) {
+        return None;
+    }
+
+    let mut visited = SpaceTimeStorage {
+        max_time,
+        values: HashMap::new(),
+    };
+    // ties are broken towards fewer moves, and every move or wait takes one time step, so the
+    // earliest arrival is settled first
+    let origin = SpaceTime {
+        node: start,
+        time: 0,
+    };
+    let mut visit_list = BinaryHeap::from([ToVisit::start(origin, context)]);
+
+    while let Some(visit) = visit_list.pop() {
+        if !settle(&mut visited, &visit) {
+            continue;
+        }
+
+        let SpaceTime { node, time } = visit.point;
+        if node == goal && reservations.is_free_from(goal, time + 1) {
+            return Some(PathResult {
+                path: backtrack(&visited, visit.point)
+                    .into_iter()
+                    .map(|state| state.node)
+                    .collect(),
+                start,
+                goal,
+                total_cost: visit.cost,
+            });
+        }
+
+        if time >= max_time {
+            continue;
+        }
+
+        let wait = std::iter::once((node, M::Cost::default()));
+        for (next, move_cost) in map.neighbors_of(node).chain(wait) {
+            let next_time = time + 1;
+            if reservations.is_reserved(next, next_time) {
+                continue;
+            }
+            // do not swap places with an agent moving the other way
+            if next != node
+                && reservations.is_reserved(next, time)
+                && reservations.is_reserved(node, next_time)
+            {
+                continue;
+            }
+
+            let state = SpaceTime {
+                node: next,
+                time: next_time,
+            };
+            if matches!(visited.try_get(state), Some(Visited(None))) {
+                let mut next_visit = visit.next(state, move_cost);
+                next_visit.priority = next_visit.cost + map.heuristic(next, goal);
+                visit_list.push(next_visit);
+            }
+        }
+    }
+
+    None
+}
+
+/// Plans paths for multiple agents, given as `(start, goal)` pairs, so that no two agents occupy
+/// the same node at the same time or swap places (cooperative pathfinding). Agents are planned in
+/// order, so earlier agents get priority, and the nodes they occupy at each time step are
+/// reserved for the following agents. Each move or wait takes one time step, and every agent
+/// takes its cheapest path (see [`find_path_space_time`]). Each result is None if that agent
+/// could not reach its goal within `max_time`.
+///
+/// An agent that can not reach its goal stays at its start the whole time. The agents before it
+/// were planned without knowing that, so they are planned again with its start blocked, until no
+/// more agents fail.
+pub fn find_path_cooperative<M, K>(
+    map: &M,
+    agents: &[(M::Reference, M::Reference)],
+    max_time: usize,
+    context: K,
+) -> Vec<Option<PathResult<M::Cost, M::Reference>>>
+where
+    M: HeuristicMap,
+    M::Reference: Hash,
+    M::Cost: AbsoluteCost<CmpContext = K>,
+    K: Clone,
+{
+    // the agents that can not reach their goals, which stay at their starts
+    let mut stuck = vec![false; agents.len()];
+    'plan: loop {
+        let mut reservations = ReservationTable::new();
+        for (&(start, _), &stuck) in agents.iter().zip(&stuck) {
+            if stuck {
+                reservations.park(start, 0);
+            } else {
+                // the start positions of all agents are occupied from the beginning
+                reservations.reserve(start, 0);
+            }
+        }
+
+        let mut results = Vec::with_capacity(agents.len());
+        for (i, &(start, goal)) in agents.iter().enumerate() {
+            if stuck[i] {
+                results.push(None);
+                continue;
+            }
+            // the agent may use its own start position
+            reservations.reserved.remove(&(start, 0));
+            match find_path_space_time(map, start, goal, &reservations, max_time, context.clone()) {
+                Some(result) => {
+                    reservations.reserve_path(&result.path);
+                    results.push(Some(result));
+                }
+                None => {
+                    stuck[i] = true;
+                    continue 'plan;
+                }
+            }
+        }
+        return results;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::grid::test::find_path;
+    use crate::grid::{Cell, GridMap, Point};
+
+    /// Returns the position of an agent at the given time, agents stay at their goal
+    fn position_at(path: &[Point], time: usize) -> Point {
+        path[time.min(path.len() - 1)]
+    }
+
+    /// Checks that no two agents are at the same node at the same time, where agents without a
+    /// path stay at their start
+    fn assert_no_collisions(
+        agents: &[(Point, Point)],
+        results: &[Option<PathResult<usize, Point>>],
+    ) {
+        let paths: Vec<Vec<Point>> = agents
+            .iter()
+            .zip(results)
+            .map(|(&(start, _), result)| match result {
+                Some(result) => result.path.clone(),
+                None => vec![start],
+            })
+            .collect();
+        let end = paths.iter().map(Vec::len).max().unwrap_or_default();
+        for t in 0..end {
+            let mut occupied = HashSet::new();
+            for path in &paths {
+                let p = position_at(path, t);
+                assert!(
+                    occupied.insert(p),
+                    "agents collide on {:?} at time {}",
+                    p,
+                    t
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_cooperative_head_on() {
+        // a corridor with a single passing place
+        let mut map = GridMap::new(2, 5, 1);
+        for col in [0, 1, 2, 4] {
+            map.cells[1][col] = Cell::Invalid;
+        }
+
+        let a = (Point { row: 0, col: 0 }, Point { row: 0, col: 4 });
+        let b = (Point { row: 0, col: 4 }, Point { row: 0, col: 0 });
+        let results = find_path_cooperative(&map, &[a, b], 20, ());
+        assert_no_collisions(&[a, b], &results);
+
+        let paths: Vec<Vec<Point>> = results.into_iter().map(|r| r.unwrap().path).collect();
+        assert_eq!(paths[0].first(), Some(&a.0));
+        assert_eq!(paths[0].last(), Some(&a.1));
+        assert_eq!(paths[1].first(), Some(&b.0));
+        assert_eq!(paths[1].last(), Some(&b.1));
+
+        // the first agent goes straight through, the second has to give way
+        assert_eq!(paths[0].len(), 5);
+        assert!(paths[1].contains(&Point { row: 1, col: 3 }));
+
+        let end = paths[0].len().max(paths[1].len());
+        for t in 0..end {
+            let (pa, pb) = (position_at(&paths[0], t), position_at(&paths[1], t));
+            assert_ne!(pa, pb, "agents collide at time {}", t);
+
+            if t + 1 < end {
+                let (na, nb) = (position_at(&paths[0], t + 1), position_at(&paths[1], t + 1));
+                assert!(!(pa == nb && pb == na), "agents swap at time {}", t);
+            }
+        }
+    }
+
+    #[test]
+    fn test_cooperative_blocked() {
+        // a corridor without a passing place, the second agent can never get past and so blocks
+        // the goal of the first one
+        let map = GridMap::new(1, 5, 1);
+        let a = (Point { row: 0, col: 0 }, Point { row: 0, col: 4 });
+        let b = (Point { row: 0, col: 4 }, Point { row: 0, col: 0 });
+        let results = find_path_cooperative(&map, &[a, b], 20, ());
+        assert!(results[0].is_none());
+        assert!(results[1].is_none());
+        assert_no_collisions(&[a, b], &results);
+    }
+
+    #[test]
+    fn test_cooperative_stuck_agent() {
+        // the last agent can not reach its goal (a wall), so the first has to go around it
+        let mut map = GridMap::new(3, 5, 1);
+        map.cells[0][2] = Cell::Invalid;
+        let a = (Point { row: 1, col: 0 }, Point { row: 1, col: 4 });
+        let b = (Point { row: 2, col: 4 }, Point { row: 0, col: 4 });
+        let c = (Point { row: 1, col: 2 }, Point { row: 0, col: 2 });
+        let agents = [a, b, c];
+        let results = find_path_cooperative(&map, &agents, 20, ());
+
+        assert!(results[0].is_some());
+        assert!(results[1].is_some());
+        assert!(results[2].is_none());
+        assert!(!results[0].as_ref().unwrap().path.contains(&c.0));
+        assert_no_collisions(&agents, &results);
+    }
+
+    #[test]
+    fn test_space_time_cheapest() {
+        // leaving the middle of the top row is expensive, so going around is cheaper but slower
+        let mut map = GridMap::new(3, 5, 1);
+        map.cells[0][2] = Cell::Valid {
+            cost: 10,
+            entry_cost: None,
+        };
+        let (start, goal) = (Point { row: 0, col: 0 }, Point { row: 0, col: 4 });
+
+        let result =
+            find_path_space_time(&map, start, goal, &ReservationTable::new(), 20, ()).unwrap();
+        assert_eq!(result.total_cost, 6);
+        assert_eq!(result.total_cost, find_path(&map, start, goal).total_cost);
+        assert_eq!(result.path.len(), 7);
+        assert!(!result.path.contains(&Point { row: 0, col: 2 }));
+
+        // waiting is free, so an agent crossing the direct route makes this one wait for it
+        let map = GridMap::new(2, 3, 1);
+        let (start, goal) = (Point { row: 0, col: 0 }, Point { row: 0, col: 2 });
+        let mut reservations = ReservationTable::new();
+        reservations.reserve_path(&[
+            Point { row: 1, col: 1 },
+            Point { row: 0, col: 1 },
+            Point { row: 1, col: 1 },
+        ]);
+        let result = find_path_space_time(&map, start, goal, &reservations, 20, ()).unwrap();
+        assert_eq!(result.total_cost, 2);
+        assert_eq!(
+            result.path,
+            vec![start, start, Point { row: 0, col: 1 }, goal]
+        );
+    }
+}
@@ -3,8 +3,9 @@ use core::panic;
 use std::{
     any::Any,
     cmp::Ordering,
-    collections::BinaryHeap,
+    collections::{BinaryHeap, HashMap, HashSet},
     fmt::{Debug, Display},
+    hash::Hash,
     ops::{Add, Deref, DerefMut},
 };
 
@@ -12,12 +13,18 @@ use std::{
 pub trait AbsoluteCost: Copy + Clone + Default + Add<Output = Self> + 'static {
     type CmpContext;
     fn context_cmp(&self, other: &Self, ctx: &Self::CmpContext) -> std::cmp::Ordering;
+
+    /// Extends the cost of a path with the cost of one more edge. Defaults to the sum, override
+    /// for other kinds of paths (e.g. [`MaxCost`] for bottleneck paths).
+    fn accumulate(self, edge: Self) -> Self {
+        self + edge
+    }
 }
 
 /// Represents a relative change in cost and can therefore be required to implement Equality
 /// operators
 // pub trait RelativeCost<A: Cost>: Copy + Clone + Add<Output = A> + 'static {}
-pub trait RelativeCost: Copy + Clone + Add + PartialEq + Eq + 'static {}
+pub trait RelativeCost: Copy + Clone + Add<Output = Self> + PartialEq + Eq + 'static {}
 
 impl RelativeCost for usize {}
 
@@ -29,10 +36,156 @@ impl AbsoluteCost for usize {
     }
 }
 
+/// A cost where the cost of a path is the largest cost of any of its edges, used to find
+/// bottleneck paths (minimizing the most expensive edge instead of the sum). Adding two costs
+/// still sums them, which is used to combine the parts of a single edge (e.g. entry costs).
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub struct MaxCost(pub usize);
+
+impl Add for MaxCost {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        MaxCost(self.0 + rhs.0)
+    }
+}
+
+impl Display for MaxCost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl RelativeCost for MaxCost {}
+
+impl AbsoluteCost for MaxCost {
+    type CmpContext = ();
+
+    fn context_cmp(&self, other: &Self, _ctx: &Self::CmpContext) -> std::cmp::Ordering {
+        self.cmp(other)
+    }
+
+    fn accumulate(self, edge: Self) -> Self {
+        self.max(edge)
+    }
+}
+
+/// A fractional cost stored as an integer number of `1 / SCALE` units, e.g. `FixedPoint<1000>`
+/// has a resolution of a thousandth. Unlike floats, sums are exact and the same on every platform,
+/// and they saturate instead of overflowing. `SCALE` must be at least 1, using a scale of 0 with
+/// any of the methods below fails to compile.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub struct FixedPoint<const SCALE: u32>(pub i64);
+
+impl<const SCALE: u32> FixedPoint<SCALE> {
+    /// Evaluated by the methods that scale or divide, so that a `SCALE` of 0 is rejected at
+    /// compile time instead of dividing by zero
+    const VALID: () = assert!(SCALE > 0, "the scale of a FixedPoint must be at least 1");
+
+    pub fn from_int(value: i64) -> Self {
+        let () = Self::VALID;
+        FixedPoint(value.saturating_mul(SCALE as i64))
+    }
+
+    /// The closest representable value, e.g. for weights like the square root of two
+    pub fn from_f64(value: f64) -> Self {
+        let () = Self::VALID;
+        FixedPoint((value * SCALE as f64).round() as i64)
+    }
+
+    /// Multiplies by `factor`, rounding towards zero
+    pub fn scaled(self, factor: Self) -> Self {
+        let () = Self::VALID;
+        let product = self.0 as i128 * factor.0 as i128 / SCALE as i128;
+        FixedPoint(product.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+    }
+}
+
+impl<const SCALE: u32> Add for FixedPoint<SCALE> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        FixedPoint(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl<const SCALE: u32> Display for FixedPoint<SCALE> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let () = Self::VALID;
+        let scale = SCALE as u64;
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let (whole, rest) = (self.0.unsigned_abs() / scale, self.0.unsigned_abs() % scale);
+        // scales made of twos and fives have exact decimals (e.g. 3 for 1000 and 2 for 4), for
+        // others use enough decimals to tell all the fractions apart
+        let (mut rest_of_scale, mut twos, mut fives) = (scale, 0, 0);
+        while rest_of_scale % 2 == 0 {
+            rest_of_scale /= 2;
+            twos += 1;
+        }
+        while rest_of_scale % 5 == 0 {
+            rest_of_scale /= 5;
+            fives += 1;
+        }
+        let digits = match rest_of_scale {
+            1 => twos.max(fives),
+            _ => (scale - 1).checked_ilog10().map_or(0, |d| d + 1),
+        };
+        if digits == 0 {
+            return write!(f, "{sign}{whole}");
+        }
+        let fraction = rest as u128 * 10u128.pow(digits) / scale as u128;
+        write!(
+            f,
+            "{sign}{whole}.{fraction:0width$}",
+            width = digits as usize
+        )
+    }
+}
+
+impl<const SCALE: u32> RelativeCost for FixedPoint<SCALE> {}
+
+impl<const SCALE: u32> AbsoluteCost for FixedPoint<SCALE> {
+    type CmpContext = ();
+
+    fn context_cmp(&self, other: &Self, _ctx: &Self::CmpContext) -> std::cmp::Ordering {
+        self.cmp(other)
+    }
+}
+
 /// Supertrait that collects all the requirements on the NodeReference values
 /// Must be copy, comparable and not references (hence 'static)
 pub trait NodeReference: Copy + Eq + 'static {}
 
+/// Node references that can be restricted to a rectangular region spanned by two corners
+pub trait BoundedReference: NodeReference {
+    /// Check if this node is within the region spanned by `min` and `max` (inclusive)
+    fn is_within(&self, min: &Self, max: &Self) -> bool;
+}
+
 // TODO: move to find.rs and rename as Map
 pub trait MapTrait {
     /// The type that can be used to reference nodes in the map
@@ -46,16 +199,58 @@ pub trait MapTrait {
     /// Check if the provided node reference is valid
     fn is_valid(&self, node: Self::Reference) -> bool;
 
+    /// Check if the provided node is valid and can be moved into, e.g. is not a wall. Maps that
+    /// have impassable nodes should override this, as a search for such a goal would otherwise
+    /// only find out after visiting everything reachable.
+    fn is_passable(&self, node: Self::Reference) -> bool {
+        self.is_valid(node)
+    }
+
     /// Return an iterator over the neighbors of the provided node and the cost required to go there
     fn neighbors_of(
         &self,
         node: Self::Reference,
     ) -> impl Iterator<Item = (Self::Reference, Self::Cost)>;
 
+    /// Like `neighbors_of`, but only returns the neighbors for which `predicate` returns true
+    fn neighbors_where(
+        &self,
+        node: Self::Reference,
+        predicate: impl Fn(Self::Reference, Self::Cost) -> bool,
+    ) -> impl Iterator<Item = (Self::Reference, Self::Cost)> {
+        self.neighbors_of(node)
+            .filter(move |&(neighbor, cost)| predicate(neighbor, cost))
+    }
+
     /// Create a storage for values of type T
     fn create_storage<T: Default + Copy + Clone + 'static>(&self) -> Self::Storage<T>;
 }
 
+/// Maps that can estimate the remaining cost between two nodes, which enables A* search
+pub trait HeuristicMap: MapTrait {
+    /// Estimate the cost of moving from `from` to `to`. For the search to find optimal paths, the
+    /// estimate must never exceed the actual cost.
+    fn heuristic(&self, from: Self::Reference, to: Self::Reference) -> Self::Cost;
+}
+
+/// Costs that can be multiplied by the weight of a heuristic, see [`PathFinder::with_heuristic`]
+pub trait WeightedCost {
+    /// Multiplies the cost by `weight`, rounding towards zero
+    fn weighted(self, weight: f64) -> Self;
+}
+
+impl WeightedCost for usize {
+    fn weighted(self, weight: f64) -> Self {
+        (self as f64 * weight) as usize
+    }
+}
+
+impl<const SCALE: u32> WeightedCost for FixedPoint<SCALE> {
+    fn weighted(self, weight: f64) -> Self {
+        FixedPoint((self.0 as f64 * weight) as i64)
+    }
+}
+
 pub trait MapStorage<T> {
     type Reference: NodeReference;
 
@@ -63,23 +258,67 @@ pub trait MapStorage<T> {
     fn get(&self, node: Self::Reference) -> T;
     fn get_mut(&mut self, node: Self::Reference) -> &mut T;
 
+    /// Returns a mutable reference to the value of the node, inserting the default value first if
+    /// the storage does not have one. Lets callers check and update a value with a single lookup,
+    /// which matters for storages where lookups are expensive (e.g. hashing).
+    fn entry(&mut self, node: Self::Reference) -> &mut T {
+        self.get_mut(node)
+    }
+
+    /// Like `get`, but returns None instead of panicking if the node is not valid
+    fn try_get(&self, node: Self::Reference) -> Option<T> {
+        self.is_valid(node).then(|| self.get(node))
+    }
+
+    /// Like `get_mut`, but returns None instead of panicking if the node is not valid
+    fn try_get_mut(&mut self, node: Self::Reference) -> Option<&mut T> {
+        if self.is_valid(node) {
+            Some(self.get_mut(node))
+        } else {
+            None
+        }
+    }
+
+    /// An estimate of the memory used by the storage in bytes, e.g. for diagnostics on large maps.
+    /// Defaults to the size of the storage itself, override it for storages that allocate.
+    fn approx_memory_bytes(&self) -> usize {
+        std::mem::size_of_val(self)
+    }
+
     fn as_any(&self) -> &dyn Any;
 }
 
-/// A trait that is used to compare two values given a context
-
 /// The objects that we store in the prioirty queue
 #[derive(Debug)]
-struct ToVisit<C: AbsoluteCost, R: Eq> {
-    context: C::CmpContext,
-    cost: C,
-    point: R,
-    from: Option<R>,
+pub(crate) struct ToVisit<C: AbsoluteCost, R: Eq> {
+    pub(crate) context: C::CmpContext,
+    pub(crate) cost: C,
+    // the cost used for ordering, which includes the heuristic estimate when using A*
+    pub(crate) priority: C,
+    pub(crate) point: R,
+    pub(crate) from: Option<R>,
+    /// the number of moves from the start, used to break ties in favor of shorter paths (which
+    /// matters when moves can be free)
+    pub(crate) moves: usize,
+    /// whether ties are broken in favor of the entries furthest from the start instead, which
+    /// lets A* follow one path through a band of equal priorities rather than expanding all of it
+    pub(crate) deepest_first: bool,
 }
 
 impl<C: AbsoluteCost, R: Eq> Ord for ToVisit<C, R> {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.cost.context_cmp(&other.cost, &self.context).reverse() // reverse for BinaryHeap to be a min-heap
+        let ties = if self.deepest_first {
+            other
+                .cost
+                .context_cmp(&self.cost, &self.context)
+                .then_with(|| other.moves.cmp(&self.moves))
+        } else {
+            self.moves.cmp(&other.moves)
+        };
+        self.priority
+            .context_cmp(&other.priority, &self.context)
+            .then(ties)
+            .reverse() // reverse for BinaryHeap to be a min-heap
     }
 }
 
@@ -97,14 +336,102 @@ impl<C: AbsoluteCost, R: Eq> PartialEq for ToVisit<C, R> {
 
 impl<C: AbsoluteCost, R: Eq> Eq for ToVisit<C, R> {}
 
-#[derive(Clone, Copy, Debug)]
+impl<C: AbsoluteCost, R: Copy + Eq> ToVisit<C, R>
+where
+    C::CmpContext: Clone,
+{
+    /// The entry a search starts from, at no cost
+    pub(crate) fn start(point: R, context: C::CmpContext) -> Self {
+        ToVisit {
+            context,
+            cost: C::default(),
+            priority: C::default(),
+            point,
+            from: None,
+            moves: 0,
+            deepest_first: false,
+        }
+    }
+
+    /// The entry for moving on from this one to `point`, prioritized by its cost alone
+    pub(crate) fn next(&self, point: R, move_cost: C) -> Self {
+        let cost = self.cost.accumulate(move_cost);
+        ToVisit {
+            context: self.context.clone(),
+            cost,
+            priority: cost,
+            point,
+            from: Some(self.point),
+            moves: self.moves + 1,
+            deepest_first: self.deepest_first,
+        }
+    }
+}
+
+/// Marks the node of `visit` as settled with the cost and predecessor of the visit. Returns false
+/// without changing anything if it was already settled, through a cheaper or equal way.
+pub(crate) fn settle<C, R, S>(visited: &mut S, visit: &ToVisit<C, R>) -> bool
+where
+    C: AbsoluteCost,
+    R: NodeReference,
+    S: MapStorage<Visited<C, R>, Reference = R>,
+{
+    let entry = visited.entry(visit.point);
+    if entry.is_some() {
+        return false;
+    }
+    *entry = Visited(Some(VisitedItem {
+        cost: visit.cost,
+        from: visit.from,
+    }));
+    true
+}
+
+/// Follows the `from` links of the settled nodes back to the start, returning the path from the
+/// start to `node`
+pub(crate) fn backtrack<C, R, S>(visited: &S, node: R) -> Vec<R>
+where
+    C: Copy,
+    R: NodeReference,
+    S: MapStorage<Visited<C, R>, Reference = R>,
+{
+    let mut path: Vec<R> = vec![node];
+    let mut previous_visit = visited.get(node);
+
+    loop {
+        previous_visit = match previous_visit {
+            Visited(Some(VisitedItem {
+                cost: _,
+                from: None,
+            })) => {
+                // we found the starting point, we are done
+                break;
+            }
+            Visited(Some(VisitedItem {
+                cost: _,
+                from: Some(from),
+            })) => {
+                path.push(from);
+                visited.get(from)
+            }
+            Visited(None) => {
+                panic!("Backtracking lead to a Point that was never visited")
+            }
+        }
+    }
+
+    path.reverse();
+    path
+}
+
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
 pub struct VisitedItem<C, R> {
     pub cost: C,
     pub from: Option<R>,
 }
 
 #[derive(Clone, Copy, Debug)]
-pub struct Visited<C, R>(Option<VisitedItem<C, R>>);
+pub struct Visited<C, R>(pub(crate) Option<VisitedItem<C, R>>);
 
 impl<C, R> Default for Visited<C, R> {
     fn default() -> Self {
@@ -132,7 +459,7 @@ impl<C: Display, R> Display for Visited<C, R> {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Eq)]
+#[derive(Debug, PartialEq, Clone, Eq, serde::Serialize, serde::Deserialize)]
 pub struct PathResult<C, R> {
     pub path: Vec<R>,
     pub start: R,
@@ -140,10 +467,48 @@ pub struct PathResult<C, R> {
     pub total_cost: C,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+impl<C, R: Hash + Eq + Copy> PathResult<C, R> {
+    /// Returns the nodes of the path as a set, for checking many nodes against the path
+    pub fn path_set(&self) -> HashSet<R> {
+        self.path.iter().copied().collect()
+    }
+}
+
+/// The reason why a search finished without finding a path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum NoPathReason {
+    /// The start is not a valid node of the map
+    StartInvalid,
+    /// The goal is not a valid node of the map or can not be moved into
+    GoalInvalid,
+    /// Every node reachable from the start was visited without reaching the goal
+    GoalUnreachable,
+    /// The search ran out of nodes to visit after visiting this many nodes, but some nodes were
+    /// never considered as they are outside the search bounds
+    PrunedByBounds(usize),
+}
+
+impl Display for NoPathReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NoPathReason::StartInvalid => write!(f, "the start is not part of the map"),
+            NoPathReason::GoalInvalid => write!(f, "the goal is not a passable part of the map"),
+            NoPathReason::GoalUnreachable => {
+                write!(f, "the goal can not be reached from the start")
+            }
+            NoPathReason::PrunedByBounds(visited) => write!(
+                f,
+                "the search ran out of nodes within the bounds after visiting {} nodes",
+                visited
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum PathFinderState<C, R> {
     Computing,
-    NoPathFound,
+    NoPathFound(NoPathReason),
     PathFound(PathResult<C, R>),
 }
 
@@ -153,6 +518,55 @@ impl<C, R> PathFinderState<C, R> {
     }
 }
 
+/// A region that the search is restricted to
+#[derive(Debug, Clone, Copy)]
+struct Bounds<R> {
+    min: R,
+    max: R,
+    is_within: fn(&R, &R, &R) -> bool,
+}
+
+/// The heuristic used when running as A*
+#[derive(Debug, Clone, Copy)]
+struct Heuristic<M, R, C> {
+    weight: f64,
+    estimate: fn(&M, R, R) -> C,
+    weighted: fn(C, f64) -> C,
+}
+
+/// The settled node with the lowest heuristic estimate to the goal, and that estimate
+#[derive(Debug, Clone, Copy)]
+struct Closest<M, R, C> {
+    estimate: fn(&M, R, R) -> C,
+    best: Option<(R, C)>,
+}
+
+/// The size of the open list, see [`PathFinder::with_open_count`]
+struct OpenCount<M: MapTrait> {
+    /// the number of entries in the heap whose node is not settled yet
+    open: usize,
+    /// the number of entries in the heap of every node, created on the first step
+    queued: Option<M::Storage<u32>>,
+}
+
+// storages need not implement Debug
+impl<M: MapTrait> std::fmt::Debug for OpenCount<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OpenCount")
+            .field("open", &self.open)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Incrementally searches for the cheapest path between two nodes.
+///
+/// The finder only holds its own search state and never the map: the map is passed to every
+/// call of [`PathFinder::step`] instead, and is only read. This means any number of finders can
+/// search the same map at once (from multiple threads if the map is `Sync`, as [`GridMap`] is),
+/// as long as every step of one search is given the same map. Use [`PathFinder::bind`] or
+/// [`PathFinder::new_borrowed`] to keep the map reference together with the finder.
+///
+/// [`GridMap`]: crate::grid::GridMap
 #[derive(Debug)]
 pub struct PathFinder<
     R: NodeReference,
@@ -167,6 +581,18 @@ pub struct PathFinder<
     visited: S,
     visit_list: BinaryHeap<ToVisit<C, R>>,
     state: PathFinderState<C, R>,
+    steps: usize,
+    nodes_visited: usize,
+    /// the size of the open list, if tracked
+    open_count: Option<OpenCount<M>>,
+    /// whether any neighbor was skipped for being outside the bounds
+    pruned: bool,
+    bounds: Option<Bounds<R>>,
+    heuristic: Option<Heuristic<M, R, C>>,
+    /// the settled nodes and their costs in the order they were settled, if recorded
+    settle_order: Option<Vec<(R, C)>>,
+    /// the settled node closest to the goal, if tracked
+    closest: Option<Closest<M, R, C>>,
     _map: std::marker::PhantomData<M>,
 }
 
@@ -184,17 +610,71 @@ impl<
             goal,
             visited,
             context: context.clone(),
-            visit_list: BinaryHeap::from([ToVisit {
-                context,
-                cost: Default::default(),
-                point: start,
-                from: None,
-            }]),
+            visit_list: BinaryHeap::from([ToVisit::start(start, context)]),
             state: PathFinderState::Computing,
+            steps: 0,
+            nodes_visited: 0,
+            open_count: None,
+            pruned: false,
+            bounds: None,
+            heuristic: None,
+            settle_order: None,
+            closest: None,
             _map: std::marker::PhantomData,
         }
     }
 
+    /// Searches from whichever of `starts` has the cheapest path to the goal, as if they were all
+    /// connected to a common source for free. The `start` of the result is the chosen start, and
+    /// [`PathFinder::start`] returns the first of them. Returns `None` if `starts` is empty.
+    pub fn new_multi_start(
+        starts: impl IntoIterator<Item = R>,
+        goal: R,
+        visited: S,
+        context: K,
+    ) -> Option<Self> {
+        let mut starts = starts.into_iter();
+        let mut finder = Self::new(starts.next()?, goal, visited, context);
+        for start in starts {
+            finder
+                .visit_list
+                .push(ToVisit::start(start, finder.context.clone()));
+        }
+        Some(finder)
+    }
+
+    /// Keeps count of the open list, see [`PathFinder::open_count`]. Off by default as it takes a
+    /// count for every node of the map and updates it on every step.
+    pub fn with_open_count(mut self) -> Self {
+        self.open_count = Some(OpenCount {
+            open: self.visit_list.len(),
+            queued: None,
+        });
+        self
+    }
+
+    /// Records every settled node and its cost, in order, so the search can be replayed
+    /// afterwards (see [`PathFinder::settle_order`]). Off by default as it uses memory for every
+    /// settled node.
+    pub fn with_settle_order(mut self) -> Self {
+        self.settle_order = Some(Vec::new());
+        self
+    }
+
+    /// Restricts the search to the region spanned by `min` and `max` (inclusive). Neighbors
+    /// outside of the region are never visited.
+    pub fn with_bounds(mut self, min: R, max: R) -> Self
+    where
+        R: BoundedReference,
+    {
+        self.bounds = Some(Bounds {
+            min,
+            max,
+            is_within: R::is_within,
+        });
+        self
+    }
+
     pub fn finish(mut self, map: &M) -> (PathFinderState<C, R>, S) {
         loop {
             match self.step(map) {
@@ -204,58 +684,164 @@ impl<
         }
     }
 
+    /// Like [`PathFinder::finish`], but gives up once `timeout` has passed, in which case the
+    /// state is [`PathFinderState::Computing`]. Guards against searching for a very long time on
+    /// huge or malformed maps.
+    #[cfg(feature = "std")]
+    pub fn finish_with_timeout(
+        mut self,
+        map: &M,
+        timeout: std::time::Duration,
+    ) -> (PathFinderState<C, R>, S) {
+        let started = std::time::Instant::now();
+        loop {
+            match self.step(map) {
+                PathFinderState::Computing if started.elapsed() >= timeout => {
+                    return (PathFinderState::Computing, self.visited)
+                }
+                PathFinderState::Computing => {}
+                s => return (s, self.visited),
+            }
+        }
+    }
+
+    /// Like [`PathFinder::finish`], but awaits the future returned by `yield_now` every
+    /// `yield_every` steps. Lets single threaded environments (e.g. the browser) stay responsive
+    /// while searching large maps, by passing a function that yields to the event loop.
+    #[cfg(feature = "async")]
+    pub async fn finish_async<F: std::future::Future<Output = ()>>(
+        mut self,
+        map: &M,
+        yield_every: usize,
+        mut yield_now: impl FnMut() -> F,
+    ) -> (PathFinderState<C, R>, S) {
+        assert!(
+            yield_every > 0,
+            "must perform at least one step between yields"
+        );
+        loop {
+            match self.step_n(map, yield_every) {
+                PathFinderState::Computing => yield_now().await,
+                s => return (s, self.visited),
+            }
+        }
+    }
+
+    /// Turns the search into (weighted) A*, visiting nodes in the order of their cost plus
+    /// `weight` times the heuristic estimate of the remaining cost. With a weight of 1 the found
+    /// path is optimal as long as the heuristic never overestimates. Larger weights explore fewer
+    /// nodes, but may return a path that costs up to `weight` times more than the optimal one.
+    pub fn with_heuristic(mut self, weight: f64) -> Self
+    where
+        M: HeuristicMap,
+        C: WeightedCost,
+    {
+        assert!(weight >= 1.0, "the heuristic weight must be at least 1");
+        self.heuristic = Some(Heuristic {
+            weight,
+            estimate: M::heuristic,
+            weighted: C::weighted,
+        });
+        self.visit_list = std::mem::take(&mut self.visit_list)
+            .into_iter()
+            .map(|visit| ToVisit {
+                deepest_first: true,
+                ..visit
+            })
+            .collect();
+        self
+    }
+
+    /// Keeps track of the settled node closest to the goal according to the heuristic, so that
+    /// the path to it can be returned by [`PathFinder::partial_path`] when the goal can not be
+    /// reached.
+    pub fn with_partial_path(mut self) -> Self
+    where
+        M: HeuristicMap,
+    {
+        self.closest = Some(Closest {
+            estimate: M::heuristic,
+            best: None,
+        });
+        self
+    }
+
+    /// Performs up to `n` steps, stopping early if the search finishes. Returns the state after
+    /// the last step. Useful for time-slicing the search, e.g. a fixed number of steps per frame.
+    pub fn step_n(&mut self, map: &M, n: usize) -> PathFinderState<C, R> {
+        for _ in 0..n {
+            if self.step(map).is_done() {
+                break;
+            }
+        }
+        self.state.clone()
+    }
+
     pub fn step(&mut self, map: &M) -> PathFinderState<C, R> {
         if self.state.is_done() {
             return self.state.clone();
         }
+        if self.steps == 0 {
+            // before the first step the heap holds exactly the starts
+            if !self
+                .visit_list
+                .iter()
+                .all(|visit| map.is_valid(visit.point))
+            {
+                self.state = PathFinderState::NoPathFound(NoPathReason::StartInvalid);
+                return self.state.clone();
+            }
+            if !map.is_passable(self.goal) {
+                self.state = PathFinderState::NoPathFound(NoPathReason::GoalInvalid);
+                return self.state.clone();
+            }
+        }
+        if let Some(OpenCount {
+            queued: queued @ None,
+            ..
+        }) = &mut self.open_count
+        {
+            let mut counts = map.create_storage::<u32>();
+            for visit in &self.visit_list {
+                *counts.entry(visit.point) += 1;
+            }
+            *queued = Some(counts);
+        }
+        self.steps += 1;
         if let Some(visit) = self.visit_list.pop() {
             // we have a point to process, find the valid neighbors to visit next
+            if let Some(count) = &mut self.open_count {
+                *count.queued.as_mut().unwrap().entry(visit.point) -= 1;
+            }
 
-            if self.visited.get(visit.point).is_some() {
+            if !settle(&mut self.visited, &visit) {
                 return self.state.clone();
             }
-
-            *self.visited.get_mut(visit.point) = Visited(Some(VisitedItem {
-                cost: visit.cost,
-                from: visit.from,
-            }));
+            if let Some(count) = &mut self.open_count {
+                // the other entries of the node are stale once it is settled
+                let remaining = count.queued.as_ref().unwrap().get(visit.point);
+                count.open -= 1 + remaining as usize;
+            }
+            self.nodes_visited += 1;
+            if let Some(order) = &mut self.settle_order {
+                order.push((visit.point, visit.cost));
+            }
+            if let Some(closest) = &mut self.closest {
+                let estimate = (closest.estimate)(map, visit.point, self.goal);
+                if closest.best.as_ref().map_or(true, |(_, best)| {
+                    estimate.context_cmp(best, &self.context) == Ordering::Less
+                }) {
+                    closest.best = Some((visit.point, estimate));
+                }
+            }
 
             // if this is the goal, we are done! (and should probably do some back-tracking to find the actual shortest path...)
             if visit.point == self.goal {
-                // backtrack to find the total shortest path
-                let mut path: Vec<R> = Vec::new();
-                path.push(self.goal);
-
-                let mut previous_visit = self.visited.get(self.goal);
-
-                loop {
-                    previous_visit = match previous_visit {
-                        Visited(Some(VisitedItem {
-                            cost: _,
-                            from: None,
-                        })) => {
-                            // we found the starting point, we are done
-                            break;
-                        }
-                        Visited(Some(VisitedItem {
-                            cost: _,
-                            from: Some(from),
-                        })) => {
-                            path.push(from);
-                            self.visited.get(from)
-                        }
-                        Visited(None) => {
-                            panic!("Backtracking lead to a Point that was never visited")
-                        }
-                    }
-                }
-
-                path.reverse();
-
+                let path = self.backtrack(self.goal);
                 self.state = PathFinderState::PathFound(PathResult {
-                    path: path,
+                    start: path[0],
+                    path,
                     total_cost: visit.cost,
-                    start: self.start,
                     goal: self.goal,
                 });
 
@@ -263,26 +849,118 @@ impl<
             }
 
             for (point, move_cost) in map.neighbors_of(visit.point) {
-                if !self.visited.get(point).is_some() {
-                    self.visit_list.push(ToVisit {
-                        context: self.context.clone(),
-                        cost: visit.cost + move_cost,
-                        point: point,
-                        from: Some(visit.point),
-                    });
+                if let Some(bounds) = &self.bounds {
+                    if !(bounds.is_within)(&point, &bounds.min, &bounds.max) {
+                        self.pruned = true;
+                        continue;
+                    }
+                }
+                // skip neighbors that are already visited, or outside the storage (e.g. due to
+                // a malformed map)
+                if matches!(self.visited.try_get(point), Some(Visited(None))) {
+                    let mut next = visit.next(point, move_cost);
+                    if let Some(h) = &self.heuristic {
+                        let estimate = (h.estimate)(map, point, self.goal);
+                        next.priority = next.cost + (h.weighted)(estimate, h.weight);
+                    }
+                    self.visit_list.push(next);
+                    if let Some(count) = &mut self.open_count {
+                        *count.queued.as_mut().unwrap().entry(point) += 1;
+                        count.open += 1;
+                    }
                 }
             }
         } else {
-            self.state = PathFinderState::NoPathFound;
+            let reason = if self.pruned {
+                NoPathReason::PrunedByBounds(self.nodes_visited)
+            } else {
+                NoPathReason::GoalUnreachable
+            };
+            self.state = PathFinderState::NoPathFound(reason);
         }
 
-        return self.state.clone();
+        self.state.clone()
+    }
+
+    /// Follows the `from` links of the visited nodes back to the start, returning the path from
+    /// the start to `node`
+    fn backtrack(&self, node: R) -> Vec<R> {
+        backtrack(&self.visited, node)
     }
 
     pub fn state(&self) -> &PathFinderState<C, R> {
         &self.state
     }
 
+    /// The path to the settled node that is closest to the goal according to the heuristic, as
+    /// the best effort when no path is found. The `goal` of the result is that closest node. None
+    /// unless enabled with [`PathFinder::with_partial_path`], or if nothing was settled yet.
+    pub fn partial_path(&self) -> Option<PathResult<C, R>> {
+        let (node, _) = self.closest.as_ref()?.best?;
+        let Visited(Some(item)) = self.visited.get(node) else {
+            return None;
+        };
+        let path = self.backtrack(node);
+        Some(PathResult {
+            start: path[0],
+            path,
+            goal: node,
+            total_cost: item.cost,
+        })
+    }
+
+    /// The number of steps performed so far
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// The number of nodes settled so far. Lower than [`PathFinder::steps`] as steps that pop an
+    /// already settled node do not count.
+    pub fn nodes_visited(&self) -> usize {
+        self.nodes_visited
+    }
+
+    /// The number of ways to nodes that are reached but not settled yet, i.e. the size of the
+    /// open list. Together with [`PathFinder::nodes_visited`], the size of the closed list, this
+    /// shows how much work a search takes. Kept as a counter, so it is cheap to call on every
+    /// step, unlike counting [`PathFinder::iter_frontier`]. None unless enabled with
+    /// [`PathFinder::with_open_count`].
+    pub fn open_count(&self) -> Option<usize> {
+        self.open_count.as_ref().map(|count| count.open)
+    }
+
+    /// Estimates how far along the search is as the fraction of the `total_nodes` nodes of the
+    /// map that have been settled, e.g. from [`GridMap::valid_count`]. Searches usually find the
+    /// goal before settling every node, so this is a pessimistic estimate, but it never decreases
+    /// and is always 1 once the search has finished.
+    ///
+    /// [`GridMap::valid_count`]: crate::grid::GridMap::valid_count
+    pub fn progress(&self, total_nodes: usize) -> f32 {
+        if self.state.is_done() {
+            return 1.0;
+        }
+        if total_nodes == 0 {
+            return 0.0;
+        }
+        (self.nodes_visited as f32 / total_nodes as f32).min(1.0)
+    }
+
+    /// The nodes settled so far and their costs, in the order they were settled. Empty unless
+    /// enabled with [`PathFinder::with_settle_order`].
+    pub fn settle_order(&self) -> &[(R, C)] {
+        self.settle_order.as_deref().unwrap_or_default()
+    }
+
+    /// The nodes that have been reached but not settled yet, i.e. the wavefront of the search, in
+    /// no particular order. A node that was reached in several ways is yielded once per way.
+    pub fn iter_frontier(&self) -> impl Iterator<Item = R> + '_ {
+        self.visit_list
+            .iter()
+            .map(|visit| visit.point)
+            // the heap keeps stale entries for nodes that were settled through a cheaper way
+            .filter(|&point| matches!(self.visited.try_get(point), Some(Visited(None))))
+    }
+
     pub fn get_visited(&self) -> &S {
         &self.visited
     }
@@ -295,3 +973,445 @@ impl<
         self.goal
     }
 }
+
+impl<
+        'a,
+        R: NodeReference,
+        K: Clone, // the context for contextord
+        C: AbsoluteCost<CmpContext = K> + Display,
+        S: MapStorage<Visited<C, R>, Reference = R>,
+        M: MapTrait<Reference = R, Storage<Visited<C, R>> = S, Cost = C>,
+    > PathFinder<R, K, C, S, M>
+{
+    /// Creates a finder that borrows `map` for the whole search, see [`PathFinder::bind`]
+    pub fn new_borrowed(
+        map: &'a M,
+        start: R,
+        goal: R,
+        context: K,
+    ) -> BorrowedPathFinder<'a, R, K, C, S, M> {
+        Self::new(start, goal, map.create_storage::<Visited<C, R>>(), context).bind(map)
+    }
+
+    /// Binds the finder to `map`, so the map no longer has to be passed to every step
+    pub fn bind(self, map: &'a M) -> BorrowedPathFinder<'a, R, K, C, S, M> {
+        BorrowedPathFinder { map, finder: self }
+    }
+}
+
+/// A [`PathFinder`] that holds a reference to the map it searches. Dereferences to the inner
+/// finder for access to its state.
+#[derive(Debug)]
+pub struct BorrowedPathFinder<
+    'a,
+    R: NodeReference,
+    K,
+    C: AbsoluteCost<CmpContext = K>,
+    S: MapStorage<Visited<C, R>, Reference = R>,
+    M: MapTrait<Reference = R, Storage<Visited<C, R>> = S, Cost = C>,
+> {
+    map: &'a M,
+    finder: PathFinder<R, K, C, S, M>,
+}
+
+impl<
+        R: NodeReference,
+        K: Clone, // the context for contextord
+        C: AbsoluteCost<CmpContext = K> + Display,
+        S: MapStorage<Visited<C, R>, Reference = R>,
+        M: MapTrait<Reference = R, Storage<Visited<C, R>> = S, Cost = C>,
+    > BorrowedPathFinder<'_, R, K, C, S, M>
+{
+    pub fn step(&mut self) -> PathFinderState<C, R> {
+        self.finder.step(self.map)
+    }
+
+    pub fn step_n(&mut self, n: usize) -> PathFinderState<C, R> {
+        self.finder.step_n(self.map, n)
+    }
+
+    pub fn finish(self) -> (PathFinderState<C, R>, S) {
+        self.finder.finish(self.map)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn finish_with_timeout(self, timeout: std::time::Duration) -> (PathFinderState<C, R>, S) {
+        self.finder.finish_with_timeout(self.map, timeout)
+    }
+
+    /// Releases the map, returning the inner finder
+    pub fn into_inner(self) -> PathFinder<R, K, C, S, M> {
+        self.finder
+    }
+}
+
+impl<
+        R: NodeReference,
+        K,
+        C: AbsoluteCost<CmpContext = K>,
+        S: MapStorage<Visited<C, R>, Reference = R>,
+        M: MapTrait<Reference = R, Storage<Visited<C, R>> = S, Cost = C>,
+    > Deref for BorrowedPathFinder<'_, R, K, C, S, M>
+{
+    type Target = PathFinder<R, K, C, S, M>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.finder
+    }
+}
+
+/// Finds the path from `start` to `goal` with the largest total cost that uses at most
+/// `max_steps` moves and never visits a node twice.
+///
+/// Finding the longest simple path is NP-hard, so this does an exhaustive depth-first search of
+/// all paths up to `max_steps` moves long. The running time grows exponentially with
+/// `max_steps`, so keep it small (in the order of tens of steps on a grid).
+pub fn find_longest_path_bounded<M, K>(
+    map: &M,
+    start: M::Reference,
+    goal: M::Reference,
+    max_steps: usize,
+    context: &K,
+) -> Option<PathResult<M::Cost, M::Reference>>
+where
+    M: MapTrait,
+    M::Cost: AbsoluteCost<CmpContext = K>,
+{
+    struct Search<'a, M: MapTrait, K> {
+        map: &'a M,
+        goal: M::Reference,
+        max_steps: usize,
+        context: &'a K,
+        on_path: M::Storage<bool>,
+        path: Vec<M::Reference>,
+        best: Option<(M::Cost, Vec<M::Reference>)>,
+    }
+
+    impl<M: MapTrait, K> Search<'_, M, K>
+    where
+        M::Cost: AbsoluteCost<CmpContext = K>,
+    {
+        fn visit(&mut self, node: M::Reference, cost: M::Cost) {
+            if node == self.goal {
+                let better = match &self.best {
+                    Some((best_cost, _)) => {
+                        cost.context_cmp(best_cost, self.context) == Ordering::Greater
+                    }
+                    None => true,
+                };
+                if better {
+                    self.best = Some((cost, self.path.clone()));
+                }
+                // the path cannot continue through the goal
+                return;
+            }
+
+            // the path includes the start, so it has one more node than the number of steps
+            if self.path.len() > self.max_steps {
+                return;
+            }
+
+            for (neighbor, move_cost) in self.map.neighbors_of(node) {
+                if self.on_path.try_get(neighbor) != Some(false) {
+                    continue;
+                }
+                *self.on_path.get_mut(neighbor) = true;
+                self.path.push(neighbor);
+                self.visit(neighbor, cost.accumulate(move_cost));
+                self.path.pop();
+                *self.on_path.get_mut(neighbor) = false;
+            }
+        }
+    }
+
+    let mut search = Search {
+        map,
+        goal,
+        max_steps,
+        context,
+        on_path: map.create_storage(),
+        path: vec![start],
+        best: None,
+    };
+    *search.on_path.get_mut(start) = true;
+    search.visit(start, Default::default());
+
+    search.best.map(|(total_cost, path)| PathResult {
+        path,
+        start,
+        goal,
+        total_cost,
+    })
+}
+
+/// Finds the cheapest path from `start` to `goal` that uses at most `max_steps` moves, e.g. when
+/// the number of moves is limited regardless of their cost. Returns None if there is no such path.
+///
+/// The cheapest way to a node might use too many moves to continue to the goal, so the search is
+/// over `(node, moves)` pairs. A pair is skipped if the node was already reached as cheaply with
+/// fewer moves, which keeps the search close to plain Dijkstra when the limit is not tight.
+pub fn find_path_max_length<M, K>(
+    map: &M,
+    start: M::Reference,
+    goal: M::Reference,
+    max_steps: usize,
+    context: K,
+) -> Option<PathResult<M::Cost, M::Reference>>
+where
+    M: MapTrait,
+    M::Reference: Hash,
+    M::Cost: AbsoluteCost<CmpContext = K>,
+    K: Clone,
+{
+    if !map.is_valid(start) || !map.is_valid(goal) {
+        return None;
+    }
+
+    // the reached states as (node, parent), referenced by index from the heap
+    let mut nodes = vec![(start, None)];
+    let mut visit_list = BinaryHeap::from([ToVisit::start(0, context)]);
+    // the fewest moves any settled state of each node used
+    let mut fewest_moves: HashMap<M::Reference, usize> = HashMap::new();
+
+    while let Some(visit) = visit_list.pop() {
+        let (node, _) = nodes[visit.point];
+        // states are settled in order of cost, so earlier ones were at least as cheap
+        if fewest_moves
+            .get(&node)
+            .is_some_and(|&moves| moves <= visit.moves)
+        {
+            continue;
+        }
+        fewest_moves.insert(node, visit.moves);
+
+        if node == goal {
+            let mut path = Vec::new();
+            let mut current = Some(visit.point);
+            while let Some(index) = current {
+                path.push(nodes[index].0);
+                current = nodes[index].1;
+            }
+            path.reverse();
+            return Some(PathResult {
+                path,
+                start,
+                goal,
+                total_cost: visit.cost,
+            });
+        }
+
+        if visit.moves >= max_steps {
+            continue;
+        }
+        for (neighbor, move_cost) in map.neighbors_of(node) {
+            if fewest_moves
+                .get(&neighbor)
+                .is_some_and(|&moves| moves <= visit.moves + 1)
+            {
+                continue;
+            }
+            nodes.push((neighbor, Some(visit.point)));
+            visit_list.push(visit.next(nodes.len() - 1, move_cost));
+        }
+    }
+
+    None
+}
+
+/// Returns every node that can be reached from `start` with a total cost of at most `budget`,
+/// together with the cost of the cheapest path there, e.g. to answer "what can be reached with N
+/// fuel". Runs Dijkstra and stops once the cheapest unvisited node costs more than the budget.
+/// The nodes are returned in order of increasing cost, starting with `start` itself.
+pub fn reachable_within<M, K>(
+    map: &M,
+    start: M::Reference,
+    budget: M::Cost,
+    context: &K,
+) -> Vec<(M::Reference, M::Cost)>
+where
+    M: MapTrait,
+    M::Cost: AbsoluteCost<CmpContext = K>,
+    K: Clone,
+{
+    let mut reached = Vec::new();
+    if !map.is_valid(start) {
+        return reached;
+    }
+
+    let mut visited = map.create_storage::<bool>();
+    let mut visit_list = BinaryHeap::from([ToVisit::<M::Cost, _>::start(start, context.clone())]);
+
+    while let Some(visit) = visit_list.pop() {
+        // everything left in the queue is at least as expensive
+        if visit.cost.context_cmp(&budget, context) == Ordering::Greater {
+            break;
+        }
+
+        let seen = visited.get_mut(visit.point);
+        if *seen {
+            continue;
+        }
+        *seen = true;
+        reached.push((visit.point, visit.cost));
+
+        for (neighbor, move_cost) in map.neighbors_of(visit.point) {
+            if visited.try_get(neighbor) == Some(false) {
+                visit_list.push(visit.next(neighbor, move_cost));
+            }
+        }
+    }
+
+    reached
+}
+
+/// A view of a map where some nodes are treated as impassable
+struct Avoiding<'a, M: MapTrait> {
+    map: &'a M,
+    blocked: &'a HashSet<M::Reference>,
+}
+
+impl<M: MapTrait> MapTrait for Avoiding<'_, M>
+where
+    M::Reference: Hash,
+{
+    type Reference = M::Reference;
+    type Storage<T: Default + Copy + Clone + 'static> = M::Storage<T>;
+    type Cost = M::Cost;
+
+    fn is_valid(&self, node: Self::Reference) -> bool {
+        self.map.is_valid(node)
+    }
+
+    fn is_passable(&self, node: Self::Reference) -> bool {
+        self.map.is_passable(node)
+    }
+
+    fn neighbors_of(
+        &self,
+        node: Self::Reference,
+    ) -> impl Iterator<Item = (Self::Reference, Self::Cost)> {
+        self.map
+            .neighbors_of(node)
+            .filter(|(neighbor, _)| !self.blocked.contains(neighbor))
+    }
+
+    fn create_storage<T: Default + Copy + Clone + 'static>(&self) -> Self::Storage<T> {
+        self.map.create_storage()
+    }
+}
+
+/// Finds the cheapest path from `start` to `goal` as if the `blocked` nodes were impassable,
+/// without changing the map. Useful for replanning around temporary obstacles or answering "what
+/// if this cell was closed". Returns `None` if there is no such path, including when `start` or
+/// `goal` is blocked.
+pub fn shortest_path_avoiding<M, K>(
+    map: &M,
+    start: M::Reference,
+    goal: M::Reference,
+    blocked: &HashSet<M::Reference>,
+    context: K,
+) -> Option<PathResult<M::Cost, M::Reference>>
+where
+    M: MapTrait,
+    M::Reference: NodeReference + Hash,
+    M::Cost: AbsoluteCost<CmpContext = K> + Display,
+    K: Clone,
+{
+    if blocked.contains(&start) || blocked.contains(&goal) {
+        return None;
+    }
+
+    let map = Avoiding { map, blocked };
+    match PathFinder::new(start, goal, map.create_storage(), context)
+        .finish(&map)
+        .0
+    {
+        PathFinderState::PathFound(result) => Some(result),
+        _ => None,
+    }
+}
+
+/// Returns whether `goal` can be reached from `start`, ignoring costs. Cheaper than a full search
+/// and useful to warn about searches that can never succeed (which would otherwise flood the
+/// whole map). As moves can be one-way, this is not necessarily symmetric.
+pub fn are_connected<M: MapTrait>(map: &M, start: M::Reference, goal: M::Reference) -> bool {
+    if !map.is_valid(start) || !map.is_valid(goal) {
+        return false;
+    }
+
+    let mut seen = map.create_storage::<bool>();
+    *seen.get_mut(start) = true;
+    let mut stack = vec![start];
+    while let Some(node) = stack.pop() {
+        if node == goal {
+            return true;
+        }
+        for (neighbor, _) in map.neighbors_of(node) {
+            if let Some(seen @ false) = seen.try_get_mut(neighbor) {
+                *seen = true;
+                stack.push(neighbor);
+            }
+        }
+    }
+    false
+}
+
+/// Finds the paths from `start` to all of the `goals` with a single search, returned in order of
+/// increasing cost (the nearest goal first). Goals that can not be reached are left out. Cheaper
+/// than searching for every goal separately, as the search is shared.
+pub fn find_goals_ordered<M, K>(
+    map: &M,
+    start: M::Reference,
+    goals: &[M::Reference],
+    context: K,
+) -> Vec<PathResult<M::Cost, M::Reference>>
+where
+    M: MapTrait,
+    M::Cost: AbsoluteCost<CmpContext = K>,
+    K: Clone,
+{
+    let mut found = Vec::new();
+    if !map.is_valid(start) {
+        return found;
+    }
+
+    let mut is_goal = map.create_storage::<bool>();
+    let mut remaining = 0;
+    for &goal in goals {
+        if let Some(is_goal @ false) = is_goal.try_get_mut(goal) {
+            *is_goal = true;
+            remaining += 1;
+        }
+    }
+
+    let mut visited = map.create_storage::<Visited<M::Cost, M::Reference>>();
+    let mut visit_list = BinaryHeap::from([ToVisit::start(start, context)]);
+
+    while remaining > 0 {
+        let Some(visit) = visit_list.pop() else {
+            break;
+        };
+        if !settle(&mut visited, &visit) {
+            continue;
+        }
+
+        // goals are settled in order of their cost
+        if is_goal.get(visit.point) {
+            remaining -= 1;
+            found.push(PathResult {
+                path: backtrack(&visited, visit.point),
+                start,
+                goal: visit.point,
+                total_cost: visit.cost,
+            });
+        }
+
+        for (neighbor, move_cost) in map.neighbors_of(visit.point) {
+            if matches!(visited.try_get(neighbor), Some(Visited(None))) {
+                visit_list.push(visit.next(neighbor, move_cost));
+            }
+        }
+    }
+
+    found
+}
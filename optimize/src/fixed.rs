@@ -0,0 +1,125 @@
+use std::any::Any;
+
+use crate::find::{MapStorage, MapTrait, RelativeCost};
+use crate::grid::{grid_neighbors, Cell, GridMap, Point, Topology};
+
+/// A MapStorage for grids with a size known at compile time. Stores everything inline without
+/// any heap allocations.
+#[derive(Debug, Clone)]
+pub struct ArrayStorage<T, const W: usize, const H: usize>([[T; W]; H]);
+
+impl<T: Copy + Default, const W: usize, const H: usize> Default for ArrayStorage<T, W, H> {
+    fn default() -> Self {
+        Self([[T::default(); W]; H])
+    }
+}
+
+impl<T: Copy + 'static, const W: usize, const H: usize> MapStorage<T> for ArrayStorage<T, W, H> {
+    type Reference = Point;
+
+    fn is_valid(&self, node: Self::Reference) -> bool {
+        node.row < H && node.col < W
+    }
+
+    fn get(&self, node: Self::Reference) -> T {
+        self.0[node.row][node.col]
+    }
+
+    fn get_mut(&mut self, node: Self::Reference) -> &mut T {
+        &mut self.0[node.row][node.col]
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// A MapTrait implementation for a rectangular grid with `W` columns and `H` rows known at compile
+/// time. Behaves like a [`GridMap`], but uses [`ArrayStorage`] to avoid any heap allocations.
+#[derive(Debug, Clone)]
+pub struct FixedGridMap<C: RelativeCost, const W: usize, const H: usize> {
+    pub cells: [[Cell<C>; W]; H],
+}
+
+impl<C: RelativeCost, const W: usize, const H: usize> FixedGridMap<C, W, H> {
+    pub fn new(cells: [[Cell<C>; W]; H]) -> Self {
+        Self { cells }
+    }
+}
+
+impl<C: RelativeCost, U, const W: usize, const H: usize> TryFrom<&GridMap<C, U>>
+    for FixedGridMap<C, W, H>
+{
+    type Error = anyhow::Error;
+
+    fn try_from(map: &GridMap<C, U>) -> Result<Self, Self::Error> {
+        if map.rows != H || map.columns != W {
+            return Err(anyhow::anyhow!(
+                "Expected a map with {}x{} cells, got {}x{}",
+                H,
+                W,
+                map.rows,
+                map.columns
+            ));
+        }
+
+        let mut cells = [[Cell::Invalid; W]; H];
+        for (row, map_row) in cells.iter_mut().zip(&map.cells) {
+            row.copy_from_slice(map_row);
+        }
+        Ok(Self { cells })
+    }
+}
+
+impl<C: RelativeCost, const W: usize, const H: usize> MapTrait for FixedGridMap<C, W, H> {
+    type Reference = Point;
+    type Storage<T: Default + Copy + Clone + 'static> = ArrayStorage<T, W, H>;
+    type Cost = C;
+
+    fn is_valid(&self, node: Self::Reference) -> bool {
+        node.row < H && node.col < W
+    }
+
+    fn is_passable(&self, node: Self::Reference) -> bool {
+        self.is_valid(node) && self.cells[node.row][node.col].is_passable()
+    }
+
+    fn neighbors_of(&self, node: Self::Reference) -> impl Iterator<Item = (Self::Reference, C)> {
+        grid_neighbors(H, W, Topology::Orthogonal, node, |p| {
+            self.cells[p.row][p.col]
+        })
+    }
+
+    fn create_storage<T: Default + Copy + Clone + 'static>(&self) -> Self::Storage<T> {
+        ArrayStorage::default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::find::{PathFinder, PathFinderState, PathResult};
+    use crate::grid::test::create_basic_map;
+
+    #[test]
+    fn test_fixed_basic_route() {
+        let map = FixedGridMap::<usize, 7, 7>::try_from(&create_basic_map()).unwrap();
+
+        let finder = PathFinder::new(
+            Point { row: 1, col: 1 },
+            Point { row: 1, col: 5 },
+            map.create_storage(),
+            (),
+        );
+
+        assert!(matches!(
+            finder.finish(&map).0,
+            PathFinderState::PathFound(PathResult { total_cost: 12, .. })
+        ));
+    }
+
+    #[test]
+    fn test_fixed_wrong_size() {
+        assert!(FixedGridMap::<usize, 6, 7>::try_from(&create_basic_map()).is_err());
+    }
+}
@@ -1,31 +1,44 @@
-use crate::find::{MapStorage, MapTrait, NodeReference, RelativeCost};
+use crate::cache::AdjacencyCache;
+use crate::find::{
+    reachable_within, BoundedReference, HeuristicMap, MapStorage, MapTrait, NodeReference,
+    PathResult, RelativeCost, Visited,
+};
+use crate::tags::TagStorage;
+use crate::util::{Rng, StableHasher};
 use std::any::Any;
+use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
 use std::{fmt::Display, str::FromStr};
 
 use serde::{Deserialize, Serialize};
+use unicode_width::UnicodeWidthStr;
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub enum Cell<C: RelativeCost> {
+    #[default]
     Invalid,
     Valid {
         cost: C,
+        // optional extra cost for stepping onto this cell (e.g. a door), charged in addition to
+        // the cost of the cell that is being left
+        #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+        entry_cost: Option<C>,
     },
     OneWay {
         cost: C,
         // the direction which one can move from this cell
         direction: Direction,
-        // optional target point to use as "teleport" when moving in the direction
+        // optional target point to use as "teleport" when moving in the direction. A target
+        // pointing at the cell itself is ignored (see `GridMap::validate`)
         target: Option<Point>,
+        // optional cost of the teleport to the target, instead of `cost`
+        #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+        target_cost: Option<C>,
     },
 }
 
-impl<C: RelativeCost> Default for Cell<C> {
-    fn default() -> Self {
-        Self::Invalid
-    }
-}
-
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub enum Direction {
     Up,
     Down,
@@ -33,6 +46,28 @@ pub enum Direction {
     Right,
 }
 
+impl Direction {
+    /// The change in (row, column) when moving one step in this direction
+    pub fn delta(self) -> (isize, isize) {
+        match self {
+            Direction::Up => (-1, 0),
+            Direction::Down => (1, 0),
+            Direction::Left => (0, -1),
+            Direction::Right => (0, 1),
+        }
+    }
+
+    /// The direction pointing the other way
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+}
+
 impl Display for Direction {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -62,65 +97,578 @@ impl FromStr for Direction {
     }
 }
 
+/// A set of characters to draw cells with, see [`GridMap::render_with`]
+pub trait CellGlyphs {
+    fn invalid(&self) -> char;
+    fn valid(&self) -> char;
+    /// The glyph of a OneWay cell, where `teleport` is set if the cell has a target
+    fn one_way(&self, direction: Direction, teleport: bool) -> char;
+}
+
+/// The glyphs used by the `Display` implementations, with Unicode arrows for OneWay cells
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnicodeGlyphs;
+
+impl CellGlyphs for UnicodeGlyphs {
+    fn invalid(&self) -> char {
+        'X'
+    }
+
+    fn valid(&self) -> char {
+        ' '
+    }
+
+    fn one_way(&self, direction: Direction, teleport: bool) -> char {
+        match (direction, teleport) {
+            (Direction::Up, false) => '🠭',
+            (Direction::Down, false) => '🠯',
+            (Direction::Left, false) => '🠬',
+            (Direction::Right, false) => '🠮',
+            (Direction::Up, true) => '↟',
+            (Direction::Down, true) => '↡',
+            (Direction::Left, true) => '↞',
+            (Direction::Right, true) => '↠',
+        }
+    }
+}
+
+/// Glyphs for terminals without Unicode support. OneWay cells are drawn as `^v<>`, with or
+/// without a target.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AsciiGlyphs;
+
+impl CellGlyphs for AsciiGlyphs {
+    fn invalid(&self) -> char {
+        'X'
+    }
+
+    fn valid(&self) -> char {
+        ' '
+    }
+
+    fn one_way(&self, direction: Direction, _teleport: bool) -> char {
+        match direction {
+            Direction::Up => '^',
+            Direction::Down => 'v',
+            Direction::Left => '<',
+            Direction::Right => '>',
+        }
+    }
+}
+
+impl<C: RelativeCost> Cell<C> {
+    /// Whether searches can move onto and out of this cell
+    pub fn is_passable(&self) -> bool {
+        self.cost().is_some()
+    }
+
+    /// The cost of leaving this cell, None if it is not passable
+    pub fn cost(&self) -> Option<C> {
+        match self {
+            Cell::Valid { cost, .. } | Cell::OneWay { cost, .. } => Some(*cost),
+            Cell::Invalid => None,
+        }
+    }
+
+    /// Returns the character that represents this cell in the provided glyph set
+    pub fn glyph(&self, glyphs: &impl CellGlyphs) -> char {
+        match self {
+            Cell::Invalid => glyphs.invalid(),
+            Cell::Valid { .. } => glyphs.valid(),
+            Cell::OneWay {
+                direction, target, ..
+            } => glyphs.one_way(*direction, target.is_some()),
+        }
+    }
+}
+
 impl<C: RelativeCost + Display> Display for Cell<C> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Cell::Invalid => "X",
-                Cell::Valid { .. } => " ",
-                Cell::OneWay {
-                    direction,
-                    target: None,
-                    ..
-                } => match direction {
-                    Direction::Up => "🠭",
-                    Direction::Down => "🠯",
-                    Direction::Left => "🠬",
-                    Direction::Right => "🠮",
-                },
-                Cell::OneWay {
-                    direction,
-                    target: Some(_),
-                    ..
-                } => match direction {
-                    Direction::Up => "↟",
-                    Direction::Down => "↡",
-                    Direction::Left => "↞",
-                    Direction::Right => "↠",
-                },
+        write!(f, "{}", self.glyph(&UnicodeGlyphs))
+    }
+}
+
+/// Problems that can be found in a map using [`GridMap::validate`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapIssue {
+    /// The cells do not match the number of rows and columns of the map
+    SizeMismatch,
+    /// A OneWay cell that teleports to itself
+    SelfTarget(Point),
+    /// A OneWay cell that teleports to a point outside of the map
+    TargetOutOfBounds { from: Point, target: Point },
+    /// A OneWay cell that teleports to a cell that is not passable
+    TargetInvalid { from: Point, target: Point },
+}
+
+impl Display for MapIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MapIssue::SizeMismatch => write!(f, "cells do not match the size of the map"),
+            MapIssue::SelfTarget(p) => {
+                write!(f, "one way cell at {}:{} targets itself", p.row, p.col)
             }
-        )
+            MapIssue::TargetOutOfBounds { from, target } => write!(
+                f,
+                "one way cell at {}:{} targets {}:{} which is outside the map",
+                from.row, from.col, target.row, target.col
+            ),
+            MapIssue::TargetInvalid { from, target } => write!(
+                f,
+                "one way cell at {}:{} targets {}:{} which is not passable",
+                from.row, from.col, target.row, target.col
+            ),
+        }
+    }
+}
+
+/// The moves that are possible from a cell of a [`GridMap`]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Topology {
+    /// Up, down, left and right
+    #[default]
+    Orthogonal,
+    /// Only the four diagonals
+    Diagonal,
+    /// All eight surrounding cells, like the king in chess
+    King,
+    /// The eight L-shaped jumps of the knight in chess
+    Knight,
+}
+
+impl Topology {
+    /// A lower bound of the number of moves needed to get between two points, ignoring walls
+    pub fn min_moves(&self, from: Point, to: Point) -> usize {
+        let (dr, dc) = (from.row.abs_diff(to.row), from.col.abs_diff(to.col));
+        match self {
+            Topology::Orthogonal => dr + dc,
+            Topology::Diagonal | Topology::King => dr.max(dc),
+            // each jump covers at most three cells in total and two along any axis
+            Topology::Knight => (dr + dc).div_ceil(3).max(dr.max(dc).div_ceil(2)),
+        }
+    }
+
+    /// The `(row, column)` offsets of the possible moves, in the order the neighbors are returned
+    pub fn offsets(&self) -> &'static [(isize, isize)] {
+        const ORTHOGONAL: [(isize, isize); 4] = [(-1, 0), (0, -1), (1, 0), (0, 1)];
+        const DIAGONAL: [(isize, isize); 4] = [(-1, -1), (1, -1), (1, 1), (-1, 1)];
+        const KING: [(isize, isize); 8] = [
+            (-1, 0),
+            (0, -1),
+            (1, 0),
+            (0, 1),
+            (-1, -1),
+            (1, -1),
+            (1, 1),
+            (-1, 1),
+        ];
+        const KNIGHT: [(isize, isize); 8] = [
+            (-2, -1),
+            (-2, 1),
+            (-1, -2),
+            (-1, 2),
+            (1, -2),
+            (1, 2),
+            (2, -1),
+            (2, 1),
+        ];
+
+        match self {
+            Topology::Orthogonal => &ORTHOGONAL,
+            Topology::Diagonal => &DIAGONAL,
+            Topology::King => &KING,
+            Topology::Knight => &KNIGHT,
+        }
     }
 }
 
-/// A MapTrait implementation that uses a rectangular grid of cells
+/// A MapTrait implementation that uses a rectangular grid of cells. Each cell can optionally
+/// carry user data of type `U` (e.g. a tile id or a label) that is ignored by the pathfinding.
 #[derive(Debug, Serialize, Deserialize)]
-pub struct GridMap<C: RelativeCost> {
+pub struct GridMap<C: RelativeCost, U = ()> {
     pub rows: usize,
     pub columns: usize,
     pub cells: Vec<Vec<Cell<C>>>,
+    /// User data attached to cells. This is not serialized.
+    #[serde(skip)]
+    pub meta: HashMap<Point, U>,
+    /// The moves that are possible between cells
+    #[serde(default)]
+    pub topology: Topology,
+    /// Boolean tags of the cells, see [`GridMap::set_tag`]
+    #[serde(default)]
+    pub tags: TagStorage,
+}
+
+/// What [`GridMap::inflate_obstacles`] does to the cells close to obstacles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InflateMode<C> {
+    /// Turn the cells into obstacles
+    Block,
+    /// Add the provided cost to the cells
+    Cost(C),
 }
 
+/// A cell that differs between two maps, as `(point, ours, theirs)`. See [`GridMap::diff`].
+pub type CellChange<C> = (Point, Cell<C>, Cell<C>);
+
 impl<C: RelativeCost> GridMap<C> {
     pub fn new(rows: usize, columns: usize, default_cost: C) -> Self {
+        Self::new_with_meta(rows, columns, default_cost)
+    }
+
+    /// Converts a maze where the walls lie between the cells into a map where the walls are
+    /// cells. `has_wall` tells if there is a wall on the `Right` or `Down` side of a cell of the
+    /// `rows` x `columns` maze. The resulting map is `2 * rows + 1` x `2 * columns + 1` cells,
+    /// with the maze cells on the odd rows and columns, passages carved between them where there
+    /// is no wall, and a wall around the outside (the same layout as [`GridMap::generate_maze`]).
+    pub fn from_wall_grid(
+        rows: usize,
+        columns: usize,
+        cost: C,
+        has_wall: impl Fn(Point, Direction) -> bool,
+    ) -> Self {
+        let mut map = Self::new(2 * rows + 1, 2 * columns + 1, cost);
+        let open = Cell::Valid {
+            cost,
+            entry_cost: None,
+        };
+        for cells in &mut map.cells {
+            cells.fill(Cell::Invalid);
+        }
+
+        for row in 0..rows {
+            for col in 0..columns {
+                let point = Point { row, col };
+                map.cells[2 * row + 1][2 * col + 1] = open;
+                if col + 1 < columns && !has_wall(point, Direction::Right) {
+                    map.cells[2 * row + 1][2 * col + 2] = open;
+                }
+                if row + 1 < rows && !has_wall(point, Direction::Down) {
+                    map.cells[2 * row + 2][2 * col + 1] = open;
+                }
+            }
+        }
+
+        map
+    }
+}
+
+impl<C: RelativeCost, U> GridMap<C, U> {
+    /// Like [`GridMap::new`], but for maps that carry user data of type `U`
+    pub fn new_with_meta(rows: usize, columns: usize, default_cost: C) -> Self {
+        let cell = Cell::Valid {
+            cost: default_cost,
+            entry_cost: None,
+        };
         Self {
             rows,
             columns,
-            cells: vec![vec![Cell::Valid { cost: default_cost }; columns]; rows],
+            cells: vec![vec![cell; columns]; rows],
+            meta: HashMap::new(),
+            topology: Topology::default(),
+            tags: TagStorage::default(),
+        }
+    }
+
+    /// Checks the map for problems such as malformed OneWay targets. Note that teleports
+    /// forming a cycle (even with zero cost) are fine, since the pathfinder never visits a cell
+    /// twice.
+    pub fn validate(&self) -> Vec<MapIssue> {
+        let mut issues = Vec::new();
+
+        if self.cells.len() != self.rows || self.cells.iter().any(|r| r.len() != self.columns) {
+            issues.push(MapIssue::SizeMismatch);
+            return issues;
+        }
+
+        for (row, cells) in self.cells.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                let from = Point { row, col };
+                if let Cell::OneWay {
+                    target: Some(target),
+                    ..
+                } = *cell
+                {
+                    if target == from {
+                        issues.push(MapIssue::SelfTarget(from));
+                    } else if !self.is_valid(target) {
+                        issues.push(MapIssue::TargetOutOfBounds { from, target });
+                    } else if self.get(target).is_some_and(|cell| !cell.is_passable()) {
+                        issues.push(MapIssue::TargetInvalid { from, target });
+                    }
+                }
+            }
+        }
+
+        issues
+    }
+
+    /// Returns the cell at the provided point, or None if it is outside the map
+    pub fn get(&self, point: Point) -> Option<Cell<C>> {
+        self.cells.get(point.row)?.get(point.col).copied()
+    }
+
+    /// Returns a mutable reference to the cell at the provided point, or None if it is outside
+    /// the map
+    pub fn get_mut(&mut self, point: Point) -> Option<&mut Cell<C>> {
+        self.cells.get_mut(point.row)?.get_mut(point.col)
+    }
+
+    /// Applies `mode` to every passable cell within `radius` cells of an `Invalid` cell, e.g. to
+    /// keep a margin to the walls for vehicles that are larger than a cell. Distances include
+    /// diagonal steps, so the margin around a single obstacle is a square. The edges of the map do
+    /// not count as obstacles.
+    pub fn inflate_obstacles(&mut self, radius: usize, mode: InflateMode<C>) {
+        // breadth first search from all the obstacles at once gives the distance to the closest
+        let mut distance = vec![vec![usize::MAX; self.columns]; self.rows];
+        let mut queue = VecDeque::new();
+        for (row, cells) in self.cells.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                if !cell.is_passable() {
+                    distance[row][col] = 0;
+                    queue.push_back(Point { row, col });
+                }
+            }
+        }
+
+        while let Some(point) = queue.pop_front() {
+            let next = distance[point.row][point.col] + 1;
+            if next > radius {
+                continue;
+            }
+            for &delta in Topology::King.offsets() {
+                let Some(neighbor) = point.offset(delta).filter(|p| self.is_valid(*p)) else {
+                    continue;
+                };
+                if distance[neighbor.row][neighbor.col] == usize::MAX {
+                    distance[neighbor.row][neighbor.col] = next;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        for (cells, distances) in self.cells.iter_mut().zip(&distance) {
+            for (cell, &d) in cells.iter_mut().zip(distances) {
+                if d == 0 || d > radius {
+                    continue;
+                }
+                match (mode, cell) {
+                    (InflateMode::Block, cell) => *cell = Cell::Invalid,
+                    (
+                        InflateMode::Cost(extra),
+                        Cell::Valid { cost, .. } | Cell::OneWay { cost, .. },
+                    ) => *cost = *cost + extra,
+                    (InflateMode::Cost(_), Cell::Invalid) => {}
+                }
+            }
+        }
+    }
+
+    /// Closes the gaps between diagonally adjacent walls, e.g. the staircase lines [`parse_img`]
+    /// produces for thin diagonal walls in an image. This is an aid for drawing and exporting maps
+    /// only: the searches of this crate never cut corners between two walls, so it does not change
+    /// which paths they find. It makes such walls look closed, and keeps tools that read the map
+    /// and do cut corners from slipping through. Returns the number of cells that were turned into
+    /// walls.
+    ///
+    /// Wherever two walls only touch at a corner, one of the two open cells next to both becomes
+    /// a wall: the one with more walls next to it, as it is the least likely to be part of a
+    /// passage, and the lower one if both have as many. This can close one cell wide passages
+    /// that run along the gap. Sealing a gap can open a new one next to it, so this repeats until
+    /// there are none left.
+    ///
+    /// [`parse_img`]: crate::util::parse_img
+    pub fn seal_diagonal_gaps(&mut self) -> usize {
+        let is_wall = |map: &Self, row: usize, col: usize| !map.cells[row][col].is_passable();
+        let walls_around = |map: &Self, point: Point| {
+            Topology::Orthogonal
+                .offsets()
+                .iter()
+                .filter_map(|&delta| point.offset(delta))
+                .filter(|p| p.row < map.rows && p.col < map.columns && is_wall(map, p.row, p.col))
+                .count()
+        };
+
+        let mut sealed = 0;
+        loop {
+            let before = sealed;
+            for row in 0..self.rows.saturating_sub(1) {
+                for col in 0..self.columns.saturating_sub(1) {
+                    // the open cells of a 2x2 window where the walls only touch at a corner
+                    let (top, bottom) = match (
+                        is_wall(self, row, col),
+                        is_wall(self, row, col + 1),
+                        is_wall(self, row + 1, col),
+                        is_wall(self, row + 1, col + 1),
+                    ) {
+                        (true, false, false, true) => (col + 1, col),
+                        (false, true, true, false) => (col, col + 1),
+                        _ => continue,
+                    };
+                    let top = Point { row, col: top };
+                    let bottom = Point {
+                        row: row + 1,
+                        col: bottom,
+                    };
+                    let seal = match walls_around(self, top) > walls_around(self, bottom) {
+                        true => top,
+                        false => bottom,
+                    };
+                    self.cells[seal.row][seal.col] = Cell::Invalid;
+                    sealed += 1;
+                }
+            }
+            if sealed == before {
+                return sealed;
+            }
+        }
+    }
+
+    /// The number of Valid and OneWay cells, i.e. the most nodes a search can visit. See
+    /// [`PathFinder::progress`](crate::find::PathFinder::progress).
+    pub fn valid_count(&self) -> usize {
+        self.cells
+            .iter()
+            .flatten()
+            .filter(|cell| cell.is_passable())
+            .count()
+    }
+
+    /// Expands the map into a list of its passable cells and a matrix with the cost of moving
+    /// from the cell at one index to the cell at another, `None` where there is no such move. The
+    /// matrix takes O(N²) memory for N passable cells, so this is only meant for small maps, e.g.
+    /// to hand them to other graph libraries. If there are several moves between two cells, the
+    /// first one returned by `neighbors_of` is used.
+    pub fn to_adjacency_matrix(&self) -> (Vec<Point>, Vec<Vec<Option<C>>>) {
+        let nodes: Vec<Point> = (0..self.rows)
+            .flat_map(|row| (0..self.columns).map(move |col| Point { row, col }))
+            .filter(|p| self.cells[p.row][p.col].is_passable())
+            .collect();
+        let index: HashMap<Point, usize> = nodes.iter().enumerate().map(|(i, p)| (*p, i)).collect();
+
+        let mut matrix = vec![vec![None; nodes.len()]; nodes.len()];
+        for (from, node) in nodes.iter().enumerate() {
+            for (neighbor, cost) in self.neighbors_of(*node) {
+                let edge = &mut matrix[from][index[&neighbor]];
+                if edge.is_none() {
+                    *edge = Some(cost);
+                }
+            }
+        }
+        (nodes, matrix)
+    }
+
+    /// Lists the cells that differ between this map and `other`, row by row. Fails if the maps do
+    /// not have the same size. User data is not compared.
+    pub fn diff<V>(&self, other: &GridMap<C, V>) -> anyhow::Result<Vec<CellChange<C>>> {
+        if self.rows != other.rows || self.columns != other.columns {
+            return Err(anyhow::anyhow!(
+                "Can not diff a {}x{} map against a {}x{} map",
+                self.rows,
+                self.columns,
+                other.rows,
+                other.columns
+            ));
+        }
+
+        let mut changes = Vec::new();
+        for (row, (ours, theirs)) in self.cells.iter().zip(&other.cells).enumerate() {
+            for (col, (a, b)) in ours.iter().zip(theirs).enumerate() {
+                if a != b {
+                    changes.push((Point { row, col }, *a, *b));
+                }
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Swaps valid and invalid cells: every `Invalid` cell becomes `Valid` with the provided cost,
+    /// while `Valid` and `OneWay` cells become `Invalid`. Data attached to cells that become
+    /// invalid is kept.
+    pub fn invert(&mut self, default_cost: C) {
+        for cell in self.cells.iter_mut().flatten() {
+            *cell = match cell {
+                Cell::Invalid => Cell::Valid {
+                    cost: default_cost,
+                    entry_cost: None,
+                },
+                Cell::Valid { .. } | Cell::OneWay { .. } => Cell::Invalid,
+            };
         }
     }
 
+    /// Returns a hash of the size and cells of the map (the user data is not included). The hash
+    /// is stable across runs and platforms, so it can be used as a key for caching data that is
+    /// precomputed for a specific map.
+    pub fn content_hash(&self) -> u64
+    where
+        C: Hash,
+    {
+        let mut hasher = StableHasher::default();
+        self.rows.hash(&mut hasher);
+        self.columns.hash(&mut hasher);
+        self.topology.hash(&mut hasher);
+        for cell in self.cells.iter().flatten() {
+            cell.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Precomputes the moves out of every cell, for many searches on a map that does not change.
+    /// See [`AdjacencyCache`].
+    pub fn build_adjacency_cache(&self) -> AdjacencyCache<C>
+    where
+        C: Hash,
+    {
+        AdjacencyCache::new(self)
+    }
+
+    /// Returns the user data attached to the provided cell, if any
+    pub fn meta(&self, point: Point) -> Option<&U> {
+        self.meta.get(&point)
+    }
+
+    /// Attaches user data to the provided cell, returning the previous data if there was any
+    pub fn set_meta(&mut self, point: Point, meta: U) -> Option<U> {
+        assert!(self.is_valid(point), "point {:?} is outside the map", point);
+        self.meta.insert(point, meta)
+    }
+
+    /// Removes the user data attached to the provided cell
+    pub fn remove_meta(&mut self, point: Point) -> Option<U> {
+        self.meta.remove(&point)
+    }
+
+    /// Adds or removes a named tag (e.g. "water") of the provided cell. Tags move along with the
+    /// cells when the map changes shape, and do not affect pathfinding.
+    pub fn set_tag(&mut self, point: Point, tag: &str, value: bool) {
+        assert!(self.is_valid(point), "point {:?} is outside the map", point);
+        // maps stored without tags have no columns to index them with yet
+        if self.tags.is_empty() {
+            self.tags = TagStorage::new(self.columns);
+        }
+        self.tags.set(point, tag, value);
+    }
+
+    pub fn has_tag(&self, point: Point, tag: &str) -> bool {
+        self.is_valid(point) && !self.tags.is_empty() && self.tags.has(point, tag)
+    }
+
+    /// The cells that have the tag, row by row
+    pub fn tagged_cells<'a>(&'a self, tag: &str) -> impl Iterator<Item = Point> + 'a {
+        self.tags.tagged(tag)
+    }
+
     pub fn resize(&mut self, columns: usize, rows: usize) {
         // create container for holding new cells
         let mut new_cells = vec![vec![Cell::default(); columns]; rows];
 
         // copy old cells into new container, or fill with default if new size is larger (already
         // done above)
-        for row in 0..self.rows.min(rows) {
-            for col in 0..self.columns.min(columns) {
-                new_cells[row][col] = self.cells[row][col];
+        for (new_row, old_row) in new_cells.iter_mut().zip(&self.cells) {
+            for (new_cell, old_cell) in new_row.iter_mut().zip(old_row) {
+                *new_cell = *old_cell;
             }
         }
 
@@ -128,6 +676,124 @@ impl<C: RelativeCost> GridMap<C> {
         self.rows = rows;
         self.columns = columns;
         self.cells = new_cells;
+
+        // drop any user data, tags and teleport targets for cells that no longer exist
+        self.remap_points(|p| Some(p).filter(|p| p.row < rows && p.col < columns));
+    }
+
+    /// Crops the map to the `rows` x `columns` cells with the top left corner at `top_left`.
+    /// OneWay targets and user data are moved along with the cells, and teleports to cells that
+    /// are cropped away are removed (the cells stay OneWay, but without a target).
+    pub fn crop(&mut self, top_left: Point, rows: usize, columns: usize) {
+        assert!(
+            top_left.row + rows <= self.rows && top_left.col + columns <= self.columns,
+            "the cropped area must be inside the map"
+        );
+
+        self.cells = self.cells[top_left.row..top_left.row + rows]
+            .iter()
+            .map(|row| row[top_left.col..top_left.col + columns].to_vec())
+            .collect();
+        self.rows = rows;
+        self.columns = columns;
+
+        let crop = |p: Point| {
+            let p = Point {
+                row: p.row.checked_sub(top_left.row)?,
+                col: p.col.checked_sub(top_left.col)?,
+            };
+            Some(p).filter(|p| p.row < rows && p.col < columns)
+        };
+        self.remap_points(crop);
+    }
+
+    /// Inserts a row of default cells before row `at`, or at the bottom if `at` is the number of
+    /// rows. The cells below move down along with their user data and the targets pointing there.
+    pub fn insert_row(&mut self, at: usize) {
+        assert!(at <= self.rows, "can not insert a row outside of the map");
+        self.cells.insert(at, vec![Cell::default(); self.columns]);
+        self.rows += 1;
+        self.remap_points(|p| {
+            Some(Point {
+                row: shift_up(p.row, at),
+                ..p
+            })
+        });
+    }
+
+    /// Removes row `at`. The cells below move up along with their user data and the targets
+    /// pointing there, while teleports to the removed row lose their target.
+    pub fn delete_row(&mut self, at: usize) {
+        assert!(at < self.rows, "can not delete a row outside of the map");
+        self.cells.remove(at);
+        self.rows -= 1;
+        self.remap_points(|p| {
+            Some(Point {
+                row: shift_down(p.row, at)?,
+                ..p
+            })
+        });
+    }
+
+    /// Inserts a column of default cells before column `at`, or at the right edge if `at` is the
+    /// number of columns. See [`GridMap::insert_row`].
+    pub fn insert_col(&mut self, at: usize) {
+        assert!(
+            at <= self.columns,
+            "can not insert a column outside of the map"
+        );
+        for row in &mut self.cells {
+            row.insert(at, Cell::default());
+        }
+        self.columns += 1;
+        self.remap_points(|p| {
+            Some(Point {
+                col: shift_up(p.col, at),
+                ..p
+            })
+        });
+    }
+
+    /// Removes column `at`. See [`GridMap::delete_row`].
+    pub fn delete_col(&mut self, at: usize) {
+        assert!(
+            at < self.columns,
+            "can not delete a column outside of the map"
+        );
+        for row in &mut self.cells {
+            row.remove(at);
+        }
+        self.columns -= 1;
+        self.remap_points(|p| {
+            Some(Point {
+                col: shift_down(p.col, at)?,
+                ..p
+            })
+        });
+    }
+
+    /// Moves the user data, tags and OneWay targets with `remap`, dropping the user data and
+    /// tags and removing the targets it returns `None` for
+    fn remap_points(&mut self, remap: impl Fn(Point) -> Option<Point>) {
+        self.meta = std::mem::take(&mut self.meta)
+            .into_iter()
+            .filter_map(|(p, meta)| Some((remap(p)?, meta)))
+            .collect();
+        self.tags.remap(self.columns, &remap);
+        self.remap_targets(remap);
+    }
+
+    /// Moves the targets of all OneWay cells with `remap`, removing the ones it returns `None` for
+    fn remap_targets(&mut self, remap: impl Fn(Point) -> Option<Point>) {
+        for cell in self.cells.iter_mut().flatten() {
+            if let Cell::OneWay {
+                target: target @ Some(_),
+                ..
+            } = cell
+            {
+                *target = target.and_then(&remap);
+            }
+        }
     }
     /// Scales the map by the given factor, i.e. to make it twice as large, pass 2.
     /// Interpolates the cells by repeating the existing cells in the new grid.
@@ -147,176 +813,849 @@ impl<C: RelativeCost> GridMap<C> {
         self.rows *= factor;
         self.columns *= factor;
         self.cells = new_cells;
-    }
-}
 
-/// A MapStorage that uses a rectangular grid of cells (a vec in a vec)
-// TODO: change from vec of vec to one single vec -> better cache friendlyness!
-#[derive(Debug)]
-pub struct GridStorage<T>(Vec<Vec<T>>);
+        // keep the user data at the top left cell of each scaled up cell
+        self.meta = std::mem::take(&mut self.meta)
+            .into_iter()
+            .map(|(p, meta)| {
+                let p = Point {
+                    row: p.row * factor,
+                    col: p.col * factor,
+                };
+                (p, meta)
+            })
+            .collect();
 
-impl<T: Copy + 'static> MapStorage<T> for GridStorage<T> {
-    type Reference = Point;
+        // every cell of a scaled up cell keeps its tags
+        self.tags.remap(self.columns, |p| {
+            (0..factor * factor).map(move |i| Point {
+                row: p.row * factor + i / factor,
+                col: p.col * factor + i % factor,
+            })
+        });
 
-    fn is_valid(&self, node: Self::Reference) -> bool {
-        node.row < self.0.len() && node.col < self.0[0].len()
+        // teleports lead to the top left cell of the scaled up target
+        self.remap_targets(|p| {
+            Some(Point {
+                row: p.row * factor,
+                col: p.col * factor,
+            })
+        });
     }
+}
 
-    fn get(&self, node: Self::Reference) -> T {
-        self.0[node.row][node.col]
+/// The new index of a row or column after inserting one at `at`
+fn shift_up(index: usize, at: usize) -> usize {
+    if index >= at {
+        index + 1
+    } else {
+        index
     }
+}
 
-    fn get_mut(&mut self, node: Self::Reference) -> &mut T {
-        &mut self.0[node.row][node.col]
+/// The new index of a row or column after deleting the one at `at`, `None` if it was deleted
+fn shift_down(index: usize, at: usize) -> Option<usize> {
+    match index.cmp(&at) {
+        Ordering::Less => Some(index),
+        Ordering::Equal => None,
+        Ordering::Greater => Some(index - 1),
     }
+}
 
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
+/// Statistics over the costs of the cells of a map, see [`GridMap::cost_stats`]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CostStats {
+    pub min: usize,
+    pub max: usize,
+    pub mean: f64,
+    /// the number of Valid and OneWay cells
+    pub valid_count: usize,
+    pub invalid_count: usize,
 }
 
-impl<T: Display> Display for GridStorage<T> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for row in &self.0 {
-            for cell in row {
-                write!(f, "{}", cell)?;
+/// The most passable cells [`GridMap::all_pairs_shortest`] accepts, as the result grows with the
+/// square of the number of cells
+pub const MAX_ALL_PAIRS_CELLS: usize = 1024;
+
+impl<U> GridMap<usize, U> {
+    /// Computes the cost of the cheapest path between every pair of passable cells, by running
+    /// Dijkstra from every cell. Pairs without a path are left out. Fails if the map has more than
+    /// [`MAX_ALL_PAIRS_CELLS`] passable cells.
+    pub fn all_pairs_shortest(&self) -> anyhow::Result<HashMap<(Point, Point), usize>> {
+        let valid_count = self.valid_count();
+        if valid_count > MAX_ALL_PAIRS_CELLS {
+            return Err(anyhow::anyhow!(
+                "The map has {} passable cells, but at most {} are supported",
+                valid_count,
+                MAX_ALL_PAIRS_CELLS
+            ));
+        }
+
+        let mut distances = HashMap::new();
+        for row in 0..self.rows {
+            for col in 0..self.columns {
+                let source = Point { row, col };
+                if !self.cells[row][col].is_passable() {
+                    continue;
+                }
+                for (target, cost) in reachable_within(self, source, usize::MAX, &()) {
+                    distances.insert((source, target), cost);
+                }
             }
-            write!(f, "\n")?;
         }
+        Ok(distances)
+    }
 
-        Ok(())
+    /// The cost of the cheapest path from `point` to the cell farthest away from it, among the
+    /// cells that can be reached at all. None if `point` is not a passable cell of the map.
+    pub fn eccentricity(&self, point: Point) -> Option<usize> {
+        if !self.get(point)?.is_passable() {
+            return None;
+        }
+        // the cells are returned in order of increasing cost
+        reachable_within(self, point, usize::MAX, &())
+            .last()
+            .map(|(_, cost)| *cost)
     }
-}
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
-pub struct Point {
-    pub row: usize,
-    pub col: usize,
-}
+    /// Returns the two cells that are farthest apart by shortest path cost, and that cost. Only
+    /// cells that reach the most other cells are considered, i.e. the largest connected part of
+    /// the map. Useful for picking start and goal pairs that make the search work hard. Runs
+    /// Dijkstra from every passable cell, so this is slow on large maps. None if the map has no
+    /// passable cells.
+    pub fn diameter(&self) -> Option<(Point, Point, usize)> {
+        // (number of reached cells, start, farthest cell, cost)
+        let mut best: Option<(usize, Point, Point, usize)> = None;
+        for row in 0..self.rows {
+            for col in 0..self.columns {
+                if !self.cells[row][col].is_passable() {
+                    continue;
+                }
+                let start = Point { row, col };
+                let reached = reachable_within(self, start, usize::MAX, &());
+                let (farthest, cost) = *reached.last()?;
+                let better = best.map_or(true, |(count, .., best_cost)| {
+                    (reached.len(), cost) > (count, best_cost)
+                });
+                if better {
+                    best = Some((reached.len(), start, farthest, cost));
+                }
+            }
+        }
+        best.map(|(_, start, farthest, cost)| (start, farthest, cost))
+    }
 
-impl NodeReference for Point {}
+    /// Suggests a start and goal that are far apart, e.g. for initializing a freshly loaded map.
+    /// Uses the [`GridMap::diameter`] on maps with at most [`MAX_ALL_PAIRS_CELLS`] passable
+    /// cells. Larger maps search from the first passable cell to the cell farthest from it, and
+    /// from there to the cell farthest from that, which is quick but only approximates the
+    /// diameter. None if there are no two connected passable cells.
+    pub fn suggest_endpoints(&self) -> Option<(Point, Point)> {
+        let (start, goal) = if self.valid_count() <= MAX_ALL_PAIRS_CELLS {
+            let (start, goal, _) = self.diameter()?;
+            (start, goal)
+        } else {
+            let farthest = |from: Point| {
+                reachable_within(self, from, usize::MAX, &())
+                    .last()
+                    .map(|(p, _)| *p)
+            };
+            let first = (0..self.rows)
+                .flat_map(|row| (0..self.columns).map(move |col| Point { row, col }))
+                .find(|p| self.cells[p.row][p.col].is_passable())?;
+            let start = farthest(first)?;
+            (start, farthest(start)?)
+        };
+        Some((start, goal)).filter(|(start, goal)| start != goal)
+    }
 
-impl<C: RelativeCost + Display> Display for GridMap<C> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for row in &self.cells {
-            for cell in row {
-                write!(f, "{}", cell)?;
+    /// Computes statistics over the costs of all passable cells, e.g. to scale a color ramp to the
+    /// actual range of costs. Entry costs are not included. All values except `invalid_count` are
+    /// zero if there are no passable cells.
+    pub fn cost_stats(&self) -> CostStats {
+        let mut stats = CostStats {
+            min: usize::MAX,
+            ..Default::default()
+        };
+        let mut total = 0u128;
+        for cell in self.cells.iter().flatten() {
+            match cell {
+                Cell::Invalid => stats.invalid_count += 1,
+                Cell::Valid { cost, .. } | Cell::OneWay { cost, .. } => {
+                    stats.min = stats.min.min(*cost);
+                    stats.max = stats.max.max(*cost);
+                    stats.valid_count += 1;
+                    total += *cost as u128;
+                }
             }
-            write!(f, "\n")?;
         }
 
-        Ok(())
+        if stats.valid_count == 0 {
+            stats.min = 0;
+        } else {
+            stats.mean = total as f64 / stats.valid_count as f64;
+        }
+        stats
     }
 }
 
-impl<C: RelativeCost> MapTrait for GridMap<C> {
-    type Reference = Point;
-    type Storage<T: Default + Copy + Clone + 'static> = GridStorage<T>;
-    type Cost = C;
+impl GridMap<usize> {
+    /// Creates a map from rows of costs, e.g. grid data from a game engine. Negative costs are
+    /// walls. Fails if the rows are not all the same length.
+    pub fn from_cost_grid(grid: &[&[i64]]) -> anyhow::Result<Self> {
+        let columns = grid.first().map_or(0, |row| row.len());
+        let mut map = Self::new(grid.len(), columns, 1);
+        for (row, costs) in grid.iter().enumerate() {
+            if costs.len() != columns {
+                return Err(anyhow::anyhow!(
+                    "Row {} has {} cells, expected {}",
+                    row,
+                    costs.len(),
+                    columns
+                ));
+            }
+            for (col, &cost) in costs.iter().enumerate() {
+                map.cells[row][col] = match usize::try_from(cost) {
+                    Ok(cost) => Cell::Valid {
+                        cost,
+                        entry_cost: None,
+                    },
+                    Err(_) => Cell::Invalid,
+                };
+            }
+        }
+        Ok(map)
+    }
 
-    fn is_valid(&self, node: Self::Reference) -> bool {
-        node.row < self.rows && node.col < self.columns
+    /// Parses a map in the format of the Moving AI benchmark maps (`.map` files), where `.` and
+    /// `G` are passable and `@`, `O`, `T` are walls. Swamps (`S`) cost 1 and water (`W`) is a wall,
+    /// see [`GridMap::from_movingai_with_costs`]. `octile` maps use the `King` topology, but
+    /// diagonal moves cost the same as straight ones, so the costs differ from the published
+    /// results by the factor of the diagonals.
+    pub fn from_movingai(s: &str) -> anyhow::Result<Self> {
+        Self::from_movingai_with_costs(s, Some(1), None)
     }
 
-    fn neighbors_of(&self, node: Self::Reference) -> impl Iterator<Item = (Self::Reference, C)> {
-        let mut points = Vec::with_capacity(4);
-
-        let c = self.cells[node.row][node.col];
-
-        match c {
-            Cell::Valid { cost } => {
-                if node.row > 0 {
-                    points.push((
-                        Point {
-                            row: node.row - 1,
-                            col: node.col,
-                        },
-                        cost,
-                    ));
-                }
-                if node.col > 0 {
-                    points.push((
-                        Point {
-                            col: node.col - 1,
-                            row: node.row,
-                        },
-                        cost,
-                    ));
-                }
+    /// Like [`GridMap::from_movingai`], with the costs of swamp (`S`) and water (`W`) cells, where
+    /// None makes them walls
+    pub fn from_movingai_with_costs(
+        s: &str,
+        swamp_cost: Option<usize>,
+        water_cost: Option<usize>,
+    ) -> anyhow::Result<Self> {
+        let mut lines = s.lines();
+        let mut header = |key: &str| {
+            let line = lines
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("Missing header line `{}`", key))?;
+            match line.trim().split_once(' ') {
+                Some((k, value)) if k == key => Ok(value.trim().to_string()),
+                _ => Err(anyhow::anyhow!("Expected `{} ...`, got `{}`", key, line)),
+            }
+        };
+        let topology = match header("type")?.as_str() {
+            "octile" => Topology::King,
+            "tile" => Topology::Orthogonal,
+            other => return Err(anyhow::anyhow!("Unsupported map type: {}", other)),
+        };
+        let rows: usize = header("height")?.parse()?;
+        let columns: usize = header("width")?.parse()?;
+        if lines.next().map(str::trim) != Some("map") {
+            return Err(anyhow::anyhow!("Expected `map` after the header"));
+        }
 
-                if node.row < self.rows - 1 {
-                    points.push((
-                        Point {
-                            row: node.row + 1,
-                            col: node.col,
-                        },
-                        cost,
-                    ));
-                }
-                if node.col < self.columns - 1 {
-                    points.push((
-                        Point {
-                            col: node.col + 1,
-                            row: node.row,
-                        },
-                        cost,
-                    ));
+        let cell = |cost: Option<usize>| match cost {
+            Some(cost) => Cell::Valid {
+                cost,
+                entry_cost: None,
+            },
+            None => Cell::Invalid,
+        };
+        let mut map = Self::new(rows, columns, 1);
+        map.topology = topology;
+        for row in 0..rows {
+            let line = lines
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("Expected {} rows, got {}", rows, row))?
+                .trim_end();
+            if line.chars().count() != columns {
+                return Err(anyhow::anyhow!(
+                    "Row {} has {} cells, expected {}",
+                    row,
+                    line.chars().count(),
+                    columns
+                ));
+            }
+            for (col, c) in line.chars().enumerate() {
+                map.cells[row][col] = match c {
+                    '.' | 'G' => cell(Some(1)),
+                    '@' | 'O' | 'T' => Cell::Invalid,
+                    'S' => cell(swamp_cost),
+                    'W' => cell(water_cost),
+                    other => {
+                        return Err(anyhow::anyhow!(
+                            "Unknown terrain `{}` at row {}, column {}",
+                            other,
+                            row,
+                            col
+                        ))
+                    }
+                };
+            }
+        }
+
+        Ok(map)
+    }
+
+    /// Generates a random maze using recursive backtracking. Rooms are placed on the odd
+    /// rows and columns and the walls between them are carved away, which means every valid cell
+    /// is reachable from every other valid cell. The same seed always generates the same maze.
+    pub fn generate_maze(rows: usize, columns: usize, seed: u64) -> Self {
+        let mut map = Self {
+            rows,
+            columns,
+            cells: vec![vec![Cell::Invalid; columns]; rows],
+            meta: HashMap::new(),
+            topology: Topology::default(),
+            tags: TagStorage::default(),
+        };
+
+        // the number of rooms in each direction
+        let (room_rows, room_cols) = (rows.saturating_sub(1) / 2, columns.saturating_sub(1) / 2);
+        if room_rows == 0 || room_cols == 0 {
+            return map;
+        }
+
+        let mut rng = Rng::new(seed);
+        let mut visited = vec![vec![false; room_cols]; room_rows];
+        let mut stack = vec![(0, 0)];
+        visited[0][0] = true;
+        let open = Cell::Valid {
+            cost: 1,
+            entry_cost: None,
+        };
+        map.cells[1][1] = open;
+
+        while let Some(&(row, col)) = stack.last() {
+            let mut unvisited = Vec::with_capacity(4);
+            if row > 0 && !visited[row - 1][col] {
+                unvisited.push((row - 1, col));
+            }
+            if col > 0 && !visited[row][col - 1] {
+                unvisited.push((row, col - 1));
+            }
+            if row + 1 < room_rows && !visited[row + 1][col] {
+                unvisited.push((row + 1, col));
+            }
+            if col + 1 < room_cols && !visited[row][col + 1] {
+                unvisited.push((row, col + 1));
+            }
+
+            if unvisited.is_empty() {
+                stack.pop();
+                continue;
+            }
+
+            let (next_row, next_col) = unvisited[rng.below(unvisited.len())];
+            visited[next_row][next_col] = true;
+
+            // carve out the next room and the wall between the rooms
+            map.cells[2 * next_row + 1][2 * next_col + 1] = open;
+            map.cells[row + next_row + 1][col + next_col + 1] = open;
+
+            stack.push((next_row, next_col));
+        }
+
+        map
+    }
+
+    /// Gives each valid cell a cost in the range `2..=max_cost` with the given probability.
+    /// Useful together with [`GridMap::generate_maze`] to get maps with varying costs.
+    pub fn randomize_costs(&mut self, probability: f64, max_cost: usize, seed: u64) {
+        let mut rng = Rng::new(seed);
+        for cell in self.cells.iter_mut().flatten() {
+            if let Cell::Valid { cost, .. } = cell {
+                if max_cost > 1 && rng.chance(probability) {
+                    *cost = 2 + rng.below(max_cost - 1);
                 }
             }
-            Cell::OneWay {
-                cost,
-                direction,
-                target,
-            } => {
-                if node.row > 0 && direction != Direction::Down {
-                    points.push((
-                        Point {
-                            row: node.row - 1,
-                            col: node.col,
-                        },
-                        cost,
-                    ));
+        }
+    }
+
+    /// Fills the rectangle spanned by `(r0, c0)` and `(r1, c1)` (inclusive, clamped to the map)
+    /// with valid cells whose cost goes linearly from `start_cost` in column `c0` to `end_cost` in
+    /// column `c1`, rounded to the nearest integer.
+    pub fn fill_rect_gradient(
+        &mut self,
+        r0: usize,
+        c0: usize,
+        r1: usize,
+        c1: usize,
+        start_cost: usize,
+        end_cost: usize,
+    ) {
+        let cost_at = |col: usize| {
+            if c1 == c0 {
+                return start_cost;
+            }
+            let t = (col as f64 - c0 as f64) / (c1 as f64 - c0 as f64);
+            (start_cost as f64 + t * (end_cost as f64 - start_cost as f64)).round() as usize
+        };
+
+        for row in r0.min(r1)..=r0.max(r1).min(self.rows.saturating_sub(1)) {
+            for col in c0.min(c1)..=c0.max(c1).min(self.columns.saturating_sub(1)) {
+                self.cells[row][col] = Cell::Valid {
+                    cost: cost_at(col),
+                    entry_cost: None,
+                };
+            }
+        }
+    }
+}
+
+/// A MapStorage that uses a rectangular grid of cells (a vec in a vec)
+// TODO: change from vec of vec to one single vec -> better cache friendlyness!
+#[derive(Debug, Clone)]
+pub struct GridStorage<T>(pub(crate) Vec<Vec<T>>);
+
+impl<T: Copy + 'static> MapStorage<T> for GridStorage<T> {
+    type Reference = Point;
+
+    fn is_valid(&self, node: Self::Reference) -> bool {
+        self.0.get(node.row).is_some_and(|row| node.col < row.len())
+    }
+
+    fn get(&self, node: Self::Reference) -> T {
+        self.0[node.row][node.col]
+    }
+
+    fn get_mut(&mut self, node: Self::Reference) -> &mut T {
+        &mut self.0[node.row][node.col]
+    }
+
+    fn approx_memory_bytes(&self) -> usize {
+        let rows = self.0.capacity() * std::mem::size_of::<Vec<T>>();
+        let cells: usize = self.0.iter().map(|row| row.capacity()).sum();
+        std::mem::size_of::<Self>() + rows + cells * std::mem::size_of::<T>()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl<T: Display> Display for GridStorage<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in &self.0 {
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Point {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl Point {
+    /// Returns the point moved by `(rows, columns)`, or None if that would move past row or
+    /// column zero
+    pub fn offset(self, (dr, dc): (isize, isize)) -> Option<Point> {
+        Some(Point {
+            row: self.row.checked_add_signed(dr)?,
+            col: self.col.checked_add_signed(dc)?,
+        })
+    }
+
+    /// Returns the point one step away in the given direction, or None if that would move past
+    /// row or column zero. Note that there is no check against the size of any map.
+    pub fn step(self, direction: Direction) -> Option<Point> {
+        self.offset(direction.delta())
+    }
+
+    /// The length of the shortest way to `other` when moving in eight directions, where diagonal
+    /// moves are √2 long, i.e. the octile distance. See [`OctileGridMap`].
+    ///
+    /// [`OctileGridMap`]: crate::octile::OctileGridMap
+    pub fn octile(self, other: Point) -> f64 {
+        let (diagonal, straight) = self.octile_moves(other);
+        straight as f64 + diagonal as f64 * std::f64::consts::SQRT_2
+    }
+
+    /// The number of diagonal and straight moves of the shortest way to `other` when moving in
+    /// eight directions
+    pub(crate) fn octile_moves(self, other: Point) -> (usize, usize) {
+        let (dr, dc) = (self.row.abs_diff(other.row), self.col.abs_diff(other.col));
+        (dr.min(dc), dr.max(dc) - dr.min(dc))
+    }
+}
+
+impl NodeReference for Point {}
+
+impl BoundedReference for Point {
+    fn is_within(&self, min: &Self, max: &Self) -> bool {
+        (min.row..=max.row).contains(&self.row) && (min.col..=max.col).contains(&self.col)
+    }
+}
+
+impl<C> PathResult<C, Point> {
+    /// Returns the path with all intermediate points on straight runs removed, i.e. only the
+    /// endpoints and the points where the direction changes are kept. Jumps that are not to an
+    /// adjacent cell (such as OneWay teleports) always keep both of their endpoints.
+    pub fn simplify_collinear(&self) -> Vec<Point> {
+        // the step between two points, or None if they are not adjacent
+        let step = |a: Point, b: Point| {
+            let (dr, dc) = (
+                b.row as isize - a.row as isize,
+                b.col as isize - a.col as isize,
+            );
+            (dr.abs() + dc.abs() == 1).then_some((dr, dc))
+        };
+
+        let mut simplified = Vec::with_capacity(self.path.len());
+        for (i, &point) in self.path.iter().enumerate() {
+            if i > 0 && i + 1 < self.path.len() {
+                let before = step(self.path[i - 1], point);
+                let after = step(point, self.path[i + 1]);
+                if before.is_some() && before == after {
+                    continue;
                 }
-                if node.col > 0 && direction != Direction::Right {
-                    points.push((
-                        Point {
-                            col: node.col - 1,
-                            row: node.row,
-                        },
-                        cost,
-                    ));
+            }
+            simplified.push(point);
+        }
+        simplified
+    }
+
+    /// Converts the path to a GeoJSON `Feature` with a `LineString` geometry, e.g. for viewing it
+    /// in QGIS or Leaflet. The top left corner of the map is placed at `(origin_lat, origin_lon)`
+    /// with rows going south and columns going east, and every point is placed at the center of
+    /// its cell. Uses a local flat earth approximation, which is fine for maps spanning a few
+    /// kilometers.
+    pub fn to_geojson(&self, origin_lat: f64, origin_lon: f64, meters_per_cell: f64) -> String {
+        const METERS_PER_DEGREE_LAT: f64 = 111_320.0;
+        let meters_per_degree_lon = METERS_PER_DEGREE_LAT * origin_lat.to_radians().cos();
+
+        let coordinates: Vec<String> = self
+            .path
+            .iter()
+            .map(|p| {
+                let lat =
+                    origin_lat - (p.row as f64 + 0.5) * meters_per_cell / METERS_PER_DEGREE_LAT;
+                let lon =
+                    origin_lon + (p.col as f64 + 0.5) * meters_per_cell / meters_per_degree_lon;
+                // GeoJSON positions are longitude first
+                format!("[{},{}]", lon, lat)
+            })
+            .collect();
+
+        format!(
+            concat!(
+                r#"{{"type":"Feature","properties":{{}},"#,
+                r#""geometry":{{"type":"LineString","coordinates":[{}]}}}}"#
+            ),
+            coordinates.join(",")
+        )
+    }
+
+    /// Renders the map like its `Display` implementation, but with the path drawn on top using
+    /// `*`, and the start and goal marked with `S` and `G`. Useful for debugging.
+    pub fn render_on<MC: RelativeCost + Display, U>(&self, map: &GridMap<MC, U>) -> String {
+        let mut out = String::new();
+        for (row, cells) in map.cells.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                let point = Point { row, col };
+                if point == self.start {
+                    out.push('S');
+                } else if point == self.goal {
+                    out.push('G');
+                } else if self.path.contains(&point) {
+                    out.push('*');
+                } else {
+                    out.push_str(&cell.to_string());
                 }
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
 
-                if node.row < self.rows - 1 && direction != Direction::Up {
-                    points.push((
-                        Point {
-                            row: node.row + 1,
-                            col: node.col,
-                        },
-                        cost,
-                    ));
+/// Writes the costs of the visited cells of a search as a CSV with `rows` lines of `columns`
+/// values each, e.g. for plotting with external tools. Cells that were not visited are left
+/// empty.
+pub fn export_cost_field_csv<C, S>(visited: &S, rows: usize, columns: usize) -> String
+where
+    C: Display + Copy + 'static,
+    S: MapStorage<Visited<C, Point>, Reference = Point>,
+{
+    let mut out = String::new();
+    for row in 0..rows {
+        let line: Vec<String> = (0..columns)
+            .map(|col| match visited.try_get(Point { row, col }) {
+                Some(Visited(Some(item))) => item.cost.to_string(),
+                _ => String::new(),
+            })
+            .collect();
+        out.push_str(&line.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// Returns the clearance of a path, i.e. the smallest number of contiguous passable cells
+/// perpendicular to the direction of travel at any point along it. A clearance of 1 means the path
+/// squeezes through a gap that is only one cell wide. Points where the path turns are measured
+/// across both directions.
+pub fn path_clearance<C: RelativeCost, U, A>(
+    map: &GridMap<C, U>,
+    result: &PathResult<A, Point>,
+) -> usize {
+    let passable = |p: Point| map.get(p).is_some_and(|c| c.is_passable());
+
+    // the number of passable cells in a line through `point`, along `direction` and its opposite
+    let width = |point: Point, direction: Direction| {
+        let mut count = 1;
+        for d in [direction, direction.opposite()] {
+            let mut current = point;
+            while let Some(next) = current.step(d).filter(|p| passable(*p)) {
+                count += 1;
+                current = next;
+            }
+        }
+        count
+    };
+
+    // whether the step between two points is vertical, or None if they are not adjacent
+    let is_vertical = |a: Point, b: Point| {
+        let (dr, dc) = (a.row.abs_diff(b.row), a.col.abs_diff(b.col));
+        (dr + dc == 1).then_some(dr == 1)
+    };
+
+    let path = &result.path;
+    path.iter()
+        .enumerate()
+        .map(|(i, &point)| {
+            let before = (i > 0).then(|| is_vertical(path[i - 1], point)).flatten();
+            let after = path.get(i + 1).and_then(|&next| is_vertical(point, next));
+
+            let (mut vertical, mut horizontal) = (
+                before == Some(true) || after == Some(true),
+                before == Some(false) || after == Some(false),
+            );
+            if !vertical && !horizontal {
+                // not moving to an adjacent cell, measure across both directions
+                (vertical, horizontal) = (true, true);
+            }
+
+            let mut clearance = usize::MAX;
+            if vertical {
+                clearance = clearance.min(width(point, Direction::Left));
+            }
+            if horizontal {
+                clearance = clearance.min(width(point, Direction::Up));
+            }
+            clearance
+        })
+        .min()
+        .unwrap_or(0)
+}
+
+impl<C: RelativeCost + Display, U> GridMap<C, U> {
+    /// Renders the map like its `Display` implementation, but pads every cell to the terminal width
+    /// of the widest glyph so that the columns line up even if some glyphs take up more than one
+    /// terminal column.
+    pub fn render_aligned(&self) -> String {
+        let glyphs: Vec<Vec<String>> = self
+            .cells
+            .iter()
+            .map(|row| row.iter().map(|cell| cell.to_string()).collect())
+            .collect();
+        let width = glyphs
+            .iter()
+            .flatten()
+            .map(|glyph| glyph.width())
+            .max()
+            .unwrap_or(1);
+
+        let mut out = String::new();
+        for row in glyphs {
+            for glyph in row {
+                out.push_str(&glyph);
+                out.push_str(&" ".repeat(width - glyph.width()));
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Exports the graph induced by the map in the Graphviz DOT format, with one node per
+    /// passable cell and one edge labeled with its cost per move returned by `neighbors_of`.
+    /// The teleports of OneWay cells are drawn dashed, even when they land next to the cell.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph {\n");
+        for (row, cells) in self.cells.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                if !cell.is_passable() {
+                    continue;
                 }
-                if node.col < self.columns - 1 && direction != Direction::Left {
-                    points.push((
-                        Point {
-                            col: node.col + 1,
-                            row: node.row,
-                        },
-                        cost,
+                let node = Point { row, col };
+                out.push_str(&format!("    \"{},{}\";\n", row, col));
+
+                let moves = grid_moves(self.rows, self.columns, self.topology, node, |p| {
+                    self.get(p).unwrap_or(Cell::Invalid)
+                });
+                for (p, cost, teleport) in moves {
+                    let style = if teleport {
+                        ", style=dashed, color=blue"
+                    } else {
+                        ""
+                    };
+                    out.push_str(&format!(
+                        "    \"{},{}\" -> \"{},{}\" [label=\"{}\"{}];\n",
+                        row, col, p.row, p.col, cost, style
                     ));
                 }
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+}
 
-                if let Some(target) = target {
-                    points.push((target, cost));
-                }
+impl<C: RelativeCost, U> GridMap<C, U> {
+    /// Renders the map like its `Display` implementation, but with the provided glyph set
+    pub fn render_with(&self, glyphs: &impl CellGlyphs) -> String {
+        let mut out = String::new();
+        for row in &self.cells {
+            out.extend(row.iter().map(|cell| cell.glyph(glyphs)));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+impl<C: RelativeCost + Display, U> Display for GridMap<C, U> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in &self.cells {
+            for cell in row {
+                write!(f, "{}", cell)?;
             }
-            Cell::Invalid => {}
-        };
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the neighbors of `node` in a grid with the given size and topology, where `cell_at` is
+/// used to look up the cells. Shared by all the grid based maps.
+///
+/// Diagonal moves are not allowed to cut corners, i.e. both cells next to the move must be valid.
+/// Knight moves jump, so only the target cell is checked.
+pub(crate) fn grid_neighbors<C: RelativeCost>(
+    rows: usize,
+    columns: usize,
+    topology: Topology,
+    node: Point,
+    cell_at: impl Fn(Point) -> Cell<C>,
+) -> impl Iterator<Item = (Point, C)> {
+    grid_moves(rows, columns, topology, node, cell_at).map(|(p, cost, _)| (p, cost))
+}
+
+/// Like [`grid_neighbors`], but also tells whether each move is the teleport of a OneWay cell
+/// rather than a step of the topology
+pub(crate) fn grid_moves<C: RelativeCost>(
+    rows: usize,
+    columns: usize,
+    topology: Topology,
+    node: Point,
+    cell_at: impl Fn(Point) -> Cell<C>,
+) -> impl Iterator<Item = (Point, C, bool)> {
+    let cell = cell_at(node);
+    let cost = cell.cost();
+    // the direction that can not be moved in and the teleport of OneWay cells
+    let (backwards, target) = match cell {
+        Cell::OneWay {
+            cost,
+            direction,
+            target,
+            target_cost,
+        } => (
+            Some(direction.opposite().delta()),
+            target
+                .filter(|t| *t != node)
+                .map(|t| (t, target_cost.unwrap_or(cost), true)),
+        ),
+        Cell::Valid { .. } | Cell::Invalid => (None, None),
+    };
+    let offsets = if cost.is_some() {
+        topology.offsets()
+    } else {
+        &[]
+    };
+
+    // the candidates are yielded lazily to avoid allocating for every expanded node
+    offsets
+        .iter()
+        // a OneWay cell can be left in any direction except backwards
+        .filter(move |&&(dr, dc)| backwards.map_or(true, |(br, bc)| dr * br + dc * bc <= 0))
+        .filter_map(move |&delta| Some((node.offset(delta)?, cost?, false)))
+        .chain(target)
+        .filter_map(move |(p, cost, teleport)| {
+            // only keep valid cells (a OneWay target might point outside the map)
+            let passable = |p: Point| p.row < rows && p.col < columns && cell_at(p).is_passable();
+            if !passable(p) {
+                return None;
+            }
+            // teleports do not move through the cells in between, so only steps can cut corners
+            let diagonal = p.row.abs_diff(node.row) == 1 && p.col.abs_diff(node.col) == 1;
+            if !teleport
+                && diagonal
+                && !(passable(Point {
+                    row: p.row,
+                    col: node.col,
+                }) && passable(Point {
+                    row: node.row,
+                    col: p.col,
+                }))
+            {
+                return None;
+            }
+
+            // add the entry cost of the cell being moved to
+            match cell_at(p) {
+                Cell::Valid {
+                    entry_cost: Some(entry_cost),
+                    ..
+                } => Some((p, cost + entry_cost, teleport)),
+                _ => Some((p, cost, teleport)),
+            }
+        })
+}
+
+impl<C: RelativeCost, U> MapTrait for GridMap<C, U> {
+    type Reference = Point;
+    type Storage<T: Default + Copy + Clone + 'static> = GridStorage<T>;
+    type Cost = C;
+
+    fn is_valid(&self, node: Self::Reference) -> bool {
+        node.row < self.rows && node.col < self.columns
+    }
 
-        // filter to only keep valid cells
-        points.retain(|(p, _)| self.cells[p.row][p.col] != Cell::Invalid);
+    fn is_passable(&self, node: Self::Reference) -> bool {
+        self.get(node).is_some_and(|cell| cell.is_passable())
+    }
 
-        points.into_iter()
+    fn neighbors_of(&self, node: Self::Reference) -> impl Iterator<Item = (Self::Reference, C)> {
+        grid_neighbors(self.rows, self.columns, self.topology, node, |p| {
+            self.get(p).unwrap_or(Cell::Invalid)
+        })
     }
 
     fn create_storage<T: Default + Copy + Clone + 'static>(&self) -> Self::Storage<T> {
@@ -324,14 +1663,36 @@ impl<C: RelativeCost> MapTrait for GridMap<C> {
     }
 }
 
+/// Uses the fewest moves the topology needs between the two points as the estimate (see
+/// [`Topology::min_moves`]), e.g. the manhattan distance for orthogonal moves and the chebyshev
+/// distance for king moves, which assumes that every move costs at least 1. The estimate is not a
+/// lower bound on maps with cheaper cells or with OneWay teleports, so A* might not find the
+/// optimal path on those.
+impl<U> HeuristicMap for GridMap<usize, U> {
+    fn heuristic(&self, from: Point, to: Point) -> usize {
+        self.topology.min_moves(from, to)
+    }
+}
+
 #[cfg(test)]
-mod test {
+pub(crate) mod test {
 
     use super::*;
-    use crate::find::{PathFinder, PathFinderState, PathResult};
+    use crate::bellman::Signed;
+    use crate::find::{
+        are_connected, find_goals_ordered, find_longest_path_bounded, find_path_max_length,
+        reachable_within, shortest_path_avoiding, AbsoluteCost, FixedPoint, MaxCost, NoPathReason,
+        PathFinder, PathFinderState, VisitedItem,
+    };
+    use crate::octile::OctileGridMap;
+    use std::collections::HashSet;
 
-    fn create_basic_map() -> GridMap<usize> {
+    pub(crate) fn create_basic_map() -> GridMap<usize> {
         use Cell::*;
+        const V: Cell<usize> = Valid {
+            cost: 1,
+            entry_cost: None,
+        };
         GridMap {
             rows: 7,
             columns: 7,
@@ -339,55 +1700,38 @@ mod test {
                 vec![
                     Invalid, Invalid, Invalid, Invalid, Invalid, Invalid, Invalid,
                 ],
-                vec![
-                    Invalid,
-                    Valid { cost: 1 },
-                    Invalid,
-                    Invalid,
-                    Invalid,
-                    Valid { cost: 1 },
-                    Invalid,
-                ],
-                vec![
-                    Invalid,
-                    Valid { cost: 1 },
-                    Invalid,
-                    Invalid,
-                    Invalid,
-                    Valid { cost: 1 },
-                    Invalid,
-                ],
-                vec![
-                    Invalid,
-                    Valid { cost: 1 },
-                    Invalid,
-                    Valid { cost: 1 },
-                    Valid { cost: 1 },
-                    Valid { cost: 1 },
-                    Invalid,
-                ],
-                vec![
-                    Invalid,
-                    Valid { cost: 1 },
-                    Invalid,
-                    Valid { cost: 1 },
-                    Invalid,
-                    Invalid,
-                    Invalid,
-                ],
-                vec![
-                    Invalid,
-                    Valid { cost: 1 },
-                    Valid { cost: 1 },
-                    Valid { cost: 1 },
-                    Valid { cost: 1 },
-                    Valid { cost: 1 },
-                    Valid { cost: 1 },
-                ],
+                vec![Invalid, V, Invalid, Invalid, Invalid, V, Invalid],
+                vec![Invalid, V, Invalid, Invalid, Invalid, V, Invalid],
+                vec![Invalid, V, Invalid, V, V, V, Invalid],
+                vec![Invalid, V, Invalid, V, Invalid, Invalid, Invalid],
+                vec![Invalid, V, V, V, V, V, V],
                 vec![
                     Invalid, Invalid, Invalid, Invalid, Invalid, Invalid, Invalid,
                 ],
             ],
+            meta: HashMap::new(),
+            topology: Topology::default(),
+            tags: TagStorage::default(),
+        }
+    }
+
+    /// Runs an unweighted search on `map` and returns the path it finds, panicking if there is none
+    pub(crate) fn find_path<M>(
+        map: &M,
+        start: M::Reference,
+        goal: M::Reference,
+    ) -> PathResult<M::Cost, M::Reference>
+    where
+        M: MapTrait,
+        M::Cost: AbsoluteCost<CmpContext = ()> + std::fmt::Display + std::fmt::Debug,
+        M::Reference: std::fmt::Debug,
+    {
+        match PathFinder::new(start, goal, map.create_storage(), ())
+            .finish(map)
+            .0
+        {
+            PathFinderState::PathFound(result) => result,
+            state => panic!("expected a path, got {:?}", state),
         }
     }
 
@@ -422,18 +1766,80 @@ mod test {
             visited,
             (),
         );
-        // no route to target
-        assert!(matches!(
+        // no route to target, as it is a wall
+        assert_eq!(
             finder.finish(&map).0,
-            PathFinderState::NoPathFound
-        ));
+            PathFinderState::NoPathFound(NoPathReason::GoalInvalid)
+        );
+    }
+
+    #[test]
+    fn test_basic_walled_off_goal() {
+        let mut map = create_basic_map();
+        // close the only passage leading to the top right corridor
+        map.cells[3][5] = Cell::Invalid;
+
+        let finder = PathFinder::new(
+            Point { row: 1, col: 1 },
+            Point { row: 1, col: 5 },
+            map.create_storage(),
+            (),
+        );
+        assert_eq!(
+            finder.finish(&map).0,
+            PathFinderState::NoPathFound(NoPathReason::GoalUnreachable)
+        );
+    }
+
+    #[test]
+    fn test_basic_invalid_endpoints() {
+        let map = create_basic_map();
+
+        let finder = PathFinder::new(
+            Point { row: 1, col: 1 },
+            Point { row: 10, col: 10 },
+            map.create_storage(),
+            (),
+        );
+        assert_eq!(
+            finder.finish(&map).0,
+            PathFinderState::NoPathFound(NoPathReason::GoalInvalid)
+        );
+
+        // a goal inside the map on a wall is rejected before searching
+        let mut finder = PathFinder::new(
+            Point { row: 1, col: 1 },
+            Point { row: 0, col: 0 },
+            map.create_storage(),
+            (),
+        );
+        assert_eq!(
+            finder.step(&map),
+            PathFinderState::NoPathFound(NoPathReason::GoalInvalid)
+        );
+        assert_eq!(finder.steps(), 0);
+
+        let mut finder = PathFinder::new(
+            Point { row: 7, col: 1 },
+            Point { row: 1, col: 5 },
+            map.create_storage(),
+            (),
+        );
+        assert_eq!(
+            finder.step(&map),
+            PathFinderState::NoPathFound(NoPathReason::StartInvalid)
+        );
+        assert_eq!(finder.steps(), 0);
     }
 
     #[test]
     fn test_basic_shortcut() {
         let mut map = create_basic_map();
         // create higher cost shortcut
-        map.cells[3][2] = Cell::Valid { cost: 2 };
+        map.cells[3][2] = Cell::Valid {
+            cost: 2,
+            entry_cost: None,
+        };
         let visited = map.create_storage();
 
         let finder = PathFinder::new(
@@ -449,7 +1855,10 @@ mod test {
         ));
 
         let visited = map.create_storage();
-        map.cells[3][2] = Cell::Valid { cost: 4 };
+        map.cells[3][2] = Cell::Valid {
+            cost: 4,
+            entry_cost: None,
+        };
 
         let finder = PathFinder::new(
             Point { row: 1, col: 1 },
@@ -464,7 +1873,10 @@ mod test {
         ));
 
         let visited = map.create_storage();
-        map.cells[3][2] = Cell::Valid { cost: 10 };
+        map.cells[3][2] = Cell::Valid {
+            cost: 10,
+            entry_cost: None,
+        };
 
         let finder = PathFinder::new(
             Point { row: 1, col: 1 },
@@ -478,4 +1890,2017 @@ mod test {
             PathFinderState::PathFound(PathResult { total_cost: 12, .. })
         ));
     }
+
+    /// Returns the number of valid cells reachable from `start` by following `neighbors_of`
+    fn count_reachable(map: &GridMap<usize>, start: Point) -> usize {
+        let mut seen = map.create_storage::<bool>();
+        let mut stack = vec![start];
+        *seen.get_mut(start) = true;
+        let mut count = 0;
+        while let Some(point) = stack.pop() {
+            count += 1;
+            for (neighbor, _) in map.neighbors_of(point) {
+                if !seen.get(neighbor) {
+                    *seen.get_mut(neighbor) = true;
+                    stack.push(neighbor);
+                }
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn test_generate_maze_connected() {
+        for seed in [0, 1, 42, 1337] {
+            let mut map = GridMap::generate_maze(21, 31, seed);
+            map.randomize_costs(0.2, 5, seed);
+
+            let valid = map.cells.iter().flatten().filter(|c| **c != Cell::Invalid);
+            assert_eq!(valid.count(), 10 * 15 * 2 - 1);
+            assert_eq!(
+                count_reachable(&map, Point { row: 1, col: 1 }),
+                10 * 15 * 2 - 1
+            );
+        }
+    }
+
+    #[test]
+    fn test_generate_maze_deterministic() {
+        let a = GridMap::generate_maze(15, 15, 7);
+        let b = GridMap::generate_maze(15, 15, 7);
+        let c = GridMap::generate_maze(15, 15, 8);
+        assert_eq!(a.cells, b.cells);
+        assert_ne!(a.cells, c.cells);
+    }
+
+    #[test]
+    fn test_basic_bounds() {
+        let map = create_basic_map();
+
+        // the only route goes through the bottom row, which is outside the bounds
+        let finder = PathFinder::new(
+            Point { row: 1, col: 1 },
+            Point { row: 1, col: 5 },
+            map.create_storage(),
+            (),
+        )
+        .with_bounds(Point { row: 1, col: 1 }, Point { row: 4, col: 5 });
+        // only the left column is reachable within the bounds
+        assert_eq!(
+            finder.finish(&map).0,
+            PathFinderState::NoPathFound(NoPathReason::PrunedByBounds(4))
+        );
+
+        let finder = PathFinder::new(
+            Point { row: 1, col: 1 },
+            Point { row: 1, col: 5 },
+            map.create_storage(),
+            (),
+        )
+        .with_bounds(Point { row: 1, col: 1 }, Point { row: 5, col: 5 });
+        assert!(matches!(
+            finder.finish(&map).0,
+            PathFinderState::PathFound(PathResult { total_cost: 12, .. })
+        ));
+    }
+
+    #[test]
+    fn test_meta() {
+        let mut map: GridMap<usize, &str> = GridMap::new_with_meta(3, 3, 1);
+        map.cells[1][1] = Cell::Invalid;
+
+        let control = Point { row: 0, col: 2 };
+        assert_eq!(map.set_meta(control, "control 1"), None);
+        assert_eq!(map.set_meta(control, "control 2"), Some("control 1"));
+        assert_eq!(map.meta(control), Some(&"control 2"));
+        assert_eq!(map.meta(Point { row: 0, col: 0 }), None);
+
+        // the metadata does not affect the pathfinding
+        let finder = PathFinder::new(
+            Point { row: 0, col: 0 },
+            Point { row: 2, col: 2 },
+            map.create_storage(),
+            (),
+        );
+        assert!(matches!(
+            finder.finish(&map).0,
+            PathFinderState::PathFound(PathResult { total_cost: 4, .. })
+        ));
+
+        // metadata outside of the map is dropped when resizing
+        map.resize(2, 3);
+        assert_eq!(map.meta(control), None);
+    }
+
+    #[test]
+    fn test_storage_try_get() {
+        let map = create_basic_map();
+        let mut storage = map.create_storage::<usize>();
+        *storage.get_mut(Point { row: 6, col: 6 }) = 3;
+
+        assert_eq!(storage.try_get(Point { row: 6, col: 6 }), Some(3));
+        assert_eq!(storage.try_get(Point { row: 7, col: 0 }), None);
+        assert_eq!(storage.try_get(Point { row: 0, col: 100 }), None);
+        assert!(storage.try_get_mut(Point { row: 7, col: 7 }).is_none());
+    }
+
+    #[test]
+    fn test_storage_entry() {
+        let map = create_basic_map();
+        let mut storage = map.create_storage::<usize>();
+
+        let point = Point { row: 2, col: 3 };
+        assert_eq!(*storage.entry(point), 0);
+        *storage.entry(point) += 5;
+        assert_eq!(storage.get(point), 5);
+        assert_eq!(*storage.entry(point), 5);
+    }
+
+    #[test]
+    fn test_oneway_target_out_of_bounds() {
+        let mut map = create_basic_map();
+        map.cells[1][1] = Cell::OneWay {
+            cost: 1,
+            direction: Direction::Down,
+            target: Some(Point { row: 100, col: 100 }),
+            target_cost: None,
+        };
+
+        let finder = PathFinder::new(
+            Point { row: 1, col: 1 },
+            Point { row: 1, col: 5 },
+            map.create_storage(),
+            (),
+        );
+        assert!(matches!(
+            finder.finish(&map).0,
+            PathFinderState::PathFound(PathResult { total_cost: 12, .. })
+        ));
+    }
+
+    #[test]
+    fn test_step_n() {
+        let map = create_basic_map();
+
+        let mut finder = PathFinder::new(
+            Point { row: 1, col: 1 },
+            Point { row: 1, col: 5 },
+            map.create_storage(),
+            (),
+        );
+
+        assert_eq!(finder.step_n(&map, 3), PathFinderState::Computing);
+        assert_eq!(finder.steps(), 3);
+
+        assert!(matches!(
+            finder.step_n(&map, 1000),
+            PathFinderState::PathFound(PathResult { total_cost: 12, .. })
+        ));
+        let steps = finder.steps();
+        assert!(steps < 1003);
+
+        // no more steps are performed once done
+        finder.step_n(&map, 10);
+        assert_eq!(finder.steps(), steps);
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut map = create_basic_map();
+        assert_eq!(map.validate(), vec![]);
+
+        let point = Point { row: 1, col: 1 };
+        map.cells[1][1] = Cell::OneWay {
+            cost: 1,
+            direction: Direction::Down,
+            target: Some(point),
+            target_cost: None,
+        };
+        map.cells[5][1] = Cell::OneWay {
+            cost: 1,
+            direction: Direction::Down,
+            target: Some(Point { row: 0, col: 0 }),
+            target_cost: None,
+        };
+        assert_eq!(
+            map.validate(),
+            vec![
+                MapIssue::SelfTarget(point),
+                MapIssue::TargetInvalid {
+                    from: Point { row: 5, col: 1 },
+                    target: Point { row: 0, col: 0 }
+                }
+            ]
+        );
+
+        // the self target is never returned as a neighbor
+        assert!(map.neighbors_of(point).all(|(p, _)| p != point));
+    }
+
+    #[test]
+    fn test_zero_cost_teleport_cycle() {
+        let mut map = create_basic_map();
+        map.cells[1][1] = Cell::OneWay {
+            cost: 0,
+            direction: Direction::Down,
+            target: Some(Point { row: 1, col: 5 }),
+            target_cost: None,
+        };
+        map.cells[1][5] = Cell::OneWay {
+            cost: 0,
+            direction: Direction::Down,
+            target: Some(Point { row: 1, col: 1 }),
+            target_cost: None,
+        };
+        assert_eq!(map.validate(), vec![]);
+
+        let finder = PathFinder::new(
+            Point { row: 1, col: 1 },
+            Point { row: 5, col: 6 },
+            map.create_storage(),
+            (),
+        );
+        assert!(matches!(
+            finder.finish(&map).0,
+            PathFinderState::PathFound(PathResult { total_cost: 8, .. })
+        ));
+    }
+
+    #[test]
+    fn test_longest_path_bounded() {
+        let map = GridMap::new(3, 3, 1);
+        let (start, goal) = (Point { row: 0, col: 0 }, Point { row: 0, col: 2 });
+
+        let (shortest, _) = PathFinder::new(start, goal, map.create_storage(), ()).finish(&map);
+        assert!(matches!(
+            shortest,
+            PathFinderState::PathFound(PathResult { total_cost: 2, .. })
+        ));
+
+        // with enough steps the path visits every cell
+        let longest = find_longest_path_bounded(&map, start, goal, 20, &()).unwrap();
+        assert_eq!(longest.total_cost, 8);
+        assert_eq!(longest.path.len(), 9);
+        assert_eq!(longest.path.first(), Some(&start));
+        assert_eq!(longest.path.last(), Some(&goal));
+
+        let longest = find_longest_path_bounded(&map, start, goal, 5, &()).unwrap();
+        assert_eq!(longest.total_cost, 4);
+
+        assert_eq!(find_longest_path_bounded(&map, start, goal, 1, &()), None);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_finish_with_timeout() {
+        use std::time::Duration;
+
+        let map = GridMap::generate_maze(301, 301, 3);
+        let (start, goal) = (Point { row: 1, col: 1 }, Point { row: 299, col: 299 });
+        let finder = || PathFinder::new(start, goal, map.create_storage(), ());
+
+        let (state, _) = finder().finish_with_timeout(&map, Duration::from_nanos(1));
+        assert_eq!(state, PathFinderState::Computing);
+
+        let (state, _) = finder().finish_with_timeout(&map, Duration::from_secs(60));
+        assert!(matches!(state, PathFinderState::PathFound(_)));
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_finish_async() {
+        use std::future::Future;
+        use std::pin::pin;
+        use std::sync::Arc;
+        use std::task::{Context, Poll, Wake, Waker};
+
+        struct NoopWaker;
+
+        impl Wake for NoopWaker {
+            fn wake(self: Arc<Self>) {}
+        }
+
+        /// Returns pending once before completing, like yielding to an event loop
+        struct YieldNow(bool);
+
+        impl Future for YieldNow {
+            type Output = ();
+
+            fn poll(mut self: std::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+                if self.0 {
+                    return Poll::Ready(());
+                }
+                self.0 = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+
+        let map = create_basic_map();
+        let (start, goal) = (Point { row: 1, col: 1 }, Point { row: 1, col: 5 });
+        let (expected, _) = PathFinder::new(start, goal, map.create_storage(), ()).finish(&map);
+        let steps = {
+            let mut finder = PathFinder::new(start, goal, map.create_storage(), ());
+            while finder.step(&map) == PathFinderState::Computing {}
+            finder.steps()
+        };
+
+        let mut yields = 0;
+        let (state, _) = {
+            let mut future = pin!(PathFinder::new(start, goal, map.create_storage(), ())
+                .finish_async(&map, 3, || {
+                    yields += 1;
+                    YieldNow(false)
+                }));
+
+            // a minimal executor that polls until the search completes
+            let waker = Waker::from(Arc::new(NoopWaker));
+            let mut cx = Context::from_waker(&waker);
+            loop {
+                if let Poll::Ready(result) = future.as_mut().poll(&mut cx) {
+                    break result;
+                }
+            }
+        };
+
+        assert_eq!(state, expected);
+        assert_eq!(yields, steps.div_ceil(3) - 1);
+    }
+
+    #[test]
+    fn test_to_geojson() {
+        let result = path_result(&[(0, 0), (0, 1), (1, 1), (2, 1)]);
+        let json: serde_json::Value =
+            serde_json::from_str(&result.to_geojson(57.7, 11.9, 10.0)).unwrap();
+
+        assert_eq!(json["type"], "Feature");
+        assert_eq!(json["geometry"]["type"], "LineString");
+        let coordinates = json["geometry"]["coordinates"].as_array().unwrap();
+        assert_eq!(coordinates.len(), 4);
+
+        // the start is half a cell east and south of the origin, as [lon, lat]
+        let (lon, lat) = (
+            coordinates[0][0].as_f64().unwrap(),
+            coordinates[0][1].as_f64().unwrap(),
+        );
+        assert!(lon > 11.9 && lon - 11.9 < 0.0001);
+        assert!(lat < 57.7 && 57.7 - lat < 0.0001);
+
+        // moving down a row goes south without changing the longitude
+        assert_eq!(coordinates[1][0], coordinates[2][0]);
+        assert!(coordinates[2][1].as_f64().unwrap() < coordinates[1][1].as_f64().unwrap());
+    }
+
+    #[test]
+    fn test_from_wall_grid() {
+        // a 2x2 maze with a wall between the two top cells, so the path must go around
+        let map: GridMap<usize> = GridMap::from_wall_grid(2, 2, 1, |p, direction| {
+            p == Point { row: 0, col: 0 } && direction == Direction::Right
+        });
+        assert_eq!(map.to_string(), "XXXXX\nX X X\nX X X\nX   X\nXXXXX\n");
+
+        let (state, _) = PathFinder::new(
+            Point { row: 1, col: 1 },
+            Point { row: 1, col: 3 },
+            map.create_storage(),
+            (),
+        )
+        .finish(&map);
+        assert!(matches!(
+            state,
+            PathFinderState::PathFound(PathResult { total_cost: 6, .. })
+        ));
+    }
+
+    #[test]
+    fn test_shortest_path_avoiding() {
+        let mut map = create_basic_map();
+        // open a shortcut between the left and the middle corridor
+        map.cells[3][2] = Cell::Valid {
+            cost: 1,
+            entry_cost: None,
+        };
+        let (start, goal) = (Point { row: 1, col: 1 }, Point { row: 1, col: 5 });
+        let shortcut = Point { row: 3, col: 2 };
+
+        let result = shortest_path_avoiding(&map, start, goal, &HashSet::new(), ()).unwrap();
+        assert_eq!(result.total_cost, 8);
+        assert!(result.path.contains(&shortcut));
+
+        // with the shortcut closed the path has to go around through the bottom
+        let result =
+            shortest_path_avoiding(&map, start, goal, &HashSet::from([shortcut]), ()).unwrap();
+        assert_eq!(result.total_cost, 12);
+        assert!(!result.path.contains(&shortcut));
+
+        let blocked = HashSet::from([shortcut, Point { row: 4, col: 3 }]);
+        assert_eq!(
+            shortest_path_avoiding(&map, start, goal, &blocked, ()),
+            None
+        );
+        assert_eq!(
+            shortest_path_avoiding(&map, start, goal, &HashSet::from([goal]), ()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_are_connected() {
+        let mut map = create_basic_map();
+        let (start, goal) = (Point { row: 1, col: 1 }, Point { row: 1, col: 5 });
+        assert!(are_connected(&map, start, goal));
+        assert!(are_connected(&map, start, start));
+
+        // cut the only corridor between the two sides
+        map.cells[4][3] = Cell::Invalid;
+        assert!(!are_connected(&map, start, goal));
+        assert!(!are_connected(&map, goal, start));
+        assert!(are_connected(&map, start, Point { row: 5, col: 6 }));
+
+        // a one way cell only connects in one direction
+        map.cells[4][3] = Cell::OneWay {
+            cost: 1,
+            direction: Direction::Up,
+            target: None,
+            target_cost: None,
+        };
+        assert!(are_connected(&map, start, goal));
+        assert!(!are_connected(&map, goal, start));
+
+        assert!(!are_connected(&map, start, Point { row: 9, col: 9 }));
+    }
+
+    #[test]
+    fn test_transforms_remap_targets() {
+        let mut map = GridMap::new(6, 6, 1);
+        let teleport = |target| Cell::OneWay {
+            cost: 1,
+            direction: Direction::Right,
+            target: Some(target),
+            target_cost: None,
+        };
+        map.cells[2][2] = teleport(Point { row: 4, col: 3 });
+        map.cells[3][3] = teleport(Point { row: 0, col: 0 });
+
+        // the teleport keeps pointing at the same logical cell, the one to a cropped away cell is
+        // removed
+        map.crop(Point { row: 1, col: 2 }, 4, 3);
+        assert_eq!((map.rows, map.columns), (4, 3));
+        assert_eq!(map.cells[1][0], teleport(Point { row: 3, col: 1 }));
+        assert_eq!(
+            map.cells[2][1],
+            Cell::OneWay {
+                cost: 1,
+                direction: Direction::Right,
+                target: None,
+                target_cost: None
+            }
+        );
+        assert!(map.validate().is_empty());
+
+        let mut map = GridMap::new(3, 3, 1);
+        map.cells[0][0] = teleport(Point { row: 2, col: 1 });
+        map.cells[0][1] = teleport(Point { row: 1, col: 1 });
+        map.scale_up(2);
+        assert_eq!(map.cells[1][1], teleport(Point { row: 4, col: 2 }));
+
+        map.resize(3, 3);
+        assert_eq!(
+            map.cells[0][0],
+            Cell::OneWay {
+                cost: 1,
+                direction: Direction::Right,
+                target: None,
+                target_cost: None
+            }
+        );
+        assert_eq!(map.cells[0][2], teleport(Point { row: 2, col: 2 }));
+    }
+
+    #[test]
+    fn test_cost_stats() {
+        let mut map = GridMap::new(2, 3, 2);
+        map.cells[0][0] = Cell::Invalid;
+        map.cells[0][1] = Cell::Valid {
+            cost: 8,
+            entry_cost: Some(100),
+        };
+        map.cells[1][2] = Cell::OneWay {
+            cost: 1,
+            direction: Direction::Up,
+            target: None,
+            target_cost: None,
+        };
+
+        assert_eq!(
+            map.cost_stats(),
+            CostStats {
+                min: 1,
+                max: 8,
+                mean: 3.0,
+                valid_count: 5,
+                invalid_count: 1,
+            }
+        );
+
+        map.invert(1);
+        map.cells[0][0] = Cell::Invalid;
+        assert_eq!(
+            map.cost_stats(),
+            CostStats {
+                invalid_count: 6,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_find_goals_ordered() {
+        let map = create_basic_map();
+        let p = |row, col| Point { row, col };
+        // given out of order, with a duplicate and a goal that can not be reached
+        let goals = [p(1, 5), p(5, 6), p(2, 1), p(5, 6), p(0, 0)];
+
+        let results = find_goals_ordered(&map, p(1, 1), &goals, ());
+        let found: Vec<(Point, usize)> = results.iter().map(|r| (r.goal, r.total_cost)).collect();
+        assert_eq!(found, vec![(p(2, 1), 1), (p(5, 6), 9), (p(1, 5), 12)]);
+
+        // the paths are the same as from separate searches
+        for result in results {
+            let (state, _) =
+                PathFinder::new(p(1, 1), result.goal, map.create_storage(), ()).finish(&map);
+            assert_eq!(state, PathFinderState::PathFound(result));
+        }
+    }
+
+    #[test]
+    fn test_settle_order() {
+        let map = create_basic_map();
+        let (start, goal) = (Point { row: 1, col: 1 }, Point { row: 1, col: 5 });
+
+        let mut finder = PathFinder::new(start, goal, map.create_storage(), ());
+        finder.step(&map);
+        assert!(finder.settle_order().is_empty());
+
+        let mut finder = PathFinder::new(start, goal, map.create_storage(), ()).with_settle_order();
+        while finder.step(&map) == PathFinderState::Computing {}
+
+        // every settled node is recorded once, in order of cost, ending at the goal
+        let order = finder.settle_order();
+        let settled = (0..map.rows)
+            .flat_map(|row| (0..map.columns).map(move |col| Point { row, col }))
+            .filter(|p| finder.get_visited().get(*p).is_some())
+            .count();
+        assert_eq!(order.len(), settled);
+        assert!(order.len() <= finder.steps());
+        assert_eq!(order.first(), Some(&(start, 0)));
+        assert_eq!(order.last(), Some(&(goal, 12)));
+        assert!(order.windows(2).all(|w| w[0].1 <= w[1].1));
+    }
+
+    #[test]
+    fn test_storage_memory() {
+        let map = GridMap::new(10, 10, 1);
+        let storage = map.create_storage::<u8>();
+        assert!(storage.approx_memory_bytes() >= 100);
+        assert!(map.create_storage::<u64>().approx_memory_bytes() >= 800);
+    }
+
+    #[test]
+    fn test_reachable_within() {
+        let map = create_basic_map();
+        let p = |row, col| Point { row, col };
+
+        let reached = reachable_within(&map, p(3, 3), 2, &());
+        assert_eq!(reached[0], (p(3, 3), 0));
+        assert_eq!(
+            reached.into_iter().collect::<HashSet<_>>(),
+            HashSet::from([
+                (p(3, 3), 0),
+                (p(4, 3), 1),
+                (p(3, 4), 1),
+                (p(5, 3), 2),
+                (p(3, 5), 2),
+            ])
+        );
+
+        assert_eq!(reachable_within(&map, p(3, 3), 0, &()), vec![(p(3, 3), 0)]);
+        assert!(reachable_within(&map, p(9, 9), 5, &()).is_empty());
+    }
+
+    #[test]
+    fn test_entry_cost() {
+        let mut map = create_basic_map();
+        map.cells[3][2] = Cell::Valid {
+            cost: 10,
+            entry_cost: None,
+        };
+
+        let result = find_path(&map, Point { row: 1, col: 1 }, Point { row: 1, col: 5 });
+        assert_eq!(result.total_cost, 12);
+        assert!(!result.path.contains(&Point { row: 3, col: 2 }));
+
+        // a door on the bottom route makes the expensive shortcut the better option
+        map.cells[5][2] = Cell::Valid {
+            cost: 1,
+            entry_cost: Some(6),
+        };
+        let result = find_path(&map, Point { row: 1, col: 1 }, Point { row: 1, col: 5 });
+        assert_eq!(result.total_cost, 17);
+        assert!(result.path.contains(&Point { row: 3, col: 2 }));
+
+        // maps stored without the entry cost can still be loaded
+        let cell: Cell<usize> = serde_json::from_str(r#"{"Valid":{"cost":3}}"#).unwrap();
+        assert_eq!(
+            cell,
+            Cell::Valid {
+                cost: 3,
+                entry_cost: None
+            }
+        );
+    }
+
+    #[test]
+    fn test_serde_cost_types() {
+        /// A float cost, compared bitwise to be usable as a RelativeCost
+        #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+        struct Meters(f32);
+
+        impl PartialEq for Meters {
+            fn eq(&self, other: &Self) -> bool {
+                self.0.to_bits() == other.0.to_bits()
+            }
+        }
+        impl Eq for Meters {}
+        impl std::ops::Add for Meters {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self {
+                Meters(self.0 + rhs.0)
+            }
+        }
+        impl RelativeCost for Meters {}
+
+        let mut map = GridMap::new(2, 3, Meters(1.5));
+        map.cells[0][1] = Cell::Invalid;
+        map.cells[1][2] = Cell::OneWay {
+            cost: Meters(0.25),
+            direction: Direction::Left,
+            target: Some(Point { row: 0, col: 0 }),
+            target_cost: None,
+        };
+        map.topology = Topology::King;
+
+        let json = serde_json::to_string(&map).unwrap();
+        let restored: GridMap<Meters> = serde_json::from_str(&json).unwrap();
+        assert_eq!((restored.rows, restored.columns), (2, 3));
+        assert_eq!(restored.cells, map.cells);
+        assert_eq!(restored.topology, Topology::King);
+
+        let mut map = GridMap::new(1, 2, MaxCost(4));
+        map.cells[0][1] = Cell::Valid {
+            cost: MaxCost(2),
+            entry_cost: Some(MaxCost(7)),
+        };
+        let restored: GridMap<MaxCost> =
+            serde_json::from_str(&serde_json::to_string(&map).unwrap()).unwrap();
+        assert_eq!(restored.cells, map.cells);
+
+        let map = GridMap::new(1, 1, Signed(-3));
+        let restored: GridMap<Signed> =
+            serde_json::from_str(&serde_json::to_string(&map).unwrap()).unwrap();
+        assert_eq!(restored.cells, map.cells);
+    }
+
+    #[test]
+    fn test_heuristic_weight() {
+        let mut map = GridMap::new(20, 20, 1);
+        let (start, goal) = (Point { row: 0, col: 0 }, Point { row: 19, col: 19 });
+
+        let run = |map: &GridMap<usize>, finder: PathFinder<_, _, _, _, _>| {
+            let mut finder = finder;
+            let state = finder.step_n(map, usize::MAX);
+            let PathFinderState::PathFound(result) = state else {
+                panic!("expected a path");
+            };
+            (result.total_cost, finder.steps())
+        };
+
+        // on an open map every cell between the corners has the same priority, ties are broken
+        // towards the goal so only one path of them is expanded
+        let (astar_cost, astar_steps) = run(
+            &map,
+            PathFinder::new(start, goal, map.create_storage(), ()).with_heuristic(1.0),
+        );
+        assert_eq!(astar_cost, 38);
+        assert_eq!(astar_steps, 39);
+
+        // a wall the heuristic does not know about, the way around it is no longer a straight line
+        for row in 0..19 {
+            map.cells[row][10] = Cell::Invalid;
+        }
+        let goal = Point { row: 0, col: 19 };
+        let (dijkstra_cost, dijkstra_steps) =
+            run(&map, PathFinder::new(start, goal, map.create_storage(), ()));
+        let (astar_cost, astar_steps) = run(
+            &map,
+            PathFinder::new(start, goal, map.create_storage(), ()).with_heuristic(1.0),
+        );
+        let (weighted_cost, weighted_steps) = run(
+            &map,
+            PathFinder::new(start, goal, map.create_storage(), ()).with_heuristic(3.0),
+        );
+
+        assert_eq!(dijkstra_cost, 57);
+        assert_eq!(astar_cost, dijkstra_cost);
+        assert!(weighted_cost <= 3 * dijkstra_cost);
+        assert!(astar_steps <= dijkstra_steps);
+        assert!(weighted_steps < astar_steps);
+    }
+
+    #[test]
+    fn test_heuristic_optimal_with_costs() {
+        // with a weight of 1 A* must find the same cost as dijkstra
+        for seed in 0..5 {
+            let mut map = GridMap::generate_maze(21, 21, seed);
+            map.randomize_costs(0.3, 5, seed);
+            let (start, goal) = (Point { row: 1, col: 1 }, Point { row: 19, col: 19 });
+
+            let (dijkstra, _) = PathFinder::new(start, goal, map.create_storage(), ()).finish(&map);
+            let (astar, _) = PathFinder::new(start, goal, map.create_storage(), ())
+                .with_heuristic(1.0)
+                .finish(&map);
+            assert_eq!(dijkstra, astar);
+        }
+    }
+
+    #[test]
+    fn test_point_step() {
+        let p = Point { row: 0, col: 0 };
+        assert_eq!(p.step(Direction::Up), None);
+        assert_eq!(p.step(Direction::Left), None);
+        assert_eq!(p.step(Direction::Down), Some(Point { row: 1, col: 0 }));
+        assert_eq!(p.step(Direction::Right), Some(Point { row: 0, col: 1 }));
+
+        let p = Point { row: 3, col: 5 };
+        assert_eq!(p.step(Direction::Up), Some(Point { row: 2, col: 5 }));
+        assert_eq!(p.step(Direction::Left), Some(Point { row: 3, col: 4 }));
+    }
+
+    #[test]
+    fn test_direction_opposite() {
+        for direction in [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ] {
+            let opposite = direction.opposite();
+            assert_ne!(direction, opposite);
+            assert_eq!(opposite.opposite(), direction);
+
+            let (dr, dc) = direction.delta();
+            assert_eq!(opposite.delta(), (-dr, -dc));
+        }
+    }
+
+    fn path_result(path: &[(usize, usize)]) -> PathResult<usize, Point> {
+        let path: Vec<Point> = path.iter().map(|&(row, col)| Point { row, col }).collect();
+        PathResult {
+            start: path[0],
+            goal: *path.last().unwrap(),
+            total_cost: path.len() - 1,
+            path,
+        }
+    }
+
+    #[test]
+    fn test_checked_cell_access() {
+        let mut map = create_basic_map();
+
+        assert_eq!(map.get(Point { row: 7, col: 0 }), None);
+        assert_eq!(map.get(Point { row: 0, col: 7 }), None);
+        assert!(map.get_mut(Point { row: 100, col: 100 }).is_none());
+
+        *map.get_mut(Point { row: 0, col: 0 }).unwrap() = Cell::Valid {
+            cost: 3,
+            entry_cost: None,
+        };
+        assert_eq!(
+            map.get(Point { row: 0, col: 0 }),
+            Some(Cell::Valid {
+                cost: 3,
+                entry_cost: None
+            })
+        );
+    }
+
+    #[test]
+    fn test_fill_rect_gradient() {
+        let cost_of = |map: &GridMap<usize>, row: usize, col: usize| match map.cells[row][col] {
+            Cell::Valid { cost, .. } => cost,
+            _ => panic!("expected a valid cell"),
+        };
+
+        let mut map = GridMap::new(1, 5, 9);
+        map.fill_rect_gradient(0, 0, 0, 4, 1, 5);
+        for col in 0..5 {
+            assert_eq!(cost_of(&map, 0, col), col + 1);
+        }
+
+        // decreasing costs that do not divide evenly are rounded
+        let mut map = GridMap::new(2, 4, 9);
+        map.fill_rect_gradient(0, 0, 1, 3, 4, 0);
+        for row in 0..2 {
+            let costs: Vec<usize> = (0..4).map(|col| cost_of(&map, row, col)).collect();
+            assert_eq!(costs, vec![4, 3, 1, 0]);
+        }
+    }
+
+    #[test]
+    fn test_neighbors_where() {
+        let mut map = GridMap::new(3, 3, 1);
+        // entering the cells to the left and below is more expensive
+        for (row, col) in [(1, 0), (2, 1)] {
+            map.cells[row][col] = Cell::Valid {
+                cost: 1,
+                entry_cost: Some(2),
+            };
+        }
+
+        let center = Point { row: 1, col: 1 };
+        assert_eq!(map.neighbors_of(center).count(), 4);
+
+        let cheap: Vec<Point> = map
+            .neighbors_where(center, |_, cost| cost <= 1)
+            .map(|(p, _)| p)
+            .collect();
+        assert_eq!(
+            cheap,
+            vec![Point { row: 0, col: 1 }, Point { row: 1, col: 2 }]
+        );
+
+        // only vertical neighbors
+        let vertical = map.neighbors_where(center, |p, _| p.col == center.col);
+        assert_eq!(vertical.count(), 2);
+    }
+
+    #[test]
+    fn test_neighbors_of_one_way_king() {
+        let mut map = GridMap::new(4, 4, 1);
+        map.topology = Topology::King;
+        map.cells[1][1] = Cell::OneWay {
+            cost: 1,
+            direction: Direction::Right,
+            target: Some(Point { row: 3, col: 3 }),
+            target_cost: None,
+        };
+        map.cells[0][2] = Cell::Invalid;
+        map.cells[2][1] = Cell::Valid {
+            cost: 1,
+            entry_cost: Some(2),
+        };
+
+        // nothing to the left, the wall blocks up-right and the target comes last
+        let neighbors: Vec<(Point, usize)> = map.neighbors_of(Point { row: 1, col: 1 }).collect();
+        assert_eq!(
+            neighbors,
+            vec![
+                (Point { row: 0, col: 1 }, 1),
+                (Point { row: 2, col: 1 }, 3),
+                (Point { row: 1, col: 2 }, 1),
+                (Point { row: 2, col: 2 }, 1),
+                (Point { row: 3, col: 3 }, 1),
+            ]
+        );
+        assert_eq!(map.neighbors_of(Point { row: 0, col: 2 }).count(), 0);
+    }
+
+    #[test]
+    fn test_one_way_target_between_walls() {
+        // the teleport lands diagonally next to the OneWay cell, between two walls
+        let mut map = GridMap::new(3, 3, 1);
+        map.cells[1][1] = Cell::OneWay {
+            cost: 1,
+            direction: Direction::Up,
+            target: Some(Point { row: 2, col: 2 }),
+            target_cost: None,
+        };
+        map.cells[1][2] = Cell::Invalid;
+        map.cells[2][1] = Cell::Invalid;
+
+        // it does not move through the walls, so it can not cut their corner
+        for topology in [Topology::Orthogonal, Topology::King] {
+            map.topology = topology;
+            let neighbors: Vec<(Point, usize)> =
+                map.neighbors_of(Point { row: 1, col: 1 }).collect();
+            assert!(
+                neighbors.contains(&(Point { row: 2, col: 2 }, 1)),
+                "{:?}",
+                topology
+            );
+        }
+        assert_eq!(
+            find_path(&map, Point { row: 0, col: 1 }, Point { row: 2, col: 2 }).total_cost,
+            2
+        );
+    }
+
+    #[test]
+    fn test_frontier_excludes_settled() {
+        let map = GridMap::new(3, 3, 1);
+        let center = Point { row: 1, col: 1 };
+        let mut pathfinder = PathFinder::new(
+            Point { row: 0, col: 0 },
+            Point { row: 2, col: 2 },
+            map.create_storage(),
+            (),
+        );
+
+        // the center is reached from two sides, so a stale entry stays behind once it is settled
+        while pathfinder.get_visited().get(center).0.is_none() {
+            pathfinder.step(&map);
+        }
+
+        let frontier: HashSet<Point> = pathfinder.iter_frontier().collect();
+        assert!(!frontier.is_empty());
+        assert!(!frontier.contains(&center));
+        for point in frontier {
+            assert!(pathfinder.get_visited().get(point).0.is_none());
+        }
+    }
+
+    #[test]
+    fn test_export_cost_field_csv() {
+        let map = create_basic_map();
+        let (start, goal) = (Point { row: 1, col: 1 }, Point { row: 1, col: 5 });
+        let (state, visited) = PathFinder::new(start, goal, map.create_storage(), ()).finish(&map);
+        let PathFinderState::PathFound(result) = state else {
+            panic!("expected a path, got {:?}", state);
+        };
+
+        let csv = export_cost_field_csv(&visited, map.rows, map.columns);
+        let lines: Vec<Vec<&str>> = csv.lines().map(|l| l.split(',').collect()).collect();
+        assert_eq!(lines.len(), 7);
+        assert!(lines.iter().all(|l| l.len() == 7));
+        assert_eq!(lines[1][1], "0");
+        assert_eq!(lines[1][5], result.total_cost.to_string());
+        // walls are never visited
+        assert_eq!(lines[0][0], "");
+    }
+
+    #[test]
+    fn test_one_way_target_cost() {
+        let mut map = create_basic_map();
+        let (start, goal) = (Point { row: 1, col: 1 }, Point { row: 1, col: 5 });
+        let teleport = |target_cost| Cell::OneWay {
+            cost: 1,
+            direction: Direction::Down,
+            target: Some(goal),
+            target_cost,
+        };
+        let total_cost = |map: &GridMap<usize>| find_path(map, start, goal).total_cost;
+
+        map.cells[1][1] = teleport(None);
+        assert_eq!(total_cost(&map), 1);
+        map.cells[1][1] = teleport(Some(3));
+        assert_eq!(total_cost(&map), 3);
+        // walking around is cheaper than an expensive teleport
+        map.cells[1][1] = teleport(Some(50));
+        assert_eq!(total_cost(&map), 12);
+
+        // maps stored before the target cost existed use the cost of the cell
+        let cell: Cell<usize> =
+            serde_json::from_str(r#"{"OneWay":{"cost":1,"direction":"Down","target":null}}"#)
+                .unwrap();
+        assert_eq!(
+            cell,
+            Cell::OneWay {
+                cost: 1,
+                direction: Direction::Down,
+                target: None,
+                target_cost: None,
+            }
+        );
+        assert!(!serde_json::to_string(&cell)
+            .unwrap()
+            .contains("target_cost"));
+    }
+
+    #[test]
+    fn test_from_movingai() {
+        let input = "type octile\nheight 4\nwidth 6\nmap\n\
+                     ..@...\n\
+                     .T@.W.\n\
+                     ..SS..\n\
+                     @@@@@G\n";
+        let map = GridMap::from_movingai(input).unwrap();
+        assert_eq!((map.rows, map.columns), (4, 6));
+        assert_eq!(map.topology, Topology::King);
+        assert_eq!(map.cells[1][1], Cell::Invalid);
+        assert_eq!(map.cells[1][4], Cell::Invalid);
+
+        // the wall can only be passed through the swamp
+        let (start, goal) = (Point { row: 0, col: 0 }, Point { row: 3, col: 5 });
+        let result = find_path(&map, start, goal);
+        assert_eq!(result.total_cost, 8);
+        assert!(result.path.contains(&Point { row: 2, col: 2 }));
+
+        // making the swamp impassable cuts the map in two
+        let map = GridMap::from_movingai_with_costs(input, None, None).unwrap();
+        assert!(!are_connected(&map, start, goal));
+
+        assert!(GridMap::from_movingai("type hex\nheight 1\nwidth 1\nmap\n.").is_err());
+        assert!(GridMap::from_movingai("type octile\nheight 2\nwidth 1\nmap\n.").is_err());
+        assert!(GridMap::from_movingai("type octile\nheight 1\nwidth 1\nmap\nx").is_err());
+    }
+
+    #[test]
+    fn test_multi_start() {
+        let map = create_basic_map();
+        let (far, near) = (Point { row: 1, col: 1 }, Point { row: 3, col: 5 });
+        let goal = Point { row: 1, col: 5 };
+
+        for starts in [[far, near], [near, far]] {
+            let (state, _) = PathFinder::new_multi_start(starts, goal, map.create_storage(), ())
+                .unwrap()
+                .finish(&map);
+            let PathFinderState::PathFound(result) = state else {
+                panic!("expected a path, got {:?}", state);
+            };
+            assert_eq!(result.start, near);
+            assert_eq!(result.path.first(), Some(&near));
+            assert_eq!(result.total_cost, 2);
+        }
+
+        let outside = Point { row: 10, col: 10 };
+        let (state, _) =
+            PathFinder::new_multi_start([far, outside], goal, map.create_storage(), ())
+                .unwrap()
+                .finish(&map);
+        assert_eq!(
+            state,
+            PathFinderState::NoPathFound(NoPathReason::StartInvalid)
+        );
+
+        let empty: [Point; 0] = [];
+        let finder = PathFinder::new_multi_start(empty, goal, map.create_storage(), ());
+        assert!(finder.map(|f| f.finish(&map)).is_none());
+    }
+
+    #[test]
+    fn test_tags() {
+        let mut map = GridMap::new(5, 70, 1);
+        // a region that spans several words of the bitset
+        let region: Vec<Point> = (1..3)
+            .flat_map(|row| (60..65).map(move |col| Point { row, col }))
+            .collect();
+        for &p in &region {
+            map.set_tag(p, "water", true);
+        }
+        map.set_tag(Point { row: 4, col: 0 }, "indoor", true);
+
+        assert_eq!(map.tagged_cells("water").collect::<Vec<_>>(), region);
+        assert!(map.has_tag(Point { row: 1, col: 60 }, "water"));
+        assert!(!map.has_tag(Point { row: 1, col: 60 }, "indoor"));
+        assert!(!map.has_tag(Point { row: 10, col: 60 }, "water"));
+        assert_eq!(
+            map.tags.names().collect::<Vec<_>>(),
+            vec!["indoor", "water"]
+        );
+
+        map.set_tag(Point { row: 4, col: 0 }, "indoor", false);
+        assert_eq!(map.tags.names().collect::<Vec<_>>(), vec!["water"]);
+
+        // tags move along with the cells
+        map.insert_row(0);
+        map.delete_col(0);
+        assert!(map.has_tag(Point { row: 2, col: 59 }, "water"));
+        assert_eq!(map.tagged_cells("water").count(), region.len());
+
+        // maps stored before tags existed have none
+        let json = r#"{"rows":1,"columns":2,"cells":[["Invalid","Invalid"]]}"#;
+        let mut restored: GridMap<usize> = serde_json::from_str(json).unwrap();
+        assert!(!restored.has_tag(Point { row: 0, col: 1 }, "water"));
+        restored.set_tag(Point { row: 0, col: 1 }, "water", true);
+        assert_eq!(
+            restored.tagged_cells("water").collect::<Vec<_>>(),
+            vec![Point { row: 0, col: 1 }]
+        );
+    }
+
+    #[test]
+    fn test_suggest_endpoints() {
+        let map = create_basic_map();
+        let (start, goal) = map.suggest_endpoints().unwrap();
+        assert_ne!(start, goal);
+        assert!(map.get(start).is_some_and(|c| c != Cell::Invalid));
+        assert!(map.get(goal).is_some_and(|c| c != Cell::Invalid));
+        assert!(are_connected(&map, start, goal));
+
+        // too large for the exact diameter
+        let maze = GridMap::generate_maze(81, 81, 5);
+        assert!(maze.valid_count() > MAX_ALL_PAIRS_CELLS);
+        let (start, goal) = maze.suggest_endpoints().unwrap();
+        assert_ne!(start, goal);
+        assert!(are_connected(&maze, start, goal));
+
+        let mut single = GridMap::new(1, 2, 1);
+        single.cells[0][1] = Cell::Invalid;
+        assert_eq!(single.suggest_endpoints(), None);
+    }
+
+    #[test]
+    fn test_fixed_point_diagonals() {
+        let mut grid = GridMap::new(5, 5, 1);
+        grid.topology = Topology::King;
+        let map = OctileGridMap::new(&grid);
+        let (start, goal) = (Point { row: 0, col: 0 }, Point { row: 4, col: 2 });
+        let result = find_path(&map, start, goal);
+
+        // two diagonal and two straight moves, rather than zig-zagging diagonally
+        assert_eq!(result.total_cost, FixedPoint(4828));
+        let diagonals = result
+            .path
+            .windows(2)
+            .filter(|w| w[0].row != w[1].row && w[0].col != w[1].col)
+            .count();
+        assert_eq!(diagonals, 2);
+
+        assert_eq!(result.total_cost.to_string(), "4.828");
+        assert_eq!(FixedPoint::<1000>::from_f64(-0.25).to_string(), "-0.250");
+        assert_eq!(FixedPoint::<4>(7).to_string(), "1.75");
+        assert_eq!(FixedPoint::<1>(7).to_string(), "7");
+        assert_eq!(
+            FixedPoint::<10>(i64::MAX) + FixedPoint(1),
+            FixedPoint(i64::MAX)
+        );
+    }
+
+    #[test]
+    fn test_from_cost_grid() {
+        let map = GridMap::from_cost_grid(&[&[1, 6, 1], &[1, -1, 1], &[1, 1, 1]]).unwrap();
+        assert_eq!((map.rows, map.columns), (3, 3));
+        assert_eq!(map.cells[1][1], Cell::Invalid);
+        assert_eq!(
+            map.cells[0][1],
+            Cell::Valid {
+                cost: 6,
+                entry_cost: None
+            }
+        );
+
+        // around the wall, avoiding the expensive cell
+        let (start, goal) = (Point { row: 0, col: 0 }, Point { row: 0, col: 2 });
+        let result = find_path(&map, start, goal);
+        assert_eq!(result.total_cost, 6);
+        assert_eq!(result.path.len(), 7);
+
+        assert!(GridMap::from_cost_grid(&[&[1, 1], &[1]]).is_err());
+        let empty = GridMap::from_cost_grid(&[]).unwrap();
+        assert_eq!((empty.rows, empty.columns), (0, 0));
+    }
+
+    #[test]
+    fn test_swapped_endpoints() {
+        let cost = |map: &GridMap<usize>, start, goal| find_path(map, start, goal).total_cost;
+        let (a, b) = (Point { row: 1, col: 1 }, Point { row: 1, col: 5 });
+
+        // without OneWay cells the map is undirected
+        let mut map = create_basic_map();
+        assert_eq!(cost(&map, a, b), cost(&map, b, a));
+
+        // a teleport only shortens the way there
+        map.cells[1][1] = Cell::OneWay {
+            cost: 1,
+            direction: Direction::Down,
+            target: Some(b),
+            target_cost: None,
+        };
+        assert_eq!(cost(&map, a, b), 1);
+        assert_eq!(cost(&map, b, a), 12);
+    }
+
+    #[test]
+    fn test_find_path_max_length() {
+        // the direct way is expensive, the cheap detour takes two more moves
+        let mut map = GridMap::new(3, 5, 1);
+        for col in 1..=3 {
+            map.cells[0][col] = Cell::Valid {
+                cost: 5,
+                entry_cost: None,
+            };
+        }
+        let (start, goal) = (Point { row: 0, col: 0 }, Point { row: 0, col: 4 });
+
+        let detour = find_path_max_length(&map, start, goal, 10, ()).unwrap();
+        assert_eq!(detour.total_cost, 6);
+        assert_eq!(detour.path.len(), 7);
+
+        let direct = find_path_max_length(&map, start, goal, 5, ()).unwrap();
+        assert_eq!(direct.total_cost, 16);
+        assert_eq!(direct.path.len(), 5);
+        assert!(direct.path.iter().all(|p| p.row == 0));
+
+        assert_eq!(find_path_max_length(&map, start, goal, 3, ()), None);
+        assert_eq!(
+            find_path_max_length(&map, start, start, 0, ()).map(|r| r.path),
+            Some(vec![start])
+        );
+    }
+
+    #[test]
+    fn test_seal_diagonal_gaps() {
+        // whether any two walls only touch at a corner
+        let has_gaps = |map: &GridMap<usize>| {
+            let wall = |row: usize, col: usize| !map.cells[row][col].is_passable();
+            (0..map.rows - 1).any(|row| {
+                (0..map.columns - 1).any(|col| {
+                    let (a, b) = (wall(row, col), wall(row, col + 1));
+                    let (c, d) = (wall(row + 1, col), wall(row + 1, col + 1));
+                    (a && d && !b && !c) || (b && c && !a && !d)
+                })
+            })
+        };
+
+        // a staircase wall from the top right to the bottom left, made of diagonal steps only
+        let mut map = GridMap::new(6, 6, 1);
+        for i in 0..6 {
+            map.cells[i][5 - i] = Cell::Invalid;
+        }
+        assert!(has_gaps(&map));
+
+        assert_eq!(map.seal_diagonal_gaps(), 5);
+        assert!(!has_gaps(&map));
+        assert_eq!(map.valid_count(), 36 - 6 - 5);
+        // of two open cells with as many walls around them, the lower one is sealed
+        assert_eq!(map.cells[1][5], Cell::Invalid);
+        assert!(map.cells[0][4].is_passable());
+        assert_eq!(map.seal_diagonal_gaps(), 0);
+
+        // walls that touch along an edge or not at all are left alone
+        let mut map = create_basic_map();
+        assert_eq!(map.seal_diagonal_gaps(), 0);
+        assert_eq!(map.cells, create_basic_map().cells);
+    }
+
+    #[test]
+    fn test_cell_passability() {
+        let cells: [(Cell<usize>, Option<usize>); 4] = [
+            (Cell::Invalid, None),
+            (
+                Cell::Valid {
+                    cost: 2,
+                    entry_cost: Some(5),
+                },
+                Some(2),
+            ),
+            (
+                Cell::OneWay {
+                    cost: 3,
+                    direction: Direction::Left,
+                    target: None,
+                    target_cost: None,
+                },
+                Some(3),
+            ),
+            (
+                Cell::OneWay {
+                    cost: 4,
+                    direction: Direction::Up,
+                    target: Some(Point { row: 0, col: 0 }),
+                    target_cost: Some(1),
+                },
+                Some(4),
+            ),
+        ];
+        for (cell, cost) in cells {
+            assert_eq!(cell.cost(), cost, "{:?}", cell);
+            assert_eq!(cell.is_passable(), cost.is_some(), "{:?}", cell);
+        }
+    }
+
+    #[test]
+    fn test_open_and_closed_counts() {
+        // varying costs make nodes reachable in several ways, leaving stale entries in the heap
+        let mut map = GridMap::new(15, 21, 1);
+        map.randomize_costs(0.4, 6, 4);
+        let (start, goal) = (Point { row: 1, col: 1 }, Point { row: 13, col: 19 });
+        let mut finder = PathFinder::new(start, goal, map.create_storage(), ())
+            .with_settle_order()
+            .with_open_count();
+        assert_eq!(finder.open_count(), Some(1));
+
+        let mut stale = 0;
+        while matches!(finder.state(), PathFinderState::Computing) {
+            let settled = finder.nodes_visited();
+            finder.step(&map);
+            if finder.nodes_visited() == settled {
+                stale += 1;
+            }
+            assert_eq!(finder.nodes_visited(), finder.steps() - stale);
+            assert_eq!(finder.nodes_visited(), finder.settle_order().len());
+            assert_eq!(finder.open_count(), Some(finder.iter_frontier().count()));
+        }
+        assert!(stale > 0);
+        assert!(matches!(finder.state(), PathFinderState::PathFound(_)));
+    }
+
+    #[test]
+    fn test_invert() {
+        let original = create_basic_map();
+        let mut map = create_basic_map();
+        map.cells[1][1] = Cell::OneWay {
+            cost: 1,
+            direction: Direction::Down,
+            target: None,
+            target_cost: None,
+        };
+
+        map.invert(5);
+        for (row, cells) in map.cells.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                match original.cells[row][col] {
+                    Cell::Invalid => assert_eq!(
+                        *cell,
+                        Cell::Valid {
+                            cost: 5,
+                            entry_cost: None
+                        }
+                    ),
+                    _ => assert_eq!(*cell, Cell::Invalid),
+                }
+            }
+        }
+
+        // inverting again restores which cells are valid
+        map.invert(1);
+        assert_eq!(map.cells, original.cells);
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let map = create_basic_map();
+        let hash = map.content_hash();
+        assert_eq!(create_basic_map().content_hash(), hash);
+
+        let mut changed = create_basic_map();
+        changed.cells[1][1] = Cell::Valid {
+            cost: 2,
+            entry_cost: None,
+        };
+        assert_ne!(changed.content_hash(), hash);
+
+        // the same cells in a different shape
+        let flat = GridMap::new(1, 4, 1);
+        let tall = GridMap::new(4, 1, 1);
+        assert_ne!(flat.content_hash(), tall.content_hash());
+    }
+
+    #[test]
+    fn test_path_set() {
+        let map = create_basic_map();
+        let finder = PathFinder::new(
+            Point { row: 1, col: 1 },
+            Point { row: 1, col: 5 },
+            map.create_storage(),
+            (),
+        );
+        let (PathFinderState::PathFound(result), visited) = finder.finish(&map) else {
+            panic!("expected a path");
+        };
+
+        let on_path = result.path_set();
+        assert_eq!(on_path.len(), result.path.len());
+        assert!(on_path.contains(&Point { row: 5, col: 3 }));
+
+        // the dead end at the bottom right is visited, but not on the path
+        let dead_end = Point { row: 5, col: 6 };
+        assert!(visited.get(dead_end).is_some());
+        assert!(!on_path.contains(&dead_end));
+    }
+
+    #[test]
+    fn test_render_on() {
+        let map = create_basic_map();
+        let result = find_path(&map, Point { row: 1, col: 1 }, Point { row: 1, col: 5 });
+
+        let rendered = result.render_on(&map);
+        assert_eq!(rendered.matches('S').count(), 1);
+        assert_eq!(rendered.matches('G').count(), 1);
+        // the path has 13 nodes including the start and goal
+        assert_eq!(rendered.matches('*').count(), 11);
+        assert_eq!(rendered.lines().nth(1), Some("XSXXXGX"));
+    }
+
+    #[test]
+    fn test_borrowed_finders() {
+        fn assert_sync<T: Sync>(_: &T) {}
+
+        let map = create_basic_map();
+        assert_sync(&map);
+
+        // two independent searches borrowing the same map
+        let mut first =
+            PathFinder::new_borrowed(&map, Point { row: 1, col: 1 }, Point { row: 1, col: 5 }, ());
+        let second =
+            PathFinder::new_borrowed(&map, Point { row: 1, col: 5 }, Point { row: 5, col: 1 }, ());
+
+        assert_eq!(first.step_n(3), PathFinderState::Computing);
+        assert_eq!(first.steps(), 3);
+        assert!(matches!(
+            second.finish().0,
+            PathFinderState::PathFound(PathResult { total_cost: 8, .. })
+        ));
+        assert!(matches!(
+            first.finish().0,
+            PathFinderState::PathFound(PathResult { total_cost: 12, .. })
+        ));
+    }
+
+    #[test]
+    fn test_bottleneck_path() {
+        // a short route along the top through an expensive cell, and a long but cheap route
+        // around the bottom
+        fn create_map<C: RelativeCost>(cost: impl Fn(usize) -> C) -> GridMap<C> {
+            let mut map = GridMap::new(3, 5, cost(3));
+            for col in [0, 1, 3, 4] {
+                map.cells[0][col] = Cell::Valid {
+                    cost: cost(1),
+                    entry_cost: None,
+                };
+            }
+            map.cells[0][2] = Cell::Valid {
+                cost: cost(9),
+                entry_cost: None,
+            };
+            for col in 1..=3 {
+                map.cells[1][col] = Cell::Invalid;
+            }
+            map
+        }
+        let (start, goal) = (Point { row: 0, col: 0 }, Point { row: 0, col: 4 });
+
+        let map = create_map(|c| c);
+        let result = find_path(&map, start, goal);
+        assert_eq!(result.total_cost, 12);
+        assert!(result.path.contains(&Point { row: 0, col: 2 }));
+
+        let map = create_map(MaxCost);
+        let result = find_path(&map, start, goal);
+        assert_eq!(result.total_cost, MaxCost(3));
+        assert!(result.path.contains(&Point { row: 2, col: 2 }));
+    }
+
+    #[test]
+    fn test_path_clearance() {
+        let mut map = GridMap::new(5, 7, 1);
+
+        let find = |map: &GridMap<usize>| {
+            find_path(map, Point { row: 2, col: 0 }, Point { row: 2, col: 6 })
+        };
+
+        // an open map is only limited by its height
+        assert_eq!(path_clearance(&map, &find(&map)), 5);
+
+        // a wall across the middle column with a single gap
+        for row in [0, 1, 3, 4] {
+            map.cells[row][3] = Cell::Invalid;
+        }
+        let result = find(&map);
+        assert!(result.path.contains(&Point { row: 2, col: 3 }));
+        assert_eq!(path_clearance(&map, &result), 1);
+    }
+
+    #[test]
+    fn test_simplify_collinear() {
+        let result = path_result(&[(0, 0), (0, 1), (0, 2), (0, 3), (1, 3), (2, 3)]);
+        assert_eq!(
+            result.simplify_collinear(),
+            vec![
+                Point { row: 0, col: 0 },
+                Point { row: 0, col: 3 },
+                Point { row: 2, col: 3 }
+            ]
+        );
+
+        // the endpoints of a teleport must be kept even if the steps around it are collinear
+        let result = path_result(&[(0, 0), (0, 1), (5, 5), (5, 6), (5, 7)]);
+        assert_eq!(
+            result.simplify_collinear(),
+            vec![
+                Point { row: 0, col: 0 },
+                Point { row: 0, col: 1 },
+                Point { row: 5, col: 5 },
+                Point { row: 5, col: 7 }
+            ]
+        );
+    }
+
+    fn neighbor_set(map: &GridMap<usize>, row: usize, col: usize) -> HashSet<(usize, usize)> {
+        map.neighbors_of(Point { row, col })
+            .map(|(p, _)| (p.row, p.col))
+            .collect()
+    }
+
+    #[test]
+    fn test_topologies() {
+        let mut map = GridMap::new(5, 5, 1);
+        let expected: [(Topology, &[(usize, usize)]); 4] = [
+            (Topology::Orthogonal, &[(1, 2), (2, 1), (3, 2), (2, 3)]),
+            (Topology::Diagonal, &[(1, 1), (3, 1), (3, 3), (1, 3)]),
+            (
+                Topology::King,
+                &[
+                    (1, 2),
+                    (2, 1),
+                    (3, 2),
+                    (2, 3),
+                    (1, 1),
+                    (3, 1),
+                    (3, 3),
+                    (1, 3),
+                ],
+            ),
+            (
+                Topology::Knight,
+                &[
+                    (0, 1),
+                    (0, 3),
+                    (1, 0),
+                    (1, 4),
+                    (3, 0),
+                    (3, 4),
+                    (4, 1),
+                    (4, 3),
+                ],
+            ),
+        ];
+        for (topology, neighbors) in expected {
+            map.topology = topology;
+            assert_eq!(
+                neighbor_set(&map, 2, 2),
+                neighbors.iter().copied().collect(),
+                "{:?}",
+                topology
+            );
+        }
+
+        // knight moves jump over walls, diagonal moves can not cut corners
+        map.cells[2][1] = Cell::Invalid;
+        map.cells[1][2] = Cell::Invalid;
+        map.topology = Topology::Knight;
+        assert_eq!(neighbor_set(&map, 2, 2).len(), 8);
+        map.topology = Topology::King;
+        assert_eq!(
+            neighbor_set(&map, 2, 2),
+            HashSet::from([(3, 2), (2, 3), (3, 3)])
+        );
+
+        // near the edge only the moves to valid cells inside the map remain
+        map.topology = Topology::Knight;
+        assert_eq!(neighbor_set(&map, 0, 4), HashSet::from([(2, 3)]));
+    }
+
+    #[test]
+    fn test_partial_path() {
+        // a wall splits the map in two
+        let mut map = GridMap::new(5, 7, 1);
+        for row in 0..5 {
+            map.cells[row][4] = Cell::Invalid;
+        }
+        let (start, goal) = (Point { row: 2, col: 0 }, Point { row: 2, col: 6 });
+
+        let mut finder = PathFinder::new(start, goal, map.create_storage(), ())
+            .with_heuristic(1.0)
+            .with_partial_path();
+        assert_eq!(finder.partial_path(), None);
+        assert_eq!(
+            finder.step_n(&map, usize::MAX),
+            PathFinderState::NoPathFound(NoPathReason::GoalUnreachable)
+        );
+
+        let partial = finder.partial_path().unwrap();
+        assert_eq!(partial.goal, Point { row: 2, col: 3 });
+        assert_eq!(partial.path.first(), Some(&start));
+        assert_eq!(partial.path.last(), Some(&Point { row: 2, col: 3 }));
+        assert_eq!(partial.total_cost, 3);
+
+        // not tracked unless enabled
+        let mut finder = PathFinder::new(start, goal, map.create_storage(), ());
+        finder.step_n(&map, usize::MAX);
+        assert_eq!(finder.partial_path(), None);
+    }
+
+    #[test]
+    fn test_insert_row() {
+        let mut map = create_basic_map();
+        map.cells[1][1] = Cell::OneWay {
+            cost: 1,
+            direction: Direction::Down,
+            target: Some(Point { row: 5, col: 6 }),
+            target_cost: None,
+        };
+        map.cells[5][2] = Cell::OneWay {
+            cost: 1,
+            direction: Direction::Up,
+            target: Some(Point { row: 2, col: 5 }),
+            target_cost: None,
+        };
+        let original = map.cells.clone();
+
+        map.insert_row(3);
+        assert_eq!((map.rows, map.cells.len()), (8, 8));
+        assert_eq!(map.cells[0], original[0]);
+        assert_eq!(map.cells[2], original[2]);
+        assert!(map.cells[3].iter().all(|c| *c == Cell::default()));
+        assert_eq!(map.cells[4], original[3]);
+        assert_eq!(map.cells[7], original[6]);
+
+        // only the target below the new row moves
+        assert!(matches!(
+            map.cells[1][1],
+            Cell::OneWay {
+                target: Some(Point { row: 6, col: 6 }),
+                ..
+            }
+        ));
+        assert!(matches!(
+            map.cells[6][2],
+            Cell::OneWay {
+                target: Some(Point { row: 2, col: 5 }),
+                ..
+            }
+        ));
+
+        map.delete_row(3);
+        assert_eq!(map.cells, original);
+        assert_eq!(map.rows, 7);
+
+        // targets in a deleted row are removed
+        map.delete_row(2);
+        assert!(matches!(map.cells[4][2], Cell::OneWay { target: None, .. }));
+        assert_eq!(map.validate(), vec![]);
+    }
+
+    #[test]
+    fn test_insert_col() {
+        let mut map = create_basic_map();
+        map.set_meta(Point { row: 5, col: 4 }, ());
+        map.cells[1][1] = Cell::OneWay {
+            cost: 1,
+            direction: Direction::Down,
+            target: Some(Point { row: 5, col: 6 }),
+            target_cost: None,
+        };
+        let original = map.cells.clone();
+
+        map.insert_col(0);
+        assert_eq!(map.columns, 8);
+        assert!(map.cells.iter().all(|row| row.len() == 8));
+        assert_eq!(map.cells[5][1..], original[5][..]);
+        assert!(map.meta(Point { row: 5, col: 5 }).is_some());
+        assert!(matches!(
+            map.cells[1][2],
+            Cell::OneWay {
+                target: Some(Point { row: 5, col: 7 }),
+                ..
+            }
+        ));
+
+        map.delete_col(0);
+        assert_eq!(map.cells, original);
+        map.delete_col(4);
+        assert!(map.meta(Point { row: 5, col: 4 }).is_none());
+        assert_eq!(map.columns, 6);
+    }
+
+    #[test]
+    fn test_progress() {
+        let map = create_basic_map();
+        assert_eq!(map.valid_count(), 16);
+
+        let mut finder = PathFinder::new(
+            Point { row: 1, col: 1 },
+            Point { row: 1, col: 5 },
+            map.create_storage(),
+            (),
+        );
+        let mut last = finder.progress(map.valid_count());
+        assert_eq!(last, 0.0);
+        while finder.step(&map) == PathFinderState::Computing {
+            let progress = finder.progress(map.valid_count());
+            assert!(progress >= last && progress < 1.0);
+            last = progress;
+        }
+        assert!(last > 0.5);
+        assert_eq!(finder.progress(map.valid_count()), 1.0);
+    }
+
+    #[test]
+    fn test_inflate_obstacles() {
+        // a wall with a gap that is one cell wide
+        let mut map = GridMap::new(5, 9, 1);
+        for row in [0, 1, 3, 4] {
+            map.cells[row][4] = Cell::Invalid;
+        }
+        let (start, goal) = (Point { row: 2, col: 0 }, Point { row: 2, col: 8 });
+
+        let mut costly = GridMap::new(5, 9, 1);
+        costly.cells = map.cells.clone();
+        costly.inflate_obstacles(1, InflateMode::Cost(10));
+        let inflated = Cell::Valid {
+            cost: 11,
+            entry_cost: None,
+        };
+        assert_eq!(costly.cells[2][4], inflated);
+        assert_eq!(costly.cells[0][3], inflated);
+        assert_eq!(costly.cells[2][2], map.cells[2][2]);
+        assert_eq!(find_path(&costly, start, goal).total_cost, 38);
+
+        map.inflate_obstacles(1, InflateMode::Block);
+        assert_eq!(map.cells[2][4], Cell::Invalid);
+        assert_eq!(map.cells[4][5], Cell::Invalid);
+        assert_ne!(map.cells[2][2], Cell::Invalid);
+        assert_eq!(
+            PathFinder::new(start, goal, map.create_storage(), ())
+                .finish(&map)
+                .0,
+            PathFinderState::NoPathFound(NoPathReason::GoalUnreachable)
+        );
+    }
+
+    #[test]
+    fn test_result_json_round_trip() {
+        let map = create_basic_map();
+        let (state, visited) = PathFinder::new(
+            Point { row: 1, col: 1 },
+            Point { row: 1, col: 5 },
+            map.create_storage(),
+            (),
+        )
+        .finish(&map);
+        let PathFinderState::PathFound(result) = &state else {
+            panic!("expected a path");
+        };
+
+        let json = serde_json::to_string(result).unwrap();
+        let parsed: PathResult<usize, Point> = serde_json::from_str(&json).unwrap();
+        assert_eq!(&parsed, result);
+
+        let json = serde_json::to_string(&state).unwrap();
+        assert_eq!(
+            serde_json::from_str::<PathFinderState<usize, Point>>(&json).unwrap(),
+            state
+        );
+        let unreachable =
+            PathFinderState::<usize, Point>::NoPathFound(NoPathReason::GoalUnreachable);
+        let json = serde_json::to_string(&unreachable).unwrap();
+        assert_eq!(
+            serde_json::from_str::<PathFinderState<usize, Point>>(&json).unwrap(),
+            unreachable
+        );
+
+        let item = visited.get(Point { row: 2, col: 1 }).0.unwrap();
+        let json = serde_json::to_string(&item).unwrap();
+        let parsed: VisitedItem<usize, Point> = serde_json::from_str(&json).unwrap();
+        assert_eq!((parsed.cost, parsed.from), (item.cost, item.from));
+    }
+
+    #[test]
+    fn test_free_zone() {
+        // a large block of free cells between the start and the goal
+        let mut map = GridMap::new(9, 9, 1);
+        for row in 1..8 {
+            for col in 1..8 {
+                map.cells[row][col] = Cell::Valid {
+                    cost: 0,
+                    entry_cost: None,
+                };
+            }
+        }
+        let (start, goal) = (Point { row: 4, col: 0 }, Point { row: 4, col: 8 });
+
+        let result = find_path(&map, start, goal);
+        // only the move out of the start costs anything
+        assert_eq!(result.total_cost, 1);
+        // of all the free paths, the one with the fewest moves is reported
+        assert_eq!(result.path.len(), 9);
+        assert!(result.path.iter().all(|p| p.row == 4));
+
+        // a map without any costs at all still terminates with the shortest path
+        let map = GridMap::new(30, 30, 0);
+        let goal = Point { row: 29, col: 29 };
+        let result = find_path(&map, Point { row: 0, col: 0 }, goal);
+        assert_eq!(result.total_cost, 0);
+        assert_eq!(result.path.len(), 59);
+    }
+
+    #[test]
+    fn test_adjacency_matrix() {
+        let mut map = create_basic_map();
+        let (nodes, matrix) = map.to_adjacency_matrix();
+        assert_eq!(nodes.len(), 16);
+        assert_eq!(matrix.len(), 16);
+        assert!(matrix.iter().all(|row| row.len() == 16));
+
+        let edges = |matrix: &Vec<Vec<Option<usize>>>| matrix.iter().flatten().flatten().count();
+        assert_eq!(edges(&matrix), 30);
+        for (i, row) in matrix.iter().enumerate() {
+            assert_eq!(row[i], None);
+            for (j, cost) in row.iter().enumerate() {
+                assert_eq!(*cost, matrix[j][i]);
+            }
+        }
+        let index = |row, col| nodes.iter().position(|p| *p == Point { row, col }).unwrap();
+        let (above, cell, below) = (index(1, 1), index(2, 1), index(3, 1));
+        assert_eq!(matrix[above][cell], Some(1));
+
+        // a OneWay cell that can not be left downwards breaks the symmetry
+        map.cells[2][1] = Cell::OneWay {
+            cost: 1,
+            direction: Direction::Up,
+            target: None,
+            target_cost: None,
+        };
+        let (_, matrix) = map.to_adjacency_matrix();
+        assert_eq!(edges(&matrix), 29);
+        assert_eq!(matrix[cell][above], Some(1));
+        assert_eq!(matrix[cell][below], None);
+        assert_eq!(matrix[below][cell], Some(1));
+    }
+
+    #[test]
+    fn test_all_pairs_shortest() {
+        // a ring around an obstacle, where one cell is expensive to leave
+        let mut map = GridMap::new(3, 3, 1);
+        map.cells[1][1] = Cell::Invalid;
+        map.cells[0][1] = Cell::Valid {
+            cost: 5,
+            entry_cost: None,
+        };
+        let p = |row, col| Point { row, col };
+
+        let distances = map.all_pairs_shortest().unwrap();
+        assert_eq!(distances.len(), 8 * 8);
+        assert_eq!(distances[&(p(0, 0), p(0, 0))], 0);
+        assert_eq!(distances[&(p(0, 0), p(2, 2))], 4);
+        assert_eq!(distances[&(p(2, 2), p(0, 0))], 4);
+        // passing along the bottom is cheaper than leaving the expensive cell
+        assert_eq!(distances[&(p(1, 0), p(1, 2))], 4);
+        assert_eq!(distances[&(p(0, 1), p(0, 2))], 5);
+        assert_eq!(distances[&(p(0, 2), p(0, 1))], 1);
+        assert!(!distances.contains_key(&(p(0, 0), p(1, 1))));
+
+        // symmetric between cells with the same cost
+        for ((a, b), cost) in &distances {
+            if *a != p(0, 1) && *b != p(0, 1) {
+                assert_eq!(distances[&(*b, *a)], *cost);
+            }
+        }
+
+        assert!(GridMap::<usize>::new(40, 40, 1)
+            .all_pairs_shortest()
+            .is_err());
+    }
+
+    #[test]
+    fn test_diameter() {
+        let mut map = create_basic_map();
+        let p = |row, col| Point { row, col };
+
+        assert_eq!(map.diameter(), Some((p(1, 1), p(1, 5), 12)));
+        assert_eq!(map.eccentricity(p(1, 1)), Some(12));
+        assert_eq!(map.eccentricity(p(5, 3)), Some(6));
+        assert_eq!(map.eccentricity(p(0, 0)), None);
+        assert_eq!(map.eccentricity(p(10, 0)), None);
+
+        // a cell cut off from the rest is ignored, even if it is far away
+        map.cells[5][5] = Cell::Invalid;
+        assert_eq!(map.eccentricity(p(5, 6)), Some(0));
+        assert_eq!(map.diameter(), Some((p(1, 1), p(1, 5), 12)));
+
+        assert_eq!(GridMap::<usize>::new(0, 0, 1).diameter(), None);
+    }
+
+    #[test]
+    fn test_diff() {
+        let map = create_basic_map();
+        let mut other = create_basic_map();
+        other.cells[1][1] = Cell::Invalid;
+        other.cells[3][4] = Cell::Valid {
+            cost: 5,
+            entry_cost: None,
+        };
+
+        let changes = map.diff(&other).unwrap();
+        assert_eq!(
+            changes,
+            vec![
+                (Point { row: 1, col: 1 }, map.cells[1][1], Cell::Invalid),
+                (Point { row: 3, col: 4 }, map.cells[3][4], other.cells[3][4]),
+            ]
+        );
+        assert!(map.diff(&map).unwrap().is_empty());
+
+        other.resize(8, 7);
+        assert!(map.diff(&other).is_err());
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let mut map = create_basic_map();
+        let dot = map.to_dot();
+        assert!(dot.starts_with("digraph {"));
+        assert_eq!(dot.matches(" -> ").count(), 30);
+        assert!(!dot.contains("dashed"));
+
+        map.cells[1][1] = Cell::OneWay {
+            cost: 2,
+            direction: Direction::Down,
+            target: Some(Point { row: 1, col: 5 }),
+            target_cost: None,
+        };
+        let dot = map.to_dot();
+        assert!(dot.contains("\"1,1\" -> \"1,5\" [label=\"2\", style=dashed, color=blue];"));
+        assert_eq!(dot.matches("dashed").count(), 1);
+
+        // a teleport to the next cell is still a teleport, next to the step there
+        let mut map = GridMap::new(2, 2, 1);
+        map.cells[0][0] = Cell::OneWay {
+            cost: 1,
+            direction: Direction::Down,
+            target: Some(Point { row: 1, col: 0 }),
+            target_cost: None,
+        };
+        let dot = map.to_dot();
+        assert!(dot.contains("\"0,0\" -> \"1,0\" [label=\"1\"];"));
+        assert!(dot.contains("\"0,0\" -> \"1,0\" [label=\"1\", style=dashed, color=blue];"));
+        assert_eq!(dot.matches("dashed").count(), 1);
+    }
+
+    #[test]
+    fn test_render_with() {
+        let mut map = create_basic_map();
+        map.cells[5][6] = Cell::OneWay {
+            cost: 1,
+            direction: Direction::Right,
+            target: None,
+            target_cost: None,
+        };
+        map.cells[1][5] = Cell::OneWay {
+            cost: 1,
+            direction: Direction::Down,
+            target: Some(Point { row: 1, col: 1 }),
+            target_cost: None,
+        };
+
+        let expected = "\
+XXXXXXX
+X XXXvX
+X XXX X
+X X   X
+X X XXX
+X     >
+XXXXXXX
+";
+        assert_eq!(map.render_with(&AsciiGlyphs), expected);
+        assert_eq!(map.render_with(&UnicodeGlyphs), map.to_string());
+    }
+
+    #[test]
+    fn test_render_aligned() {
+        let mut map = GridMap::new(2, 3, 1);
+        map.cells[0][0] = Cell::Invalid;
+        map.cells[0][2] = Cell::OneWay {
+            cost: 1,
+            direction: Direction::Up,
+            target: None,
+            target_cost: None,
+        };
+        map.cells[1][1] = Cell::OneWay {
+            cost: 1,
+            direction: Direction::Left,
+            target: Some(Point { row: 0, col: 1 }),
+            target_cost: None,
+        };
+
+        let rendered = map.render_aligned();
+        let width = ["X", " ", "🠭", "↞"]
+            .map(|g| g.width())
+            .into_iter()
+            .max()
+            .unwrap();
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines.len(), 2);
+        for line in &lines {
+            assert_eq!(line.width(), 3 * width);
+        }
+
+        // every glyph starts at a multiple of the cell width
+        let column_of = |line: &str, glyph: char| line[..line.find(glyph).unwrap()].width();
+        assert_eq!(column_of(lines[0], 'X'), 0);
+        assert_eq!(column_of(lines[0], '🠭'), 2 * width);
+        assert_eq!(column_of(lines[1], '↞'), width);
+    }
 }
@@ -0,0 +1,87 @@
+use crate::find::MapTrait;
+use crate::grid::{GridMap, GridStorage, Point};
+
+/// Makes it cheaper to keep going through the same kind of terrain than to switch between them,
+/// to model momentum. The cost of a cell is its terrain class, and moves between two cells of
+/// the same class cost `discount` less (but never less than zero). As the discount only depends
+/// on the two cells of a move, the nodes are still plain points and no extra search state is
+/// needed.
+pub struct TerrainInertiaMap<'a, U = ()> {
+    base: &'a GridMap<usize, U>,
+    discount: usize,
+}
+
+impl<'a, U> TerrainInertiaMap<'a, U> {
+    pub fn new(base: &'a GridMap<usize, U>, discount: usize) -> Self {
+        Self { base, discount }
+    }
+
+    /// The terrain class of the cell at the provided point, None for walls
+    fn terrain(&self, point: Point) -> Option<usize> {
+        self.base.get(point)?.cost()
+    }
+}
+
+impl<U> MapTrait for TerrainInertiaMap<'_, U> {
+    type Reference = Point;
+    type Storage<T: Default + Copy + Clone + 'static> = GridStorage<T>;
+    type Cost = usize;
+
+    fn is_valid(&self, node: Self::Reference) -> bool {
+        self.base.is_valid(node)
+    }
+
+    fn is_passable(&self, node: Self::Reference) -> bool {
+        self.base.is_passable(node)
+    }
+
+    fn neighbors_of(
+        &self,
+        node: Self::Reference,
+    ) -> impl Iterator<Item = (Self::Reference, Self::Cost)> {
+        let terrain = self.terrain(node);
+        self.base.neighbors_of(node).map(move |(neighbor, cost)| {
+            if self.terrain(neighbor) == terrain {
+                (neighbor, cost.saturating_sub(self.discount))
+            } else {
+                (neighbor, cost)
+            }
+        })
+    }
+
+    fn create_storage<T: Default + Copy + Clone + 'static>(&self) -> Self::Storage<T> {
+        self.base.create_storage()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::find::PathResult;
+    use crate::grid::test::find_path;
+    use crate::grid::Cell;
+
+    #[test]
+    fn test_inertia_prefers_one_terrain() {
+        // an expensive band on top of a cheaper row that keeps switching terrain
+        let mut map = GridMap::new(2, 6, 3);
+        for col in 0..6 {
+            map.cells[1][col] = Cell::Valid {
+                cost: 1 + col % 2,
+                entry_cost: None,
+            };
+        }
+        let (start, goal) = (Point { row: 0, col: 0 }, Point { row: 0, col: 5 });
+        let route = |map: &TerrainInertiaMap| find_path(map, start, goal);
+
+        // without a discount the zig-zag through the bottom row is cheaper
+        let result = route(&TerrainInertiaMap::new(&map, 0));
+        assert!(matches!(result, PathResult { total_cost: 12, .. }));
+        assert!(result.path.contains(&Point { row: 1, col: 2 }));
+
+        // with a discount, staying in the top band wins
+        let result = route(&TerrainInertiaMap::new(&map, 2));
+        assert!(matches!(result, PathResult { total_cost: 5, .. }));
+        assert!(result.path.iter().all(|p| p.row == 0));
+    }
+}
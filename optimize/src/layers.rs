@@ -0,0 +1,130 @@
+use crate::find::{MapTrait, RelativeCost};
+use crate::grid::{grid_neighbors, Cell, GridStorage, Point, Topology};
+
+/// A grid with several sets of costs over the same cells, e.g. one for walking and one for
+/// cycling. Which cells are passable is shared between all layers, and searches use the costs of
+/// the active layer. Cheaper than keeping a separate [`GridMap`](crate::grid::GridMap) per mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiLayerGridMap<C: RelativeCost> {
+    pub rows: usize,
+    pub columns: usize,
+    passable: Vec<Vec<bool>>,
+    layers: Vec<Vec<Vec<C>>>,
+    active: usize,
+}
+
+impl<C: RelativeCost> MultiLayerGridMap<C> {
+    /// Creates a map where all cells are passable, with `layers` layers that all have the
+    /// provided cost everywhere. The first layer is active.
+    pub fn new(rows: usize, columns: usize, layers: usize, default_cost: C) -> Self {
+        assert!(layers > 0, "the map must have at least one layer");
+        Self {
+            rows,
+            columns,
+            passable: vec![vec![true; columns]; rows],
+            layers: vec![vec![vec![default_cost; columns]; rows]; layers],
+            active: 0,
+        }
+    }
+
+    pub fn layer_count(&self) -> usize {
+        self.layers.len()
+    }
+
+    pub fn active_layer(&self) -> usize {
+        self.active
+    }
+
+    /// Selects the layer whose costs are used by following searches
+    pub fn set_active_layer(&mut self, layer: usize) {
+        assert!(layer < self.layers.len(), "layer {} does not exist", layer);
+        self.active = layer;
+    }
+
+    pub fn is_passable(&self, point: Point) -> bool {
+        self.passable[point.row][point.col]
+    }
+
+    /// Makes the cell passable or not in all layers
+    pub fn set_passable(&mut self, point: Point, passable: bool) {
+        self.passable[point.row][point.col] = passable;
+    }
+
+    pub fn cost(&self, layer: usize, point: Point) -> C {
+        self.layers[layer][point.row][point.col]
+    }
+
+    pub fn set_cost(&mut self, layer: usize, point: Point, cost: C) {
+        self.layers[layer][point.row][point.col] = cost;
+    }
+
+    /// The cell at the provided point as seen by the active layer
+    fn cell_at(&self, point: Point) -> Cell<C> {
+        if self.passable[point.row][point.col] {
+            Cell::Valid {
+                cost: self.layers[self.active][point.row][point.col],
+                entry_cost: None,
+            }
+        } else {
+            Cell::Invalid
+        }
+    }
+}
+
+impl<C: RelativeCost> MapTrait for MultiLayerGridMap<C> {
+    type Reference = Point;
+    type Storage<T: Default + Copy + Clone + 'static> = GridStorage<T>;
+    type Cost = C;
+
+    fn is_valid(&self, node: Self::Reference) -> bool {
+        node.row < self.rows && node.col < self.columns
+    }
+
+    fn is_passable(&self, node: Self::Reference) -> bool {
+        self.is_valid(node) && MultiLayerGridMap::is_passable(self, node)
+    }
+
+    fn neighbors_of(&self, node: Self::Reference) -> impl Iterator<Item = (Self::Reference, C)> {
+        grid_neighbors(self.rows, self.columns, Topology::Orthogonal, node, |p| {
+            self.cell_at(p)
+        })
+    }
+
+    fn create_storage<T: Default + Copy + Clone + 'static>(&self) -> Self::Storage<T> {
+        GridStorage(vec![vec![Default::default(); self.columns]; self.rows])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::find::PathResult;
+    use crate::grid::test::find_path;
+
+    #[test]
+    fn test_route_depends_on_layer() {
+        const WALKING: usize = 0;
+        const CYCLING: usize = 1;
+
+        // the middle row is a shortcut for walking, but expensive for cycling
+        let mut map = MultiLayerGridMap::new(3, 5, 2, 1usize);
+        for col in 1..4 {
+            map.set_cost(CYCLING, Point { row: 1, col }, 10);
+        }
+        map.set_passable(Point { row: 2, col: 2 }, false);
+
+        let (start, goal) = (Point { row: 1, col: 0 }, Point { row: 1, col: 4 });
+        let mut route = |layer| {
+            map.set_active_layer(layer);
+            find_path(&map, start, goal)
+        };
+
+        let walking = route(WALKING);
+        assert!(matches!(walking, PathResult { total_cost: 4, .. }));
+        assert!(walking.path.iter().all(|p| p.row == 1));
+
+        let cycling = route(CYCLING);
+        assert!(matches!(cycling, PathResult { total_cost: 6, .. }));
+        assert!(cycling.path.contains(&Point { row: 0, col: 2 }));
+    }
+}
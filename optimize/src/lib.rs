@@ -1,3 +1,18 @@
+pub mod bellman;
+pub mod bitmap;
+pub mod cache;
+pub mod color;
+pub mod cooperative;
 pub mod find;
+pub mod fixed;
 pub mod grid;
+pub mod inertia;
+pub mod layers;
+pub mod octile;
+pub mod overlay;
+pub mod stochastic;
+pub mod tags;
+pub mod tiled;
+pub mod timed;
+pub mod traversal;
 pub mod util;
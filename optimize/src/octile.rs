@@ -0,0 +1,197 @@
+use crate::find::{FixedPoint, HeuristicMap, MapTrait};
+use crate::grid::{grid_moves, Cell, GridMap, GridStorage, Point, Topology};
+
+/// The cost type of [`OctileGridMap`], with a resolution of a thousandth
+pub type OctileCost = FixedPoint<1000>;
+
+/// The factor diagonal moves cost more than straight ones, rounded to the resolution of the cost
+const DIAGONAL: OctileCost = FixedPoint(1414);
+
+/// Measures a [`GridMap`] in the plane: diagonal moves cost √2 times the cost of the cell they
+/// leave, as they cover that much more ground. Most useful with [`Topology::King`], where the
+/// plain integer costs of the map make diagonal moves as cheap as straight ones and paths zig-zag.
+/// Entry costs and OneWay teleports are not scaled.
+///
+/// The heuristic is the octile distance (see [`Point::octile`]) times the cheapest cell of the
+/// map, except for [`Topology::Knight`], where a jump covers more ground than a diagonal move and
+/// the fewest jumps (see [`Topology::min_moves`]) are used instead. It is not a lower bound on
+/// maps with OneWay teleports, so A* might not find the optimal path on those; on other maps it
+/// never overestimates, and A* with a weight of 1 finds optimal paths.
+pub struct OctileGridMap<'a, U = ()> {
+    base: &'a GridMap<usize, U>,
+    /// the lowest cost of leaving any cell, used to keep the heuristic admissible
+    min_cost: usize,
+}
+
+impl<'a, U> OctileGridMap<'a, U> {
+    pub fn new(base: &'a GridMap<usize, U>) -> Self {
+        let min_cost = base
+            .cells
+            .iter()
+            .flatten()
+            .filter_map(|cell| cell.cost())
+            .min()
+            .unwrap_or_default();
+        Self { base, min_cost }
+    }
+}
+
+/// The octile distance in units of `cost`, using the same rounded factor as the diagonal moves so
+/// that it never exceeds the cost of a path of such moves
+fn octile_cost(from: Point, to: Point, cost: usize) -> OctileCost {
+    let (diagonal, straight) = from.octile_moves(to);
+    OctileCost::from_int((straight * cost) as i64)
+        + OctileCost::from_int((diagonal * cost) as i64).scaled(DIAGONAL)
+}
+
+impl<U> MapTrait for OctileGridMap<'_, U> {
+    type Reference = Point;
+    type Storage<T: Default + Copy + Clone + 'static> = GridStorage<T>;
+    type Cost = OctileCost;
+
+    fn is_valid(&self, node: Self::Reference) -> bool {
+        self.base.is_valid(node)
+    }
+
+    fn is_passable(&self, node: Self::Reference) -> bool {
+        self.base.is_passable(node)
+    }
+
+    fn neighbors_of(
+        &self,
+        node: Self::Reference,
+    ) -> impl Iterator<Item = (Self::Reference, Self::Cost)> {
+        let base = self.base;
+        let leave = base.get(node).and_then(|cell| cell.cost());
+        let moves = grid_moves(base.rows, base.columns, base.topology, node, move |p| {
+            base.get(p).unwrap_or(Cell::Invalid)
+        });
+        moves.map(move |(neighbor, cost, teleport)| {
+            // teleports do not cross the plane, even when they land diagonally next to the cell
+            let diagonal = !teleport
+                && neighbor.row.abs_diff(node.row) == 1
+                && neighbor.col.abs_diff(node.col) == 1;
+            match (diagonal, leave) {
+                // only the cost of leaving the cell is scaled, not the entry cost of the neighbor
+                (true, Some(leave)) => (
+                    neighbor,
+                    OctileCost::from_int(leave as i64).scaled(DIAGONAL)
+                        + OctileCost::from_int((cost - leave) as i64),
+                ),
+                _ => (neighbor, OctileCost::from_int(cost as i64)),
+            }
+        })
+    }
+
+    fn create_storage<T: Default + Copy + Clone + 'static>(&self) -> Self::Storage<T> {
+        self.base.create_storage()
+    }
+}
+
+impl<U> HeuristicMap for OctileGridMap<'_, U> {
+    fn heuristic(&self, from: Point, to: Point) -> OctileCost {
+        match self.base.topology {
+            Topology::Knight => OctileCost::from_int(
+                (self.base.topology.min_moves(from, to) * self.min_cost) as i64,
+            ),
+            _ => octile_cost(from, to, self.min_cost),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::find::{reachable_within, PathFinder, PathFinderState, PathResult};
+    use crate::grid::test::find_path;
+    use crate::grid::Direction;
+
+    fn route(
+        map: &OctileGridMap,
+        start: Point,
+        goal: Point,
+        heuristic: bool,
+    ) -> (PathResult<OctileCost, Point>, usize) {
+        let mut finder = PathFinder::new(start, goal, map.create_storage(), ());
+        if heuristic {
+            finder = finder.with_heuristic(1.0);
+        }
+        let (state, visited) = finder.finish(map);
+        let settled = visited.0.iter().flatten().filter(|v| v.is_some()).count();
+        match state {
+            PathFinderState::PathFound(result) => (result, settled),
+            state => panic!("expected a path, got {:?}", state),
+        }
+    }
+
+    #[test]
+    fn test_octile_matches_dijkstra() {
+        let mut grid = GridMap::new(20, 25, 2);
+        grid.topology = Topology::King;
+        for row in 3..15 {
+            grid.cells[row][12] = Cell::Invalid;
+        }
+        let map = OctileGridMap::new(&grid);
+        let goal = Point { row: 9, col: 20 };
+
+        // the heuristic never exceeds the real remaining cost, which on a grid with the same cost
+        // everywhere is the same in both directions
+        for (point, cost) in reachable_within(&map, goal, FixedPoint(i64::MAX), &()) {
+            assert!(map.heuristic(point, goal) <= cost, "{:?}", point);
+        }
+
+        for start in [
+            Point { row: 9, col: 2 },
+            Point { row: 0, col: 0 },
+            Point { row: 19, col: 11 },
+        ] {
+            let (dijkstra, dijkstra_settled) = route(&map, start, goal, false);
+            let (astar, astar_settled) = route(&map, start, goal, true);
+            assert_eq!(astar.total_cost, dijkstra.total_cost);
+            assert!(astar_settled < dijkstra_settled);
+        }
+
+        // straight across an open grid costs the octile distance
+        let (result, _) = route(
+            &map,
+            Point { row: 0, col: 0 },
+            Point { row: 2, col: 5 },
+            true,
+        );
+        assert_eq!(result.total_cost, FixedPoint(2 * 2828 + 3 * 2000));
+        assert!(
+            (Point { row: 0, col: 0 }.octile(Point { row: 2, col: 5 }) * 2.0 - 11.657).abs() < 1e-3
+        );
+
+        // a knight jump covers more ground than the octile distance accounts for
+        grid.topology = Topology::Knight;
+        let map = OctileGridMap::new(&grid);
+        for (point, cost) in reachable_within(&map, goal, FixedPoint(i64::MAX), &()) {
+            assert!(map.heuristic(point, goal) <= cost, "{:?}", point);
+        }
+        let (dijkstra, _) = route(&map, Point { row: 9, col: 2 }, goal, false);
+        let (astar, _) = route(&map, Point { row: 9, col: 2 }, goal, true);
+        assert_eq!(astar.total_cost, dijkstra.total_cost);
+    }
+
+    #[test]
+    fn test_octile_cheap_teleport() {
+        // a teleport that lands diagonally next to its cell and is cheaper than leaving it
+        let mut grid = GridMap::new(3, 3, 1);
+        grid.topology = Topology::King;
+        grid.cells[0][0] = Cell::OneWay {
+            cost: 5,
+            direction: Direction::Down,
+            target: Some(Point { row: 1, col: 1 }),
+            target_cost: Some(1),
+        };
+        let map = OctileGridMap::new(&grid);
+
+        // the diagonal step is scaled, the teleport costs exactly its target cost
+        let neighbors: Vec<_> = map.neighbors_of(Point { row: 0, col: 0 }).collect();
+        assert!(neighbors.contains(&(Point { row: 1, col: 1 }, FixedPoint(7070))));
+        assert!(neighbors.contains(&(Point { row: 1, col: 1 }, FixedPoint(1000))));
+        let result = find_path(&map, Point { row: 0, col: 0 }, Point { row: 1, col: 1 });
+        assert_eq!(result.total_cost, FixedPoint(1000));
+    }
+}
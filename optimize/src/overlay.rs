@@ -0,0 +1,80 @@
+use crate::find::MapTrait;
+
+/// Layers a cost modifier on top of a base map without changing it, e.g. to make a region more
+/// expensive while it is congested. `modify` is called with the node being moved to and the cost
+/// of the move on the base map, and returns the cost to use instead. Which moves are possible is
+/// still decided by the base map.
+pub struct OverlayMap<'a, M, F> {
+    base: &'a M,
+    modify: F,
+}
+
+impl<'a, M, F> OverlayMap<'a, M, F>
+where
+    M: MapTrait,
+    F: Fn(M::Reference, M::Cost) -> M::Cost,
+{
+    pub fn new(base: &'a M, modify: F) -> Self {
+        Self { base, modify }
+    }
+}
+
+impl<M, F> MapTrait for OverlayMap<'_, M, F>
+where
+    M: MapTrait,
+    F: Fn(M::Reference, M::Cost) -> M::Cost,
+{
+    type Reference = M::Reference;
+    type Storage<T: Default + Copy + Clone + 'static> = M::Storage<T>;
+    type Cost = M::Cost;
+
+    fn is_valid(&self, node: Self::Reference) -> bool {
+        self.base.is_valid(node)
+    }
+
+    fn is_passable(&self, node: Self::Reference) -> bool {
+        self.base.is_passable(node)
+    }
+
+    fn neighbors_of(
+        &self,
+        node: Self::Reference,
+    ) -> impl Iterator<Item = (Self::Reference, Self::Cost)> {
+        self.base
+            .neighbors_of(node)
+            .map(|(neighbor, cost)| (neighbor, (self.modify)(neighbor, cost)))
+    }
+
+    fn create_storage<T: Default + Copy + Clone + 'static>(&self) -> Self::Storage<T> {
+        self.base.create_storage()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::find::{PathFinder, PathFinderState};
+    use crate::grid::test::find_path;
+    use crate::grid::{GridMap, Point};
+
+    #[test]
+    fn test_overlay_reroutes() {
+        let map = GridMap::new(7, 7, 1);
+        let (start, goal) = (Point { row: 3, col: 0 }, Point { row: 3, col: 6 });
+        let in_zone = |p: Point| (2..=4).contains(&p.row) && (1..=5).contains(&p.col);
+
+        let direct = find_path(&map, start, goal);
+        assert_eq!(direct.total_cost, 6);
+        assert!(direct.path.iter().any(|p| in_zone(*p)));
+
+        // moving into the congested zone costs twice as much, so it is cheaper to go around
+        let overlay = OverlayMap::new(&map, |p, cost| if in_zone(p) { cost * 2 } else { cost });
+        let around = find_path(&overlay, start, goal);
+        assert_eq!(around.total_cost, 10);
+        assert!(!around.path.iter().any(|p| in_zone(*p)));
+
+        // the base map is unchanged
+        let (state, _) = PathFinder::new(start, goal, map.create_storage(), ()).finish(&map);
+        assert_eq!(state, PathFinderState::PathFound(direct));
+    }
+}
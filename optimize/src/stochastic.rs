@@ -0,0 +1,220 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::find::{MapTrait, PathFinder, PathFinderState};
+use crate::util::Rng;
+
+/// The cost of a move that is not known exactly, modeled as a normal distribution
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UncertainCost {
+    pub mean: f64,
+    pub variance: f64,
+}
+
+impl UncertainCost {
+    /// A cost without any uncertainty, which always samples to `cost`
+    pub fn exact(cost: usize) -> Self {
+        Self {
+            mean: cost as f64,
+            variance: 0.0,
+        }
+    }
+
+    /// Draws a cost from the distribution, rounded to the nearest non-negative integer
+    pub fn sample(&self, rng: &mut Rng) -> usize {
+        // Box-Muller transform, `1 - x` keeps the logarithm away from zero
+        let (u1, u2) = (1.0 - rng.next_f64(), rng.next_f64());
+        let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+        (self.mean + self.variance.sqrt() * z).round().max(0.0) as usize
+    }
+}
+
+/// The outcome of a [`MonteCarloPathFinder`] run
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonteCarloResult<R> {
+    /// The number of runs that found a path, the statistics only cover these
+    pub runs_found: usize,
+    pub min_cost: usize,
+    pub mean_cost: f64,
+    pub max_cost: usize,
+    /// The path that was found most often, the earliest one on ties
+    pub modal_path: Vec<R>,
+}
+
+/// A view of a map where the cost of every move is sampled from its uncertain cost
+struct Sampled<'a, M, F> {
+    base: &'a M,
+    uncertainty: &'a F,
+    rng: RefCell<Rng>,
+}
+
+impl<M, F> MapTrait for Sampled<'_, M, F>
+where
+    M: MapTrait<Cost = usize>,
+    F: Fn(M::Reference, usize) -> UncertainCost,
+{
+    type Reference = M::Reference;
+    type Storage<T: Default + Copy + Clone + 'static> = M::Storage<T>;
+    type Cost = usize;
+
+    fn is_valid(&self, node: Self::Reference) -> bool {
+        self.base.is_valid(node)
+    }
+
+    fn is_passable(&self, node: Self::Reference) -> bool {
+        self.base.is_passable(node)
+    }
+
+    fn neighbors_of(
+        &self,
+        node: Self::Reference,
+    ) -> impl Iterator<Item = (Self::Reference, Self::Cost)> {
+        self.base.neighbors_of(node).map(|(neighbor, cost)| {
+            let cost = (self.uncertainty)(neighbor, cost).sample(&mut self.rng.borrow_mut());
+            (neighbor, cost)
+        })
+    }
+
+    fn create_storage<T: Default + Copy + Clone + 'static>(&self) -> Self::Storage<T> {
+        self.base.create_storage()
+    }
+}
+
+/// Estimates the distribution of the cheapest path cost on a map with uncertain move costs, by
+/// searching it several times with costs sampled from their distributions. `uncertainty` is
+/// called with the node being moved to and the cost of the move on the base map, and returns the
+/// distribution of the actual cost.
+pub struct MonteCarloPathFinder<'a, M: MapTrait, F> {
+    base: &'a M,
+    uncertainty: F,
+    start: M::Reference,
+    goal: M::Reference,
+    runs: usize,
+    seed: u64,
+}
+
+impl<'a, M, F> MonteCarloPathFinder<'a, M, F>
+where
+    M: MapTrait<Cost = usize>,
+    M::Reference: Hash,
+    F: Fn(M::Reference, usize) -> UncertainCost,
+{
+    pub fn new(base: &'a M, start: M::Reference, goal: M::Reference, uncertainty: F) -> Self {
+        Self {
+            base,
+            uncertainty,
+            start,
+            goal,
+            runs: 100,
+            seed: 0,
+        }
+    }
+
+    /// Sets the number of searches to run, 100 by default
+    pub fn with_runs(mut self, runs: usize) -> Self {
+        self.runs = runs;
+        self
+    }
+
+    /// Sets the seed the costs are sampled with, so the results are reproducible
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Runs all the searches. Returns `None` if none of them found a path.
+    pub fn run(&self) -> Option<MonteCarloResult<M::Reference>> {
+        let map = Sampled {
+            base: self.base,
+            uncertainty: &self.uncertainty,
+            rng: RefCell::new(Rng::new(self.seed)),
+        };
+
+        let mut costs = Vec::with_capacity(self.runs);
+        // the number of times every path was found, and when it was first found
+        let mut paths: HashMap<Vec<M::Reference>, (usize, usize)> = HashMap::new();
+        for run in 0..self.runs {
+            let (state, _) =
+                PathFinder::new(self.start, self.goal, map.create_storage(), ()).finish(&map);
+            if let PathFinderState::PathFound(result) = state {
+                costs.push(result.total_cost);
+                paths.entry(result.path).or_insert((0, run)).0 += 1;
+            }
+        }
+
+        let modal_path = paths
+            .into_iter()
+            .max_by(|(_, (a, first_a)), (_, (b, first_b))| a.cmp(b).then(first_b.cmp(first_a)))?
+            .0;
+        Some(MonteCarloResult {
+            runs_found: costs.len(),
+            min_cost: *costs.iter().min()?,
+            mean_cost: costs.iter().sum::<usize>() as f64 / costs.len() as f64,
+            max_cost: *costs.iter().max()?,
+            modal_path,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::grid::test::{create_basic_map, find_path};
+    use crate::grid::Point;
+
+    #[test]
+    fn test_exact_costs_match_the_finder() {
+        let map = create_basic_map();
+        let (start, goal) = (Point { row: 1, col: 1 }, Point { row: 1, col: 5 });
+        let expected = find_path(&map, start, goal);
+
+        let result =
+            MonteCarloPathFinder::new(&map, start, goal, |_, cost| UncertainCost::exact(cost))
+                .with_runs(10)
+                .run()
+                .unwrap();
+
+        assert_eq!(
+            result,
+            MonteCarloResult {
+                runs_found: 10,
+                min_cost: expected.total_cost,
+                mean_cost: expected.total_cost as f64,
+                max_cost: expected.total_cost,
+                modal_path: expected.path,
+            }
+        );
+    }
+
+    #[test]
+    fn test_uncertain_costs_vary() {
+        let map = create_basic_map();
+        let (start, goal) = (Point { row: 1, col: 1 }, Point { row: 1, col: 5 });
+
+        let result = MonteCarloPathFinder::new(&map, start, goal, |_, cost| UncertainCost {
+            mean: cost as f64 * 5.0,
+            variance: 4.0,
+        })
+        .with_runs(50)
+        .with_seed(7)
+        .run()
+        .unwrap();
+
+        assert_eq!(result.runs_found, 50);
+        assert!(result.min_cost < result.max_cost);
+        assert!((result.min_cost as f64..=result.max_cost as f64).contains(&result.mean_cost));
+        // there is only one route through the basic map
+        assert_eq!(result.modal_path.len(), 13);
+
+        let unreachable = Point { row: 0, col: 0 };
+        assert_eq!(
+            MonteCarloPathFinder::new(&map, start, unreachable, |_, cost| {
+                UncertainCost::exact(cost)
+            })
+            .with_runs(3)
+            .run(),
+            None
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::grid::Point;
+
+const BITS: usize = u64::BITS as usize;
+
+/// Named boolean tags of the cells of a grid, e.g. "water" or "indoor", stored as one bitset per
+/// tag. Tags do not affect pathfinding. See [`GridMap::set_tag`](crate::grid::GridMap::set_tag).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagStorage {
+    /// The number of columns of the grid, used to index the bitsets
+    columns: usize,
+    tags: BTreeMap<String, Vec<u64>>,
+}
+
+impl TagStorage {
+    pub fn new(columns: usize) -> Self {
+        Self {
+            columns,
+            tags: BTreeMap::new(),
+        }
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Whether no cell has any tag
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+
+    fn index(&self, point: Point) -> usize {
+        assert!(point.col < self.columns, "{:?} is outside the grid", point);
+        point.row * self.columns + point.col
+    }
+
+    /// Adds or removes the tag of the cell at the provided point
+    pub fn set(&mut self, point: Point, tag: &str, value: bool) {
+        let index = self.index(point);
+        let (word, bit) = (index / BITS, 1 << (index % BITS));
+        if value {
+            let bits = self.tags.entry(tag.to_string()).or_default();
+            if bits.len() <= word {
+                bits.resize(word + 1, 0);
+            }
+            bits[word] |= bit;
+        } else if let Some(bits) = self.tags.get_mut(tag) {
+            if let Some(w) = bits.get_mut(word) {
+                *w &= !bit;
+            }
+            // forget tags that no cell has anymore
+            if bits.iter().all(|&w| w == 0) {
+                self.tags.remove(tag);
+            }
+        }
+    }
+
+    pub fn has(&self, point: Point, tag: &str) -> bool {
+        let index = self.index(point);
+        self.tags
+            .get(tag)
+            .and_then(|bits| bits.get(index / BITS))
+            .is_some_and(|w| w & (1 << (index % BITS)) != 0)
+    }
+
+    /// The names of the tags that at least one cell has, in alphabetical order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.tags.keys().map(String::as_str)
+    }
+
+    /// The cells that have the tag, row by row
+    pub fn tagged<'a>(&'a self, tag: &str) -> impl Iterator<Item = Point> + 'a {
+        let columns = self.columns;
+        let bits = self.tags.get(tag).map(Vec::as_slice).unwrap_or_default();
+        bits.iter().enumerate().flat_map(move |(word, &w)| {
+            (0..BITS)
+                .filter(move |bit| w & (1 << bit) != 0)
+                .map(move |bit| {
+                    let index = word * BITS + bit;
+                    Point {
+                        row: index / columns,
+                        col: index % columns,
+                    }
+                })
+        })
+    }
+
+    /// Moves the tags of every cell to the points returned by `remap`, for a grid that now has
+    /// `columns` columns. Tags of cells it returns no points for are dropped.
+    pub(crate) fn remap<I: IntoIterator<Item = Point>>(
+        &mut self,
+        columns: usize,
+        remap: impl Fn(Point) -> I,
+    ) {
+        let old = std::mem::replace(self, Self::new(columns));
+        for name in old.names() {
+            for point in old.tagged(name) {
+                for p in remap(point) {
+                    self.set(p, name, true);
+                }
+            }
+        }
+    }
+}
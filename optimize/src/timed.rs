@@ -0,0 +1,135 @@
+use std::collections::BinaryHeap;
+
+use crate::find::{
+    backtrack, settle, AbsoluteCost, MapStorage, MapTrait, PathResult, RelativeCost, ToVisit,
+    Visited,
+};
+use crate::grid::{GridMap, Point};
+
+/// A grid map where the cost of moving between two cells depends on the time the move starts,
+/// e.g. to model traffic. The time is the cost accumulated so far along the path. The wrapped map
+/// decides which moves are possible, while `cost` decides what they cost, given
+/// `(from, to, time)`.
+pub struct TimeDependentMap<'a, C: RelativeCost, U, F> {
+    map: &'a GridMap<C, U>,
+    cost: F,
+}
+
+impl<'a, C, U, F> TimeDependentMap<'a, C, U, F>
+where
+    C: AbsoluteCost + RelativeCost,
+    F: Fn(Point, Point, C) -> C,
+{
+    pub fn new(map: &'a GridMap<C, U>, cost: F) -> Self {
+        Self { map, cost }
+    }
+
+    /// Returns the neighbors of `node` and the cost of moving there when starting at `time`
+    pub fn neighbors_at(&self, node: Point, time: C) -> impl Iterator<Item = (Point, C)> + '_ {
+        self.map
+            .neighbors_of(node)
+            .map(move |(neighbor, _)| (neighbor, (self.cost)(node, neighbor, time)))
+    }
+
+    /// Finds the path from `start` to `goal` that arrives the earliest, without waiting along the
+    /// way. Like Dijkstra, but the cost of every move is evaluated at the time the search reaches
+    /// its source. The result is optimal as long as starting later never means arriving earlier
+    /// (the FIFO property), which holds if the cost never drops by more than the time passed.
+    pub fn find_path(
+        &self,
+        start: Point,
+        goal: Point,
+        context: C::CmpContext,
+    ) -> Option<PathResult<C, Point>>
+    where
+        C::CmpContext: Clone,
+    {
+        if !self.map.is_valid(start) || !self.map.is_valid(goal) {
+            return None;
+        }
+
+        let mut visited = self.map.create_storage::<Visited<C, Point>>();
+        let mut visit_list = BinaryHeap::from([ToVisit::start(start, context)]);
+
+        while let Some(visit) = visit_list.pop() {
+            if !settle(&mut visited, &visit) {
+                continue;
+            }
+
+            if visit.point == goal {
+                return Some(PathResult {
+                    path: backtrack(&visited, goal),
+                    start,
+                    goal,
+                    total_cost: visit.cost,
+                });
+            }
+
+            for (neighbor, move_cost) in self.neighbors_at(visit.point, visit.cost) {
+                if matches!(visited.try_get(neighbor), Some(Visited(None))) {
+                    visit_list.push(visit.next(neighbor, move_cost));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::grid::Cell;
+
+    /// A 3x5 map with a short route along the top row and a longer one around the bottom
+    fn create_map() -> GridMap<usize> {
+        let mut map = GridMap::new(3, 5, 1);
+        for col in 1..=3 {
+            map.cells[1][col] = Cell::Invalid;
+        }
+        map
+    }
+
+    /// Moving into `congested` is expensive until time 10
+    fn congestion(congested: Point) -> impl Fn(Point, Point, usize) -> usize {
+        move |_, to, time| if to == congested && time < 10 { 20 } else { 1 }
+    }
+
+    #[test]
+    fn test_time_dependent_route() {
+        let map = create_map();
+        let congested = Point { row: 0, col: 2 };
+
+        let timed = TimeDependentMap::new(&map, congestion(congested));
+
+        // starting at the left, the congested cell is reached early, so go around
+        let result = timed
+            .find_path(Point { row: 0, col: 0 }, Point { row: 0, col: 4 }, ())
+            .unwrap();
+        assert_eq!(result.total_cost, 8);
+        assert!(!result.path.contains(&congested));
+
+        // starting further away, the congestion has cleared once the top row is reached
+        let mut map = GridMap::new(3, 14, 1);
+        for col in 10..=12 {
+            map.cells[1][col] = Cell::Invalid;
+        }
+        let congested = Point { row: 0, col: 11 };
+        let timed = TimeDependentMap::new(&map, congestion(congested));
+        let result = timed
+            .find_path(Point { row: 0, col: 0 }, Point { row: 0, col: 13 }, ())
+            .unwrap();
+        assert_eq!(result.total_cost, 13);
+        assert!(result.path.contains(&congested));
+    }
+
+    #[test]
+    fn test_time_dependent_invalid_goal() {
+        let map = create_map();
+        let timed = TimeDependentMap::new(&map, |_, _, _| 1);
+        assert_eq!(
+            timed.find_path(Point { row: 0, col: 0 }, Point { row: 5, col: 0 }, ()),
+            None
+        );
+    }
+}
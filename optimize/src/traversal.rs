@@ -0,0 +1,160 @@
+use crate::find::{MapTrait, PathResult};
+use crate::grid::{GridMap, Point};
+
+/// One move along the path and the time it starts at
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    from: (f64, f64),
+    to: (f64, f64),
+    start: f64,
+    duration: f64,
+    /// the move is not to an adjacent cell (e.g. a OneWay teleport)
+    jump: bool,
+}
+
+/// Follows a found path at a constant speed, giving the world position of an agent at any time.
+/// Positions are `(x, y)` at the center of cells, i.e. `(col + 0.5, row + 0.5)`. Every move takes
+/// time proportional to its cost, so expensive cells are crossed slower. Moves that are not part
+/// of the map topology (teleports) keep the agent at the source for their duration and then jump
+/// to the target.
+#[derive(Debug, Clone)]
+pub struct PathTraversal {
+    segments: Vec<Segment>,
+    start: (f64, f64),
+    goal: (f64, f64),
+    duration: f64,
+}
+
+impl PathTraversal {
+    /// Creates a traversal of `result` on `map`, moving `speed` cost units per time unit
+    pub fn new<U>(map: &GridMap<usize, U>, result: &PathResult<usize, Point>, speed: f64) -> Self {
+        assert!(speed > 0.0, "the speed must be positive");
+
+        let mut segments = Vec::with_capacity(result.path.len());
+        let mut time = 0.0;
+        for pair in result.path.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            // the cheapest move between the points, as that is what the search used
+            let cost = map
+                .neighbors_of(a)
+                .filter(|(p, _)| *p == b)
+                .map(|(_, cost)| cost)
+                .min()
+                .unwrap_or(0);
+
+            let duration = cost as f64 / speed;
+            segments.push(Segment {
+                from: center(a),
+                to: center(b),
+                start: time,
+                duration,
+                jump: !map.topology.offsets().contains(&(
+                    b.row as isize - a.row as isize,
+                    b.col as isize - a.col as isize,
+                )),
+            });
+            time += duration;
+        }
+
+        Self {
+            segments,
+            start: center(result.start),
+            goal: center(result.goal),
+            duration: time,
+        }
+    }
+
+    /// The total time it takes to reach the goal
+    pub fn duration(&self) -> f64 {
+        self.duration
+    }
+
+    /// Returns the position at time `t`, clamped to the start before 0 and the goal after
+    /// [`PathTraversal::duration`]
+    pub fn position_at(&self, t: f64) -> (f64, f64) {
+        if t <= 0.0 {
+            return self.start;
+        }
+
+        // the last segment that started at or before t
+        let index = self.segments.partition_point(|s| s.start <= t);
+        let Some(segment) = index.checked_sub(1).map(|i| self.segments[i]) else {
+            return self.start;
+        };
+
+        let elapsed = t - segment.start;
+        if elapsed >= segment.duration {
+            return if index == self.segments.len() {
+                self.goal
+            } else {
+                segment.to
+            };
+        }
+
+        if segment.jump {
+            return segment.from;
+        }
+
+        let fraction = elapsed / segment.duration;
+        (
+            segment.from.0 + (segment.to.0 - segment.from.0) * fraction,
+            segment.from.1 + (segment.to.1 - segment.from.1) * fraction,
+        )
+    }
+}
+
+fn center(p: Point) -> (f64, f64) {
+    (p.col as f64 + 0.5, p.row as f64 + 0.5)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::grid::{Cell, Direction};
+
+    fn path_result(path: &[(usize, usize)], total_cost: usize) -> PathResult<usize, Point> {
+        let path: Vec<Point> = path.iter().map(|&(row, col)| Point { row, col }).collect();
+        PathResult {
+            start: path[0],
+            goal: *path.last().unwrap(),
+            total_cost,
+            path,
+        }
+    }
+
+    #[test]
+    fn test_traversal_positions() {
+        // the second cell is twice as expensive to leave
+        let mut map = GridMap::new(1, 3, 1);
+        map.cells[0][1] = Cell::Valid {
+            cost: 2,
+            entry_cost: None,
+        };
+        let traversal = PathTraversal::new(&map, &path_result(&[(0, 0), (0, 1), (0, 2)], 3), 2.0);
+
+        assert_eq!(traversal.duration(), 1.5);
+        assert_eq!(traversal.position_at(0.0), (0.5, 0.5));
+        assert_eq!(traversal.position_at(0.25), (1.0, 0.5));
+        assert_eq!(traversal.position_at(0.5), (1.5, 0.5));
+        // the slow segment takes twice as long
+        assert_eq!(traversal.position_at(1.0), (2.0, 0.5));
+        assert_eq!(traversal.position_at(10.0), (2.5, 0.5));
+        assert_eq!(traversal.position_at(-1.0), (0.5, 0.5));
+    }
+
+    #[test]
+    fn test_traversal_teleport() {
+        let mut map = GridMap::new(3, 3, 1);
+        map.cells[0][0] = Cell::OneWay {
+            cost: 1,
+            direction: Direction::Right,
+            target: Some(Point { row: 2, col: 2 }),
+            target_cost: None,
+        };
+        let traversal = PathTraversal::new(&map, &path_result(&[(0, 0), (2, 2)], 1), 1.0);
+
+        // no positions in between, the agent jumps to the target
+        assert_eq!(traversal.position_at(0.5), (0.5, 0.5));
+        assert_eq!(traversal.position_at(1.0), (2.5, 2.5));
+    }
+}
@@ -1,21 +1,39 @@
-use image::{DynamicImage, GenericImageView};
+use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
 
-use crate::grid::{Cell, GridMap};
+use crate::color::ColorRamp;
+use crate::find::{MapStorage, Visited};
+use crate::grid::{Cell, GridMap, GridStorage, Point};
 
+/// Creates a map with one cell per pixel, where dark pixels are walls. Same as
+/// [`parse_img_threshold`] with a threshold of 128.
 pub fn parse_img(img: &DynamicImage) -> Result<GridMap<usize>, anyhow::Error> {
+    parse_img_threshold(img, 128, false)
+}
+
+/// Creates a map with one cell per pixel. Pixels where the first channel is below `threshold`
+/// become walls (invalid cells) and all others valid cells with a cost of 1. With `invert` it is
+/// the other way around, i.e. for light walls on a dark background.
+pub fn parse_img_threshold(
+    img: &DynamicImage,
+    threshold: u8,
+    invert: bool,
+) -> Result<GridMap<usize>, anyhow::Error> {
     let width = img.width() as usize;
     let height = img.height() as usize;
 
-    let mut cells = vec![vec![Cell::Invalid; width as usize]; height as usize];
+    let mut cells = vec![vec![Cell::Invalid; width]; height];
 
-    for row in 0..height {
-        for col in 0..width {
+    for (row, row_cells) in cells.iter_mut().enumerate() {
+        for (col, cell) in row_cells.iter_mut().enumerate() {
             let p = img.get_pixel(col as u32, row as u32);
 
-            cells[row][col] = if p.0[0] < 128 {
+            *cell = if (p.0[0] < threshold) != invert {
                 Cell::Invalid
             } else {
-                Cell::Valid { cost: 1 }
+                Cell::Valid {
+                    cost: 1,
+                    entry_cost: None,
+                }
             }
         }
     }
@@ -24,5 +42,394 @@ pub fn parse_img(img: &DynamicImage) -> Result<GridMap<usize>, anyhow::Error> {
         rows: height,
         columns: width,
         cells,
+        meta: Default::default(),
+        topology: Default::default(),
+        tags: Default::default(),
     })
 }
+
+/// Fills a map based on the pixels of an image and a selected color. Each cell is sampled on a
+/// grid of `samples` x `samples` points spread over its footprint in the image, and matches if the
+/// majority of the samples are within `tolerance` of `color`. Samples outside of the image never
+/// match. Matching cells become valid if `matching_is_valid` is set, otherwise they become
+/// invalid and all other cells valid.
+pub fn fill_map_from_image<U>(
+    map: &mut GridMap<usize, U>,
+    image: &DynamicImage,
+    image_scale: f64,
+    color: &Rgba<u8>,
+    tolerance: f64,
+    samples: usize,
+    matching_is_valid: bool,
+) {
+    let samples = samples.max(1);
+    let (width, height) = image.dimensions();
+
+    for (row, cells) in map.cells.iter_mut().enumerate() {
+        for (col, cell) in cells.iter_mut().enumerate() {
+            let mut matching = 0;
+            for i in 0..samples {
+                for j in 0..samples {
+                    // spread the samples evenly, a single sample is at the center of the cell
+                    let y = row as f64 + (i as f64 + 0.5) / samples as f64;
+                    let x = col as f64 + (j as f64 + 0.5) / samples as f64;
+                    let (x, y) = ((x / image_scale) as u32, (y / image_scale) as u32);
+
+                    if x < width
+                        && y < height
+                        && pixel_difference_norm(&image.get_pixel(x, y), color) < tolerance
+                    {
+                        matching += 1;
+                    }
+                }
+            }
+
+            let matches = 2 * matching > samples * samples;
+            *cell = if matches == matching_is_valid {
+                Cell::Valid {
+                    cost: 1,
+                    entry_cost: None,
+                }
+            } else {
+                Cell::Invalid
+            };
+        }
+    }
+}
+
+/// The euclidean distance between the rgb components of two colors
+pub fn pixel_difference_norm(a: &Rgba<u8>, b: &Rgba<u8>) -> f64 {
+    let a = a.0;
+    let b = b.0;
+    let diff = [
+        (a[0] as f64 - b[0] as f64).abs(),
+        (a[1] as f64 - b[1] as f64).abs(),
+        (a[2] as f64 - b[2] as f64).abs(),
+    ];
+    (diff[0].powi(2) + diff[1].powi(2) + diff[2].powi(2)).sqrt()
+}
+
+/// Rasterizes an SVG image with the given DPI (96 gives one pixel per SVG user unit) on a white
+/// background. SVGs without explicit width and height use the size of their `viewBox`.
+#[cfg(feature = "svg")]
+pub fn rasterize_svg(bytes: &[u8], dpi: f32) -> Result<DynamicImage, anyhow::Error> {
+    use resvg::{tiny_skia, usvg};
+
+    let options = usvg::Options {
+        dpi,
+        ..Default::default()
+    };
+    let tree = usvg::Tree::from_data(bytes, &options)?;
+
+    let scale = dpi / 96.0;
+    let size = tree.size();
+    let (width, height) = (
+        (size.width() * scale).ceil() as u32,
+        (size.height() * scale).ceil() as u32,
+    );
+
+    let mut pixmap = tiny_skia::Pixmap::new(width, height)
+        .ok_or_else(|| anyhow::anyhow!("Invalid SVG size: {}x{}", width, height))?;
+    pixmap.fill(tiny_skia::Color::WHITE);
+    resvg::render(
+        &tree,
+        tiny_skia::Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+
+    let img = image::RgbaImage::from_raw(width, height, pixmap.take())
+        .ok_or_else(|| anyhow::anyhow!("Could not convert rasterized SVG"))?;
+    Ok(DynamicImage::ImageRgba8(img))
+}
+
+/// Rasterizes an SVG image using [`rasterize_svg`] and parses the result using [`parse_img`]
+#[cfg(feature = "svg")]
+pub fn parse_svg(bytes: &[u8], dpi: f32) -> Result<GridMap<usize>, anyhow::Error> {
+    parse_img(&rasterize_svg(bytes, dpi)?)
+}
+
+/// Renders the map together with the visited cells of a search into an image with one pixel
+/// per cell. Visited cells are drawn in red, brighter the higher their cost is.
+pub fn render_visited<U>(
+    map: &GridMap<usize, U>,
+    visited: &GridStorage<Visited<usize, Point>>,
+) -> RgbaImage {
+    let ramp = ColorRamp::default();
+
+    let max_cost = (0..map.rows)
+        .flat_map(|row| (0..map.columns).map(move |col| Point { row, col }))
+        .filter_map(|p| visited.get(p).map(|v| v.cost))
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    RgbaImage::from_fn(map.columns as u32, map.rows as u32, |x, y| {
+        let p = Point {
+            row: y as usize,
+            col: x as usize,
+        };
+        if let Some(v) = *visited.get(p) {
+            let intensity = 64 + (v.cost * 191 / max_cost) as u8;
+            return Rgba([intensity, 0, 0, 255]);
+        }
+        match map.cells[p.row][p.col] {
+            Cell::Invalid => Rgba([0, 0, 0, 255]),
+            Cell::Valid { cost, .. } => ramp.color_for(cost),
+            Cell::OneWay { target: None, .. } => Rgba([0, 255, 255, 255]),
+            Cell::OneWay {
+                target: Some(_), ..
+            } => Rgba([255, 0, 255, 255]),
+        }
+    })
+}
+
+/// Renders one image per frame of visited cells, e.g. snapshots of the storage taken after each
+/// step of the pathfinder. The frames can then be encoded into an animation (such as a GIF).
+pub fn render_search_animation<U>(
+    map: &GridMap<usize, U>,
+    frames: &[&GridStorage<Visited<usize, Point>>],
+) -> Vec<RgbaImage> {
+    frames
+        .iter()
+        .map(|visited| render_visited(map, visited))
+        .collect()
+}
+
+/// A small seedable pseudo random number generator (xorshift64*). Used wherever reproducible
+/// randomness is needed, e.g. for generating test maps, so results only depend on the seed.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // scramble the seed with splitmix64 so that similar seeds give different sequences and
+        // the state never ends up as zero (which xorshift cannot recover from)
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        Self(if z == 0 { 0x9E37_79B9_7F4A_7C15 } else { z })
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a value in the range `0..n`
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Returns a value in the range `[0, 1)`
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns true with the given probability
+    pub fn chance(&mut self, probability: f64) -> bool {
+        self.next_f64() < probability
+    }
+}
+
+/// A 64 bit FNV-1a hasher. Unlike the hasher of the standard library the output is stable
+/// across runs, Rust versions and platforms, since all integers are hashed as little endian and
+/// `usize`/`isize` as 64 bits.
+#[derive(Debug, Clone)]
+pub struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl std::hash::Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_u64(i as i64 as u64);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::find::{MapTrait, PathFinder};
+    use crate::grid::test::create_basic_map;
+
+    #[test]
+    fn test_parse_img_threshold() {
+        // a dark, a near threshold and a light pixel
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(3, 1, |x, _| {
+            let v = [20, 120, 240][x as usize];
+            Rgba([v, v, v, 255])
+        }));
+        let walls = |map: GridMap<usize>| -> Vec<bool> {
+            map.cells[0].iter().map(|c| *c == Cell::Invalid).collect()
+        };
+
+        assert_eq!(walls(parse_img(&img).unwrap()), [true, true, false]);
+        assert_eq!(
+            walls(parse_img_threshold(&img, 100, false).unwrap()),
+            [true, false, false]
+        );
+        assert_eq!(
+            walls(parse_img_threshold(&img, 200, false).unwrap()),
+            [true, true, false]
+        );
+
+        // light walls on a dark background
+        assert_eq!(
+            walls(parse_img_threshold(&img, 100, true).unwrap()),
+            [false, true, true]
+        );
+        assert_eq!(
+            walls(parse_img_threshold(&img, 128, true).unwrap()),
+            [false, false, true]
+        );
+    }
+
+    #[cfg(feature = "svg")]
+    #[test]
+    fn test_parse_svg() {
+        let svg = br##"<svg xmlns="http://www.w3.org/2000/svg" width="4" height="2">
+            <rect x="0" y="0" width="4" height="2" fill="#ffffff"/>
+            <rect x="0" y="0" width="2" height="2" fill="#000000"/>
+        </svg>"##;
+        let map = parse_svg(svg, 96.0).unwrap();
+        assert_eq!((map.rows, map.columns), (2, 4));
+        for row in &map.cells {
+            assert_eq!(row[0], Cell::Invalid);
+            assert_eq!(row[1], Cell::Invalid);
+            assert!(matches!(row[2], Cell::Valid { cost: 1, .. }));
+            assert!(matches!(row[3], Cell::Valid { cost: 1, .. }));
+        }
+
+        // without width and height, but at double resolution
+        let svg = br##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 4 2">
+            <rect x="0" y="0" width="2" height="2" fill="#000000"/>
+        </svg>"##;
+        let map = parse_svg(svg, 192.0).unwrap();
+        assert_eq!((map.rows, map.columns), (4, 8));
+        assert_eq!(map.cells[3][3], Cell::Invalid);
+        assert!(matches!(map.cells[3][4], Cell::Valid { cost: 1, .. }));
+    }
+
+    #[test]
+    fn test_fill_map_from_image() {
+        let (white, black) = (Rgba([255, 255, 255, 255]), Rgba([0, 0, 0, 255]));
+
+        // two cells of 3x3 pixels: the first is black with a white center pixel, the second is
+        // white with a black center pixel
+        let img = RgbaImage::from_fn(6, 3, |x, y| {
+            let center = (x % 3, y) == (1, 1);
+            match (x < 3, center) {
+                (true, false) | (false, true) => black,
+                _ => white,
+            }
+        });
+        let img = DynamicImage::ImageRgba8(img);
+
+        let mut map = GridMap::new(1, 2, 1);
+        // sampling only the center gets both cells wrong
+        fill_map_from_image(&mut map, &img, 1.0 / 3.0, &white, 10.0, 1, true);
+        assert!(matches!(map.cells[0][0], Cell::Valid { .. }));
+        assert_eq!(map.cells[0][1], Cell::Invalid);
+
+        fill_map_from_image(&mut map, &img, 1.0 / 3.0, &white, 10.0, 3, true);
+        assert_eq!(map.cells[0][0], Cell::Invalid);
+        assert!(matches!(map.cells[0][1], Cell::Valid { .. }));
+    }
+
+    #[test]
+    fn test_fill_map_tolerance() {
+        // a light gray cell next to a white cell
+        let img = RgbaImage::from_fn(2, 1, |x, _| match x {
+            0 => Rgba([255, 255, 255, 255]),
+            _ => Rgba([230, 230, 230, 255]),
+        });
+        let img = DynamicImage::ImageRgba8(img);
+        let white = Rgba([255, 255, 255, 255]);
+
+        let mut map = GridMap::new(1, 2, 1);
+        fill_map_from_image(&mut map, &img, 1.0, &white, 10.0, 1, true);
+        assert!(matches!(map.cells[0][0], Cell::Valid { .. }));
+        assert_eq!(map.cells[0][1], Cell::Invalid);
+
+        fill_map_from_image(&mut map, &img, 1.0, &white, 50.0, 1, true);
+        assert!(matches!(map.cells[0][0], Cell::Valid { .. }));
+        assert!(matches!(map.cells[0][1], Cell::Valid { .. }));
+
+        // matching the color marks walls instead
+        fill_map_from_image(&mut map, &img, 1.0, &white, 10.0, 1, false);
+        assert_eq!(map.cells[0][0], Cell::Invalid);
+        assert!(matches!(map.cells[0][1], Cell::Valid { .. }));
+    }
+
+    #[test]
+    fn test_stable_hasher() {
+        use std::hash::Hasher;
+
+        // reference value of FNV-1a for "a"
+        let mut hasher = StableHasher::default();
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn test_render_search_animation() {
+        let map = create_basic_map();
+        let mut finder = PathFinder::new(
+            Point { row: 1, col: 1 },
+            Point { row: 1, col: 5 },
+            map.create_storage(),
+            (),
+        );
+
+        let mut snapshots = Vec::new();
+        for _ in 0..5 {
+            finder.step(&map);
+            snapshots.push(finder.get_visited().clone());
+        }
+        let frames: Vec<_> = snapshots.iter().collect();
+        let images = render_search_animation(&map, &frames);
+
+        assert_eq!(images.len(), 5);
+        for image in &images {
+            assert_eq!(image.dimensions(), (7, 7));
+        }
+
+        // the start is visited from the first frame, walls are black
+        assert_eq!(images[0].get_pixel(1, 1).0[1..], [0, 0, 255]);
+        assert_eq!(images[0].get_pixel(0, 0), &Rgba([0, 0, 0, 255]));
+        assert_eq!(images[0].get_pixel(5, 1), &Rgba([255, 255, 255, 255]));
+    }
+}
@@ -0,0 +1,30 @@
+use std::process::Command;
+
+fn maze() -> String {
+    format!(
+        "{}/../data/maze-03_6_threshold.png",
+        env!("CARGO_MANIFEST_DIR")
+    )
+}
+
+#[test]
+fn test_cli_prints_cost() {
+    let output = Command::new(env!("CARGO_BIN_EXE_optmize"))
+        .args(["--input", &maze(), "--start", "14,0", "--goal", "44,51"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "Total cost: 265\n");
+}
+
+#[test]
+fn test_cli_no_path() {
+    let output = Command::new(env!("CARGO_BIN_EXE_optmize"))
+        .args(["-i", &maze(), "-s", "14,0", "-g", "0,0"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No path found"));
+}